@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use crate::config::policy::{FormatOptions, KeywordCase};
+use crate::engine::timestamp;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -6,6 +8,10 @@ use std::path::PathBuf;
 pub struct Cli {
     #[command(subcommand)]
     pub command: Command,
+
+    /// Policy file overriding heuristic thresholds (default: nearest sharp-policy.yaml)
+    #[arg(long, global = true)]
+    pub policy: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -13,9 +19,256 @@ pub enum Command {
     Init,
     Generate {
         schema: PathBuf,
+
+        /// Write the schema objects as numbered up/down migrations here instead of printing
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+
+        /// File naming and layout of the migration tool reading --out-dir
+        #[arg(long, value_enum, default_value_t = MigrationLayout::Plain, requires = "out_dir")]
+        layout: MigrationLayout,
+
+        #[command(flatten)]
+        format: FormatArgs,
+    },
+
+    /// Create the generated tables and views on a server over HTTP.
+    /// Credentials come from CLICKHOUSE_USER and CLICKHOUSE_PASSWORD.
+    Apply {
+        schema: PathBuf,
+
+        /// HTTP interface; add ?database=name to target a database
+        #[arg(long, default_value = "http://localhost:8123")]
+        url: String,
+
+        /// Print the statements instead of running them
+        #[arg(long)]
+        dry_run: bool,
+
+        #[command(flatten)]
+        format: FormatArgs,
+    },
+
+    /// Compare the table on a server with the plan; exits 1 when they differ
+    Drift {
+        schema: PathBuf,
+
+        #[arg(long, default_value = "http://localhost:8123")]
+        url: String,
+
+        /// Print the statements that bring the server in line instead
+        #[arg(long)]
+        migration: bool,
+    },
+
+    /// Report parts, compression, mutations and replication for a live table
+    Health {
+        #[arg(long, required_unless_present = "snapshot")]
+        table: Option<String>,
+
+        #[arg(long, default_value = "http://localhost:8123")]
+        url: String,
+
+        /// Read a snapshot saved earlier instead of querying a server
+        #[arg(long, conflicts_with = "save_snapshot")]
+        snapshot: Option<PathBuf>,
+
+        /// Write the captured system table rows to this file
+        #[arg(long)]
+        save_snapshot: Option<PathBuf>,
     },
 
     Explain {
         schema: PathBuf,
+
+        /// markdown and html render a full design document for reviews
+        #[arg(long, value_enum, default_value_t = ExplainFormat::Text)]
+        format: ExplainFormat,
+
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+
+    /// Print canonical analytical queries written for the generated schema
+    Queries {
+        schema: PathBuf,
+    },
+
+    /// Generate seeded synthetic events matching the generated table
+    Fake {
+        schema: PathBuf,
+
+        /// Days of events, at expected_events_per_day each
+        #[arg(long, default_value_t = 1)]
+        days: u32,
+
+        /// Output file (default: stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Default: from the --out extension (.csv, .tsv), else JSONEachRow
+        #[arg(long, value_enum)]
+        format: Option<FakeFormat>,
+
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+
+        /// First day of data, YYYY-MM-DD in UTC (default: --days ago)
+        #[arg(long, value_parser = parse_date)]
+        start: Option<i64>,
+    },
+
+    /// Check whether a SELECT uses the partition key, sort key and projections
+    CheckQuery {
+        schema: PathBuf,
+        query: PathBuf,
     },
+
+    /// Draft a schema from a sample of events, one JSON object per line
+    Infer {
+        sample: PathBuf,
+
+        /// Name for the events table in the draft
+        #[arg(long, default_value = "events")]
+        name: String,
+    },
+
+    /// Derive query_patterns from an exported system.query_log (TSVWithNames or JSONEachRow)
+    InferPatterns {
+        query_log: PathBuf,
+
+        /// Events table the queries are matched against
+        #[arg(long)]
+        table: String,
+    },
+
+    /// Run every heuristic as a check, for CI
+    Lint {
+        schema: PathBuf,
+
+        #[arg(long, value_enum, default_value_t = LintFormat::Text)]
+        format: LintFormat,
+
+        /// Fail on findings of this severity as well as errors
+        #[arg(long, value_enum)]
+        deny: Option<DenyLevel>,
+    },
+}
+
+// Each flag overrides the policy file's `format` section; the booleans take
+// an optional value, e.g. --semicolons=false.
+#[derive(Args)]
+pub struct FormatArgs {
+    /// Spaces per indentation level
+    #[arg(long)]
+    pub indent: Option<usize>,
+
+    #[arg(long, value_enum)]
+    pub keyword_case: Option<KeywordCase>,
+
+    /// Pad column names so their types line up
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub align_types: Option<bool>,
+
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub semicolons: Option<bool>,
+
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub if_not_exists: Option<bool>,
+
+    /// With --out-dir, one migration per statement instead of a single schema migration
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub one_statement_per_file: Option<bool>,
+}
+
+impl FormatArgs {
+    pub fn resolve(&self, policy: &FormatOptions) -> FormatOptions {
+        FormatOptions {
+            indent: self.indent.unwrap_or(policy.indent),
+            keyword_case: self.keyword_case.unwrap_or(policy.keyword_case),
+            align_types: self.align_types.unwrap_or(policy.align_types),
+            semicolons: self.semicolons.unwrap_or(policy.semicolons),
+            if_not_exists: self.if_not_exists.unwrap_or(policy.if_not_exists),
+            one_statement_per_file: self
+                .one_statement_per_file
+                .unwrap_or(policy.one_statement_per_file),
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExplainFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum MigrationLayout {
+    Plain,
+    Goose,
+    GolangMigrate,
+    Flyway,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LintFormat {
+    Text,
+    Sarif,
+    Junit,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum FakeFormat {
+    JsonEachRow,
+    Csv,
+    Tsv,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum DenyLevel {
+    Warnings,
+}
+
+// YYYY-MM-DD to Unix seconds at midnight UTC.
+fn parse_date(value: &str) -> Result<i64, String> {
+    let parts: Vec<&str> = value.split('-').collect();
+    let [y, m, d] = parts[..] else {
+        return Err(format!("expected YYYY-MM-DD, got '{value}'"));
+    };
+    let number = |s: &str| {
+        s.parse::<u32>()
+            .map_err(|_| format!("expected YYYY-MM-DD, got '{value}'"))
+    };
+    let (y, m, d) = (number(y)?, number(m)?, number(d)?);
+    // An out-of-range month or day rolls over into a neighbouring date, so it
+    // doesn't survive the round trip.
+    let days = timestamp::days_from_civil(i64::from(y), m, d);
+    if timestamp::civil_from_days(days) != (i64::from(y), m, d) {
+        return Err(format!("'{value}' is not a valid date"));
+    }
+    Ok(days * 86_400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dates_within_the_month() {
+        assert_eq!(parse_date("1970-01-02"), Ok(86_400));
+        assert!(parse_date("2024-02-29").is_ok());
+        let bad_dates = [
+            "2023-02-29",
+            "2024-02-31",
+            "2024-04-31",
+            "2024-13-01",
+            "2024-01-00",
+        ];
+        for bad in bad_dates {
+            assert_eq!(parse_date(bad), Err(format!("'{bad}' is not a valid date")));
+        }
+        assert!(parse_date("2024-02").is_err());
+    }
 }
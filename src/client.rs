@@ -0,0 +1,287 @@
+use crate::errors::SharpError;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+// ClickHouse error codes for a bad user or password.
+const AUTH_ERROR_CODES: &[u32] = &[192, 193, 194, 516];
+
+// Plain-HTTP client for the ClickHouse HTTP interface (port 8123 by default),
+// enough to send DDL one statement at a time.
+#[derive(Debug)]
+pub struct Client {
+    host: String,
+    port: u16,
+    path: String,
+    user: Option<String>,
+    password: Option<String>,
+}
+
+impl Client {
+    pub fn new(url: &str) -> Result<Self, SharpError> {
+        let invalid = |reason: &str| SharpError::InvalidUrl(format!("{url}: {reason}"));
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            if url.starts_with("https://") {
+                invalid("https is not supported; use a local or tunnelled http endpoint")
+            } else {
+                invalid("expected http://host:port")
+            }
+        })?;
+        let (authority, path) = match rest.find('/') {
+            Some(at) => (&rest[..at], &rest[at..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid("bad port"))?),
+            None => (authority, 8123),
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            user: None,
+            password: None,
+        })
+    }
+
+    // Both go into request headers verbatim, so a line break would let the
+    // value smuggle in headers of its own.
+    pub fn with_credentials(
+        mut self,
+        user: Option<String>,
+        password: Option<String>,
+    ) -> Result<Self, SharpError> {
+        for (name, value) in [
+            ("CLICKHOUSE_USER", &user),
+            ("CLICKHOUSE_PASSWORD", &password),
+        ] {
+            if value.as_deref().is_some_and(|v| v.contains(['\r', '\n'])) {
+                return Err(SharpError::InvalidCredentials(format!(
+                    "{name} contains a line break"
+                )));
+            }
+        }
+        self.user = user;
+        self.password = password;
+        Ok(self)
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    fn connect(&self) -> std::io::Result<TcpStream> {
+        let addr = self
+            .address()
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no address"))?;
+        let stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        Ok(stream)
+    }
+
+    // HTTP/1.0 so the server closes the connection after one response and
+    // never answers with a chunked body.
+    pub fn execute(&self, sql: &str) -> Result<String, SharpError> {
+        let connection_error = |e| SharpError::ConnectionError(self.address(), e);
+        let mut stream = self.connect().map_err(connection_error)?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\n",
+            self.path,
+            self.host,
+            sql.len()
+        );
+        if let Some(user) = &self.user {
+            request.push_str(&format!("X-ClickHouse-User: {user}\r\n"));
+        }
+        if let Some(password) = &self.password {
+            request.push_str(&format!("X-ClickHouse-Key: {password}\r\n"));
+        }
+        request.push_str("\r\n");
+        request.push_str(sql);
+        stream
+            .write_all(request.as_bytes())
+            .map_err(connection_error)?;
+
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .map_err(connection_error)?;
+        parse_response(&response)
+    }
+}
+
+// "Code: 60. DB::Exception: Table default.x does not exist. (UNKNOWN_TABLE)"
+fn error_code(body: &str) -> Option<u32> {
+    let rest = body.trim_start().strip_prefix("Code: ")?;
+    rest[..rest.find('.')?].parse().ok()
+}
+
+fn parse_response(response: &str) -> Result<String, SharpError> {
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((response, ""));
+    let status: u16 = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| SharpError::ServerError {
+            code: None,
+            message: "malformed HTTP response".to_string(),
+        })?;
+    if status == 200 {
+        return Ok(body.to_string());
+    }
+
+    let header_code = head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("X-ClickHouse-Exception-Code")
+            .then(|| value.trim().parse().ok())
+            .flatten()
+    });
+    let code = header_code.or_else(|| error_code(body));
+    let message = match body.trim() {
+        "" => format!("HTTP {status}"),
+        text => text.to_string(),
+    };
+    if code.is_some_and(|c| AUTH_ERROR_CODES.contains(&c)) {
+        return Err(SharpError::AuthError(message));
+    }
+    Err(SharpError::ServerError { code, message })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    // Answers each connection with the next canned response and hands the
+    // raw requests back to the test.
+    fn mock_server(responses: Vec<&'static str>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 4096];
+                // Read the head, then exactly Content-Length bytes of body.
+                loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length: usize = text
+                            .lines()
+                            .find_map(|l| l.strip_prefix("Content-Length: "))
+                            .map_or(0, |v| v.trim().parse().unwrap());
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                }
+                tx.send(String::from_utf8(request).unwrap()).unwrap();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, rx)
+    }
+
+    #[test]
+    fn parses_urls() {
+        let client = Client::new("http://ch.internal:9000/?database=analytics").unwrap();
+        assert_eq!(client.address(), "ch.internal:9000");
+        assert_eq!(client.path, "/?database=analytics");
+        assert_eq!(Client::new("http://localhost").unwrap().port, 8123);
+        assert!(matches!(
+            Client::new("https://x:8443"),
+            Err(SharpError::InvalidUrl(_))
+        ));
+        assert!(Client::new("localhost:8123").is_err());
+    }
+
+    #[test]
+    fn posts_statement_with_credentials() {
+        let (url, requests) = mock_server(vec!["HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"]);
+        let client = Client::new(&url)
+            .unwrap()
+            .with_credentials(Some("admin".to_string()), Some("secret".to_string()))
+            .unwrap();
+        client
+            .execute("CREATE TABLE t (x UInt8) ENGINE = Memory;")
+            .unwrap();
+
+        let request = requests.recv().unwrap();
+        assert!(request.starts_with("POST / HTTP/1.0\r\n"));
+        assert!(request.contains("X-ClickHouse-User: admin\r\n"));
+        assert!(request.contains("X-ClickHouse-Key: secret\r\n"));
+        assert!(request.ends_with("\r\n\r\nCREATE TABLE t (x UInt8) ENGINE = Memory;"));
+    }
+
+    #[test]
+    fn rejects_line_breaks_in_credentials() {
+        let client = Client::new("http://localhost:8123").unwrap();
+        let err = client
+            .with_credentials(
+                Some("admin".to_string()),
+                Some("secret\r\nX-ClickHouse-Database: system".to_string()),
+            )
+            .unwrap_err();
+        assert!(matches!(err, SharpError::InvalidCredentials(_)));
+        assert!(err.to_string().contains("CLICKHOUSE_PASSWORD"));
+    }
+
+    #[test]
+    fn maps_server_errors() {
+        let (url, _requests) = mock_server(vec![
+            "HTTP/1.1 500 Internal Server Error\r\nX-ClickHouse-Exception-Code: 62\r\n\r\n\
+             Code: 62. DB::Exception: Syntax error. (SYNTAX_ERROR)\n",
+            "HTTP/1.1 403 Forbidden\r\n\r\n\
+             Code: 516. DB::Exception: default: Authentication failed. (AUTHENTICATION_FAILED)\n",
+        ]);
+        let client = Client::new(&url).unwrap();
+
+        match client.execute("CREATE TABLE").unwrap_err() {
+            SharpError::ServerError { code, message } => {
+                assert_eq!(code, Some(62));
+                assert!(message.contains("SYNTAX_ERROR"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert!(matches!(
+            client.execute("SELECT 1").unwrap_err(),
+            SharpError::AuthError(_)
+        ));
+    }
+
+    #[test]
+    fn reports_unreachable_servers() {
+        // Bind then drop to get a port nothing listens on.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let client = Client::new(&format!("http://127.0.0.1:{port}")).unwrap();
+        let err = client.execute("SELECT 1").unwrap_err();
+        assert!(matches!(err, SharpError::ConnectionError(..)));
+        assert!(err.to_string().contains(&format!("127.0.0.1:{port}")));
+    }
+
+    #[test]
+    fn reads_code_from_body() {
+        assert_eq!(
+            error_code("Code: 57. DB::Exception: Table already exists"),
+            Some(57)
+        );
+        assert_eq!(error_code("oops"), None);
+    }
+}
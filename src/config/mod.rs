@@ -1,18 +1,162 @@
+pub mod policy;
 pub mod schema;
 pub mod workload;
 
 use crate::errors::SharpError;
+use crate::sql::ast::{Identifier, TableName};
 use schema::EventSchema;
 use std::path::Path;
 
 pub fn load_schema(path: &Path) -> Result<EventSchema, SharpError> {
-    let contents = std::fs::read_to_string(path)
-        .map_err(|e| SharpError::IoError(path.to_path_buf(), e))?;
+    let contents = read_schema_source(path)?;
+    parse_schema(&contents)
+}
+
+pub fn read_schema_source(path: &Path) -> Result<String, SharpError> {
+    std::fs::read_to_string(path).map_err(|e| SharpError::IoError(path.to_path_buf(), e))
+}
+
+pub fn parse_schema(contents: &str) -> Result<EventSchema, SharpError> {
     let schema: EventSchema =
-        serde_yaml::from_str(&contents).map_err(SharpError::YamlError)?;
+        serde_yaml::from_str(contents).map_err(SharpError::YamlError)?;
+    validate(&schema)?;
     Ok(schema)
 }
 
+fn validate(schema: &EventSchema) -> Result<(), SharpError> {
+    TableName::parse_event_table(&schema.event_table.name)?;
+    for index in &schema.event_table.skip_indexes {
+        Identifier::parse(&index.name)
+            .map_err(|e| SharpError::InvalidSchema(format!("skip index name {e}")))?;
+    }
+    let ts = &schema.event_table.timestamp;
+    if ts.precision > 9 {
+        return Err(SharpError::InvalidSchema(format!(
+            "timestamp.precision must be between 0 and 9, got {}",
+            ts.precision
+        )));
+    }
+    // The timezone is embedded in a quoted SQL literal.
+    if let Some(tz) = &ts.timezone
+        && (tz.is_empty() || tz.contains(['\'', '\\']))
+    {
+        return Err(SharpError::InvalidSchema(format!(
+            "timestamp.timezone '{tz}' is not a valid timezone name"
+        )));
+    }
+    if let Some(profile) = &schema.data_profile {
+        if let Some(bad) = profile
+            .properties
+            .iter()
+            .find(|p| !(0.0..=1.0).contains(&p.presence))
+        {
+            return Err(SharpError::InvalidSchema(format!(
+                "data_profile property '{}' has presence {}, expected 0 to 1",
+                bad.name, bad.presence
+            )));
+        }
+        if let Some((name, _)) = profile.event_names.iter().find(|(_, w)| **w < 0.0) {
+            return Err(SharpError::InvalidSchema(format!(
+                "data_profile event '{name}' has a negative weight"
+            )));
+        }
+    }
+    if let Some(ingestion) = &schema.ingestion {
+        let settings = [
+            ("producers", ingestion.producers.map(u64::from)),
+            ("insert_interval_ms", ingestion.insert_interval_ms),
+            ("rows_per_insert", ingestion.rows_per_insert),
+        ];
+        if let Some((key, _)) = settings.iter().find(|(_, value)| *value == Some(0)) {
+            return Err(SharpError::InvalidSchema(format!(
+                "ingestion.{key} must be at least 1"
+            )));
+        }
+    }
+    if let Some(kafka) = schema.ingestion.as_ref().and_then(|i| i.kafka.as_ref()) {
+        if kafka.brokers.is_empty() || kafka.brokers.iter().any(|b| b.trim().is_empty()) {
+            return Err(SharpError::InvalidSchema(
+                "ingestion.kafka.brokers needs at least one non-empty broker address".to_string(),
+            ));
+        }
+        for (key, value) in [
+            ("topic", &kafka.topic),
+            ("consumer_group", &kafka.consumer_group),
+        ] {
+            if value.trim().is_empty() {
+                return Err(SharpError::InvalidSchema(format!(
+                    "ingestion.kafka.{key} must not be empty"
+                )));
+            }
+        }
+        if kafka.num_consumers == Some(0) {
+            return Err(SharpError::InvalidSchema(
+                "ingestion.kafka.num_consumers must be at least 1".to_string(),
+            ));
+        }
+    }
+    if schema
+        .sessions
+        .as_ref()
+        .is_some_and(|s| s.events_per_session == 0)
+    {
+        return Err(SharpError::InvalidSchema(
+            "sessions.events_per_session must be at least 1".to_string(),
+        ));
+    }
+    for dictionary in &schema.dictionaries {
+        let invalid = |e: SharpError| {
+            SharpError::InvalidSchema(format!("dictionary '{}': {e}", dictionary.name))
+        };
+        TableName::parse(&dictionary.name).map_err(invalid)?;
+        if let Some(source) = &dictionary.source_table {
+            TableName::parse(source).map_err(invalid)?;
+        }
+        let columns = dictionary
+            .key
+            .iter()
+            .chain(&dictionary.attributes)
+            .map(|c| c.name.as_str())
+            .chain(
+                dictionary
+                    .range
+                    .iter()
+                    .flat_map(|r| [r.min.as_str(), r.max.as_str()]),
+            );
+        for column in columns {
+            Identifier::parse(column).map_err(invalid)?;
+        }
+        if dictionary.key.is_empty() || dictionary.attributes.is_empty() {
+            return Err(SharpError::InvalidSchema(format!(
+                "dictionary '{}' needs at least one key and one attribute",
+                dictionary.name
+            )));
+        }
+        let types = dictionary
+            .key
+            .iter()
+            .chain(&dictionary.attributes)
+            .map(|c| &c.data_type)
+            .chain(dictionary.range.as_ref().map(|r| &r.data_type));
+        for data_type in types {
+            data_type.validate().map_err(|e| {
+                SharpError::InvalidSchema(format!("dictionary '{}': {e}", dictionary.name))
+            })?;
+        }
+    }
+    let known: Vec<&str> = crate::engine::heuristics::registry()
+        .iter()
+        .map(|r| r.id())
+        .collect();
+    if let Some(unknown) = schema.rules.keys().find(|id| !known.contains(&id.as_str())) {
+        return Err(SharpError::InvalidSchema(format!(
+            "unknown rule '{unknown}' in `rules` (known: {})",
+            known.join(", ")
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,6 +178,77 @@ mod tests {
         assert_eq!(schema.event_table.retention_days, 30);
     }
 
+    #[test]
+    fn rejects_out_of_range_presence() {
+        let err = parse_schema(
+            "event_table:\n  name: e\n  expected_events_per_day: 1\n  retention_days: 1\n\
+             data_profile:\n  properties:\n    - {name: plan, type: string, cardinality: 3, presence: 1.5}\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("presence 1.5"));
+    }
+
+    #[test]
+    fn rejects_invalid_table_names() {
+        let err = parse_schema(
+            "event_table:\n  name: a.b.c\n  expected_events_per_day: 1\n  retention_days: 1\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("expected table or database.table"));
+        assert!(
+            parse_schema(
+                "event_table:\n  name: analytics.events\n  expected_events_per_day: 1\n  \
+                 retention_days: 1\n"
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_object_names() {
+        let base = "event_table:\n  name: e\n  expected_events_per_day: 1\n  retention_days: 1\n";
+        let dictionary = |name: &str, key: &str| {
+            format!(
+                "{base}dictionaries:\n  - name: \"{name}\"\n    key: [{{name: \"{key}\", type: UInt64}}]\n    \
+                 attributes: [{{name: plan, type: String}}]\n    rows: 10\n"
+            )
+        };
+        assert!(parse_schema(&dictionary("analytics.plans", "id")).is_ok());
+        let err = parse_schema(&dictionary("x.y.z", "id")).unwrap_err();
+        assert!(err.to_string().contains("dictionary 'x.y.z'"));
+        let err = parse_schema(&dictionary("plans", "")).unwrap_err();
+        assert!(err.to_string().contains("must not be empty"));
+
+        let err = parse_schema(&format!(
+            "{base}  skip_indexes:\n    - {{name: '', expression: x, type: minmax, granularity: 1}}\n"
+        ))
+        .unwrap_err();
+        assert!(err.to_string().contains("skip index name"));
+
+        let err = parse_schema(&base.replace("name: e", &format!("name: {}", "e".repeat(240))))
+            .unwrap_err();
+        assert!(err.to_string().contains("companion tables"));
+    }
+
+    #[test]
+    fn checks_dictionary_types() {
+        let yaml = |data_type: &str| {
+            format!(
+                "event_table:\n  name: e\n  expected_events_per_day: 1\n  retention_days: 1\n\
+                 dictionaries:\n  - name: d\n    key: [{{name: id, type: UInt64}}]\n    \
+                 attributes: [{{name: plan, type: \"{data_type}\"}}]\n    rows: 10\n"
+            )
+        };
+        let schema = parse_schema(&yaml("LowCardinality(String)")).unwrap();
+        assert_eq!(
+            schema.dictionaries[0].attributes[0].data_type.to_string(),
+            "LowCardinality(String)"
+        );
+        let err = parse_schema(&yaml("Nullable(LowCardinality(String))")).unwrap_err();
+        assert!(err.to_string().contains("dictionary 'd'"));
+        assert!(parse_schema(&yaml("Strng")).is_err());
+    }
+
     #[test]
     fn returns_io_error_for_missing_file() {
         let result = load_schema(Path::new("/nonexistent/schema.yaml"));
@@ -65,4 +280,124 @@ mod tests {
         let schema = load_schema(tmp.path()).unwrap();
         assert!(!schema.event_table.multi_tenant);
     }
+
+    #[test]
+    fn loads_timestamp_settings() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            tmp,
+            "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\n  timestamp:\n    precision: 3\n    timezone: UTC\n    ingested_at: true"
+        )
+        .unwrap();
+
+        let schema = load_schema(tmp.path()).unwrap();
+        let ts = &schema.event_table.timestamp;
+        assert_eq!(ts.precision, 3);
+        assert_eq!(ts.timezone.as_deref(), Some("UTC"));
+        assert!(ts.ingested_at);
+    }
+
+    #[test]
+    fn rejects_out_of_range_precision() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            tmp,
+            "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\n  timestamp:\n    precision: 12"
+        )
+        .unwrap();
+
+        let err = load_schema(tmp.path()).unwrap_err();
+        assert!(err.to_string().contains("timestamp.precision"));
+    }
+
+    #[test]
+    fn rejects_quoted_timezone() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            tmp,
+            "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\n  timestamp:\n    timezone: \"UTC')\""
+        )
+        .unwrap();
+
+        assert!(load_schema(tmp.path()).is_err());
+    }
+
+    #[test]
+    fn loads_kafka_ingestion_with_default_format() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            tmp,
+            "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\ningestion:\n  kafka:\n    brokers: [kafka:9092]\n    topic: events\n    consumer_group: sharp"
+        )
+        .unwrap();
+
+        let schema = load_schema(tmp.path()).unwrap();
+        let kafka = schema.ingestion.unwrap().kafka.unwrap();
+        assert_eq!(kafka.brokers, ["kafka:9092"]);
+        assert_eq!(kafka.format, "JSONEachRow");
+        assert_eq!(kafka.num_consumers, None);
+    }
+
+    #[test]
+    fn rejects_zero_insert_settings() {
+        for key in ["producers", "insert_interval_ms", "rows_per_insert"] {
+            let err = parse_schema(&format!(
+                "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\n\
+                 ingestion:\n  {key}: 0\n"
+            ))
+            .unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains(&format!("ingestion.{key} must be at least 1"))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unusable_kafka_settings() {
+        let yaml = |kafka: &str| {
+            format!(
+                "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\n\
+                 ingestion:\n  kafka:\n    {kafka}\n"
+            )
+        };
+        let valid = "brokers: [k:9092]\n    topic: t\n    consumer_group: g";
+        assert!(parse_schema(&yaml(valid)).is_ok());
+        let cases = [
+            ("[k:9092]", "[]", "brokers"),
+            ("topic: t", "topic: ''", "kafka.topic"),
+            ("group: g", "group: ' '", "kafka.consumer_group"),
+            ("p: g", "p: g\n    num_consumers: 0", "num_consumers"),
+        ];
+        for (from, to, message) in cases {
+            let err = parse_schema(&yaml(&valid.replace(from, to))).unwrap_err();
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
+
+    #[test]
+    fn loads_rule_switches() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            tmp,
+            "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\nrules:\n  sharding: false"
+        )
+        .unwrap();
+
+        let schema = load_schema(tmp.path()).unwrap();
+        assert_eq!(schema.rules.get("sharding"), Some(&false));
+    }
+
+    #[test]
+    fn rejects_unknown_rule_id() {
+        let mut tmp = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            tmp,
+            "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\nrules:\n  shardnig: false"
+        )
+        .unwrap();
+
+        let err = load_schema(tmp.path()).unwrap_err();
+        assert!(err.to_string().contains("unknown rule 'shardnig'"));
+    }
 }
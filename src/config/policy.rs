@@ -0,0 +1,298 @@
+use crate::errors::SharpError;
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+pub const POLICY_FILE_NAME: &str = "sharp-policy.yaml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default)]
+    pub thresholds: Thresholds,
+    #[serde(default)]
+    pub format: FormatOptions,
+    // Where the policy came from, for explain output. None means built-in defaults.
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+}
+
+// All values are events/day.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    pub monthly_partition_below: u64,
+    pub tenant_partition_above: u64,
+    pub high_volume: u64,
+    pub sharding: u64,
+    pub tenant_projection: u64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            monthly_partition_below: 5_000_000,
+            tenant_partition_above: 200_000_000,
+            high_volume: 100_000_000,
+            sharding: 500_000_000,
+            tenant_projection: 50_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordCase {
+    Upper,
+    Lower,
+}
+
+// How generated SQL is laid out. The command line overrides each field.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FormatOptions {
+    // Spaces per indentation level.
+    pub indent: usize,
+    pub keyword_case: KeywordCase,
+    pub align_types: bool,
+    pub semicolons: bool,
+    pub if_not_exists: bool,
+    // Only applies when writing to a directory.
+    pub one_statement_per_file: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            keyword_case: KeywordCase::Upper,
+            align_types: false,
+            semicolons: true,
+            if_not_exists: false,
+            one_statement_per_file: true,
+        }
+    }
+}
+
+impl Policy {
+    // "thresholds.high_volume = 100000000 (default)" — lets explain output show
+    // exactly which number a decision was made against.
+    pub fn cite(&self, key: &str, value: u64) -> String {
+        let source = match self.source {
+            Some(ref path) => path.display().to_string(),
+            None => "default".to_string(),
+        };
+        format!("thresholds.{key} = {value} ({source})")
+    }
+}
+
+// 5_000_000 -> "5M", 250_000 -> "250K"; anything else is printed as-is.
+pub fn human(n: u64) -> String {
+    if n >= 1_000_000 && n.is_multiple_of(1_000_000) {
+        format!("{}M", n / 1_000_000)
+    } else if n >= 1_000 && n.is_multiple_of(1_000) {
+        format!("{}K", n / 1_000)
+    } else {
+        n.to_string()
+    }
+}
+
+pub fn load_policy(path: &Path) -> Result<Policy, SharpError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| SharpError::IoError(path.to_path_buf(), e))?;
+    let mut policy: Policy = serde_yaml::from_str(&contents)
+        .map_err(|e| SharpError::PolicyError(path.to_path_buf(), e))?;
+    // The partition chooser assumes the monthly band sits below the tenant band.
+    let t = &policy.thresholds;
+    if t.monthly_partition_below >= t.tenant_partition_above {
+        return Err(SharpError::InvalidPolicy(
+            path.to_path_buf(),
+            format!(
+                "thresholds.monthly_partition_below ({}) must be below thresholds.tenant_partition_above ({})",
+                t.monthly_partition_below, t.tenant_partition_above
+            ),
+        ));
+    }
+    policy.source = Some(path.to_path_buf());
+    Ok(policy)
+}
+
+// Walks up from `start` looking for sharp-policy.yaml, so one file at the
+// repository root covers every workload below it.
+pub fn discover(start: &Path) -> Option<PathBuf> {
+    let start = start.canonicalize().ok()?;
+    start
+        .ancestors()
+        .map(|dir| dir.join(POLICY_FILE_NAME))
+        .find(|candidate| candidate.is_file())
+}
+
+// An explicit --policy wins; otherwise search from the schema's directory.
+pub fn resolve(explicit: Option<&Path>, schema_path: &Path) -> Result<Policy, SharpError> {
+    if let Some(path) = explicit {
+        return load_policy(path);
+    }
+    let dir = schema_path.parent().unwrap_or(Path::new("."));
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    match discover(dir) {
+        Some(path) => load_policy(&path),
+        None => Ok(Policy::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn defaults_match_documented_thresholds() {
+        let t = Thresholds::default();
+        assert_eq!(t.monthly_partition_below, 5_000_000);
+        assert_eq!(t.tenant_partition_above, 200_000_000);
+        assert_eq!(t.high_volume, 100_000_000);
+        assert_eq!(t.sharding, 500_000_000);
+        assert_eq!(t.tenant_projection, 50_000_000);
+    }
+
+    #[test]
+    fn partial_policy_keeps_other_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(POLICY_FILE_NAME);
+        fs::write(&path, "thresholds:\n  sharding: 1000000000\n").unwrap();
+
+        let policy = load_policy(&path).unwrap();
+        assert_eq!(policy.thresholds.sharding, 1_000_000_000);
+        assert_eq!(policy.thresholds.high_volume, 100_000_000);
+        assert_eq!(policy.source.as_deref(), Some(path.as_path()));
+    }
+
+    #[test]
+    fn reads_format_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(POLICY_FILE_NAME);
+        fs::write(&path, "format:\n  indent: 2\n  keyword_case: lower\n").unwrap();
+
+        let format = load_policy(&path).unwrap().format;
+        assert_eq!(format.indent, 2);
+        assert_eq!(format.keyword_case, KeywordCase::Lower);
+        assert!(format.semicolons);
+    }
+
+    #[test]
+    fn rejects_misspelled_format_option() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(POLICY_FILE_NAME);
+        fs::write(&path, "format: {indnet: 2}\n").unwrap();
+
+        let err = load_policy(&path).unwrap_err();
+        assert!(err.to_string().contains("unknown field `indnet`"));
+    }
+
+    #[test]
+    fn invalid_policy_reports_policy_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(POLICY_FILE_NAME);
+        fs::write(&path, "thresholds:\n  sharding: lots\n").unwrap();
+
+        let err = load_policy(&path).unwrap_err();
+        assert!(err.to_string().starts_with("invalid policy file"));
+    }
+
+    #[test]
+    fn rejects_misspelled_threshold() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(POLICY_FILE_NAME);
+        fs::write(&path, "thresholds:\n  shardng: 1000000000\n").unwrap();
+
+        let err = load_policy(&path).unwrap_err();
+        assert!(err.to_string().contains("unknown field `shardng`"));
+    }
+
+    #[test]
+    fn rejects_misspelled_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(POLICY_FILE_NAME);
+        fs::write(&path, "threshold:\n  high_volume: 1000000000\n").unwrap();
+
+        let err = load_policy(&path).unwrap_err();
+        assert!(err.to_string().contains("unknown field `threshold`"));
+    }
+
+    #[test]
+    fn rejects_overlapping_partition_thresholds() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(POLICY_FILE_NAME);
+        fs::write(&path, "thresholds:\n  monthly_partition_below: 300000000\n").unwrap();
+        let schema = dir.path().join("schema.yaml");
+
+        let err = resolve(None, &schema).unwrap_err();
+        assert!(matches!(err, SharpError::InvalidPolicy(..)));
+        assert!(err.to_string().contains("must be below"));
+    }
+
+    #[test]
+    fn discovers_policy_in_parent_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("teams").join("growth");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.path().join(POLICY_FILE_NAME), "thresholds: {}\n").unwrap();
+
+        let found = discover(&nested).unwrap();
+        assert_eq!(
+            found,
+            dir.path().canonicalize().unwrap().join(POLICY_FILE_NAME)
+        );
+    }
+
+    #[test]
+    fn resolve_prefers_explicit_path() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(POLICY_FILE_NAME),
+            "thresholds:\n  sharding: 1\n",
+        )
+        .unwrap();
+        let explicit = dir.path().join("strict.yaml");
+        fs::write(&explicit, "thresholds:\n  sharding: 2\n").unwrap();
+        let schema = dir.path().join("schema.yaml");
+
+        let policy = resolve(Some(&explicit), &schema).unwrap();
+        assert_eq!(policy.thresholds.sharding, 2);
+    }
+
+    #[test]
+    fn resolve_discovers_from_schema_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(POLICY_FILE_NAME),
+            "thresholds:\n  sharding: 1\n",
+        )
+        .unwrap();
+        let schema = dir.path().join("schema.yaml");
+
+        let policy = resolve(None, &schema).unwrap();
+        assert_eq!(policy.thresholds.sharding, 1);
+        assert!(policy.source.is_some());
+    }
+
+    #[test]
+    fn cite_names_key_value_and_source() {
+        let policy = Policy::default();
+        assert_eq!(
+            policy.cite("high_volume", 100_000_000),
+            "thresholds.high_volume = 100000000 (default)"
+        );
+    }
+
+    #[test]
+    fn human_readable_thresholds() {
+        assert_eq!(human(5_000_000), "5M");
+        assert_eq!(human(250_000), "250K");
+        assert_eq!(human(1_234), "1234");
+    }
+}
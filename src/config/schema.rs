@@ -1,15 +1,249 @@
-use serde::Deserialize;
+use crate::sql::types::ClickHouseType;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct EventSchema {
     pub event_table: EventTable,
+    #[serde(default)]
+    pub compliance: Option<ComplianceConfig>,
+    #[serde(default)]
+    pub ingestion: Option<IngestionConfig>,
+    // Per-rule switches keyed by rule id, e.g. `sharding: false`.
+    #[serde(default)]
+    pub rules: BTreeMap<String, bool>,
+    // Usually written by `sharp infer-patterns` from an exported query_log.
+    #[serde(default)]
+    pub query_patterns: Vec<QueryPattern>,
+    // Shape of the data itself, written by `sharp infer`.
+    #[serde(default)]
+    pub data_profile: Option<DataProfile>,
+    // Adds a session_id column and a sessions rollup fed by a materialized view.
+    #[serde(default)]
+    pub sessions: Option<SessionsConfig>,
+    // Adds person and distinct_id mapping tables for identify/alias merging.
+    #[serde(default)]
+    pub persons: Option<PersonsConfig>,
+    // Small dimension tables loaded into memory for dictGet lookups.
+    #[serde(default)]
+    pub dictionaries: Vec<DictionaryConfig>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct EventTable {
     pub name: String,
     #[serde(default)]
     pub multi_tenant: bool,
     pub expected_events_per_day: u64,
     pub retention_days: u32,
+    #[serde(default)]
+    pub sampling: bool,
+    #[serde(default)]
+    pub timestamp: TimestampConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_indexes: Vec<SkipIndexConfig>,
+}
+
+// A data-skipping index, e.g. `bloom_filter(0.01)` on distinct_id so lookups
+// by user skip granules the sort key can't.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SkipIndexConfig {
+    pub name: String,
+    pub expression: String,
+    #[serde(rename = "type")]
+    pub index_type: String,
+    #[serde(default = "default_index_granularity")]
+    pub granularity: u32,
+}
+
+fn default_index_granularity() -> u32 {
+    4
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TimestampConfig {
+    // Fractional-second digits: 0 keeps plain DateTime, 3 is milliseconds,
+    // 6 is microseconds. ClickHouse accepts up to 9.
+    #[serde(default)]
+    pub precision: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub ingested_at: bool,
+}
+
+// One filter shape seen in the query log: the columns it compares with
+// = / IN and with ranges, and how much of the workload it is.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct QueryPattern {
+    #[serde(default)]
+    pub equality: Vec<String>,
+    #[serde(default)]
+    pub range: Vec<String>,
+    // Fraction of analysed queries, 0.0–1.0.
+    pub share: f64,
+    pub count: u64,
+    pub p95_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DataProfile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avg_row_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenants: Option<u32>,
+    // Zipf exponent of events per tenant: 0 is uniform, ~1 is a few big
+    // tenants and a long tail.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_skew: Option<f64>,
+    // Relative weights; they don't need to sum to 1.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub event_names: BTreeMap<String, f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<PropertyShape>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PropertyShape {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: PropertyType,
+    // Distinct values seen (a lower bound once the sample saturates).
+    pub cardinality: u64,
+    // Fraction of events that carry the property.
+    #[serde(default = "default_presence")]
+    pub presence: f64,
+}
+
+fn default_presence() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PropertyType {
+    String,
+    Int,
+    Float,
+    Bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SessionsConfig {
+    // Sizes the sessions table from events/day.
+    #[serde(default = "default_events_per_session")]
+    pub events_per_session: u32,
+}
+
+impl Default for SessionsConfig {
+    fn default() -> Self {
+        Self {
+            events_per_session: default_events_per_session(),
+        }
+    }
+}
+
+fn default_events_per_session() -> u32 {
+    20
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PersonsConfig {
+    // Decides between FINAL and argMax reads; unknown means assume large.
+    #[serde(default)]
+    pub expected_persons: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DictionaryConfig {
+    pub name: String,
+    // ClickHouse table the dictionary loads from; defaults to `name`.
+    #[serde(default)]
+    pub source_table: Option<String>,
+    // Named like the event columns they match so lookups can use them as is.
+    pub key: Vec<ColumnSpec>,
+    pub attributes: Vec<ColumnSpec>,
+    pub rows: u64,
+    #[serde(default)]
+    pub refresh: Refresh,
+    // Validity interval columns, for attributes that change over time.
+    #[serde(default)]
+    pub range: Option<RangeConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ColumnSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub data_type: ClickHouseType,
+}
+
+// How often the source table changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Refresh {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RangeConfig {
+    pub min: String,
+    pub max: String,
+    #[serde(rename = "type", default = "default_range_type")]
+    pub data_type: ClickHouseType,
+}
+
+fn default_range_type() -> ClickHouseType {
+    ClickHouseType::Date
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeletionStrategy {
+    LightweightDelete,
+    Mutation,
+    Tombstone,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ComplianceConfig {
+    #[serde(default)]
+    pub erasure_requests_per_day: u32,
+    // Overrides the strategy sharp would pick from the request rate.
+    #[serde(default)]
+    pub deletion: Option<DeletionStrategy>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct IngestionConfig {
+    #[serde(default)]
+    pub kafka: Option<KafkaConfig>,
+    // Direct INSERT clients: how many, how often each one flushes, and how
+    // many rows go into a flush (derived from events/day when omitted).
+    #[serde(default)]
+    pub producers: Option<u32>,
+    #[serde(default)]
+    pub insert_interval_ms: Option<u64>,
+    #[serde(default)]
+    pub rows_per_insert: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KafkaConfig {
+    pub brokers: Vec<String>,
+    pub topic: String,
+    pub consumer_group: String,
+    #[serde(default = "default_kafka_format")]
+    pub format: String,
+    // Derived from events/day when omitted.
+    #[serde(default)]
+    pub num_consumers: Option<u32>,
+}
+
+fn default_kafka_format() -> String {
+    "JSONEachRow".to_string()
 }
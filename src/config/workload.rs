@@ -1,10 +1,20 @@
-use crate::config::schema::EventSchema;
+use crate::config::schema::{
+    ComplianceConfig, EventSchema, IngestionConfig, PersonsConfig, QueryPattern, SessionsConfig,
+    TimestampConfig,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct WorkloadProfile {
     pub events_per_day: u64,
     pub multi_tenant: bool,
     pub retention_days: u32,
+    pub sampling: bool,
+    pub timestamp: TimestampConfig,
+    pub compliance: Option<ComplianceConfig>,
+    pub ingestion: Option<IngestionConfig>,
+    pub query_patterns: Vec<QueryPattern>,
+    pub sessions: Option<SessionsConfig>,
+    pub persons: Option<PersonsConfig>,
 }
 
 impl WorkloadProfile {
@@ -13,6 +23,13 @@ impl WorkloadProfile {
             events_per_day: schema.event_table.expected_events_per_day,
             multi_tenant: schema.event_table.multi_tenant,
             retention_days: schema.event_table.retention_days,
+            sampling: schema.event_table.sampling,
+            timestamp: schema.event_table.timestamp.clone(),
+            compliance: schema.compliance.clone(),
+            ingestion: schema.ingestion.clone(),
+            query_patterns: schema.query_patterns.clone(),
+            sessions: schema.sessions.clone(),
+            persons: schema.persons.clone(),
         }
     }
 }
@@ -20,7 +37,7 @@ impl WorkloadProfile {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::schema::{EventSchema, EventTable};
+    use crate::config::schema::{EventSchema, EventTable, TimestampConfig};
 
     #[test]
     fn from_schema_maps_all_fields() {
@@ -30,13 +47,25 @@ mod tests {
                 multi_tenant: true,
                 expected_events_per_day: 42_000_000,
                 retention_days: 60,
+                sampling: true,
+                timestamp: TimestampConfig {
+                    precision: 3,
+                    timezone: Some("UTC".to_string()),
+                    ingested_at: true,
+                },
+                ..Default::default()
             },
+            ..Default::default()
         };
         let workload = WorkloadProfile::from_schema(&schema);
 
         assert_eq!(workload.events_per_day, 42_000_000);
         assert!(workload.multi_tenant);
         assert_eq!(workload.retention_days, 60);
+        assert!(workload.sampling);
+        assert_eq!(workload.timestamp.precision, 3);
+        assert_eq!(workload.timestamp.timezone.as_deref(), Some("UTC"));
+        assert!(workload.timestamp.ingested_at);
     }
 
     #[test]
@@ -47,7 +76,9 @@ mod tests {
                 multi_tenant: false,
                 expected_events_per_day: 1,
                 retention_days: 0,
+                ..Default::default()
             },
+            ..Default::default()
         };
         let workload = WorkloadProfile::from_schema(&schema);
 
@@ -0,0 +1,503 @@
+use crate::config::policy::Policy;
+use crate::config::schema::DeletionStrategy;
+use crate::config::workload::WorkloadProfile;
+use crate::sql::ast::{AlterCommand, ColumnExpr, CreateTable, Identifier, Statement, TableName};
+use crate::sql::types::ClickHouseType;
+
+// Each lightweight DELETE rewrites the `_row_exists` mask of every part that
+// holds a matching row. Past a handful of requests a day it's cheaper to batch
+// them into one mutation, and past ~100 a day even a daily mutation falls
+// behind, so erasures are recorded as tombstones and purged in bulk.
+const BATCHED_MUTATION_REQUESTS: u32 = 10;
+const TOMBSTONE_REQUESTS: u32 = 100;
+
+pub fn explain(strategy: DeletionStrategy, workload: &WorkloadProfile, policy: &Policy) -> String {
+    let Some(compliance) = &workload.compliance else {
+        return String::new();
+    };
+    let requests_per_day = compliance.erasure_requests_per_day;
+    if compliance.deletion.is_some() {
+        let chosen = choose_deletion_strategy(workload, requests_per_day, policy);
+        let verdict = if chosen == strategy {
+            "the same as sharp picks".to_string()
+        } else {
+            format!("overriding {chosen:?}, which sharp picks")
+        };
+        return format!(
+            "{strategy:?} set by compliance.deletion, {verdict} for {requests_per_day} \
+             erasure requests/day on {} events/day.",
+            workload.events_per_day
+        );
+    }
+    match strategy {
+        DeletionStrategy::LightweightDelete => format!(
+            "Lightweight DELETE selected: {requests_per_day} erasure requests/day on \
+             {} events/day is low enough to mask rows per request; rows are physically \
+             removed on the next merge or by `APPLY DELETED MASK`.",
+            workload.events_per_day
+        ),
+        DeletionStrategy::Mutation => format!(
+            "Batched ALTER ... DELETE selected: {requests_per_day} erasure requests/day on \
+             {} events/day would rewrite the same parts over and over as individual deletes, \
+             so requests are collected and applied as one mutation per day.",
+            workload.events_per_day
+        ),
+        DeletionStrategy::Tombstone => format!(
+            "Tombstone table selected: {requests_per_day} erasure requests/day exceeds what \
+             mutations can keep up with, so erased ids go into a ReplacingMergeTree table, \
+             reads filter through a view, and a periodic mutation purges the raw rows."
+        ),
+    }
+}
+
+pub fn choose_deletion_strategy(
+    workload: &WorkloadProfile,
+    requests_per_day: u32,
+    policy: &Policy,
+) -> DeletionStrategy {
+    if requests_per_day > TOMBSTONE_REQUESTS {
+        DeletionStrategy::Tombstone
+    } else if requests_per_day > BATCHED_MUTATION_REQUESTS
+        || workload.events_per_day >= policy.thresholds.high_volume
+    {
+        DeletionStrategy::Mutation
+    } else {
+        DeletionStrategy::LightweightDelete
+    }
+}
+
+pub fn deletion_strategy(workload: &WorkloadProfile, policy: &Policy) -> Option<DeletionStrategy> {
+    let compliance = workload.compliance.as_ref()?;
+    Some(compliance.deletion.unwrap_or_else(|| {
+        choose_deletion_strategy(workload, compliance.erasure_requests_per_day, policy)
+    }))
+}
+
+fn key_columns(workload: &WorkloadProfile) -> Vec<&'static str> {
+    if workload.multi_tenant {
+        vec!["project_id", "distinct_id"]
+    } else {
+        vec!["distinct_id"]
+    }
+}
+
+fn match_single(workload: &WorkloadProfile) -> String {
+    let mut preds = vec!["distinct_id = {distinct_id:String}"];
+    if workload.multi_tenant {
+        preds.insert(0, "project_id = {project_id:UInt32}");
+    }
+    preds.join(" AND ")
+}
+
+fn erasure_table(table_name: &TableName) -> TableName {
+    table_name.with_suffix("_erasures")
+}
+
+pub fn tombstone_table(table_name: &TableName, workload: &WorkloadProfile) -> CreateTable {
+    let mut columns: Vec<ColumnExpr> = key_columns(workload)
+        .into_iter()
+        .map(|name| ColumnExpr {
+            name: Identifier::new(name),
+            data_type: if name == "project_id" {
+                ClickHouseType::UInt(32)
+            } else {
+                ClickHouseType::String
+            },
+            default: None,
+        })
+        .collect();
+    columns.push(ColumnExpr {
+        name: Identifier::new("requested_at"),
+        data_type: ClickHouseType::DateTime(None),
+        default: Some("now()".to_string()),
+    });
+
+    CreateTable {
+        table_name: erasure_table(table_name),
+        columns,
+        engine: "ReplacingMergeTree(requested_at)".to_string(),
+        partition_by: None,
+        order_by: key_columns(workload)
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        sample_by: None,
+        ttl: None,
+        settings: vec![],
+    }
+}
+
+// Lightweight DELETE of one subject's rows.
+pub fn delete_subject(table_name: &TableName, workload: &WorkloadProfile) -> Statement {
+    Statement::Delete {
+        table: table_name.clone(),
+        condition: match_single(workload),
+    }
+}
+
+// One mutation for the day's batch of erasures, passed as an array parameter.
+pub fn delete_batch(table_name: &TableName, workload: &WorkloadProfile) -> Statement {
+    let keys = key_columns(workload).join(", ");
+    let key_type = if workload.multi_tenant {
+        "Array(Tuple(UInt32, String))"
+    } else {
+        "Array(String)"
+    };
+    Statement::alter(
+        table_name,
+        AlterCommand::Delete(format!("({keys}) IN {{erasures:{key_type}}}")),
+    )
+}
+
+pub fn live_view(table_name: &TableName, workload: &WorkloadProfile) -> Statement {
+    let keys = key_columns(workload).join(", ");
+    Statement::CreateView {
+        name: table_name.with_suffix("_live"),
+        select: format!(
+            "SELECT * FROM {table_name}\nWHERE ({keys}) NOT IN (SELECT {keys} FROM {})",
+            erasure_table(table_name)
+        ),
+    }
+}
+
+pub fn purge_tombstoned(table_name: &TableName, workload: &WorkloadProfile) -> Statement {
+    let keys = key_columns(workload).join(", ");
+    Statement::alter(
+        table_name,
+        AlterCommand::Delete(format!(
+            "({keys}) IN (SELECT {keys} FROM {})",
+            erasure_table(table_name)
+        )),
+    )
+}
+
+pub fn statements(
+    table_name: &TableName,
+    workload: &WorkloadProfile,
+    strategy: DeletionStrategy,
+) -> Vec<Statement> {
+    match strategy {
+        DeletionStrategy::LightweightDelete => vec![delete_subject(table_name, workload)],
+        DeletionStrategy::Mutation => vec![delete_batch(table_name, workload)],
+        DeletionStrategy::Tombstone => vec![
+            Statement::CreateTable(tombstone_table(table_name, workload)),
+            live_view(table_name, workload),
+            purge_tombstoned(table_name, workload),
+        ],
+    }
+}
+
+pub fn runbook(
+    table_name: &TableName,
+    workload: &WorkloadProfile,
+    strategy: DeletionStrategy,
+) -> Vec<(String, String)> {
+    let pred = match_single(workload);
+    let mut steps = vec![(
+        "Count rows held for the subject".to_string(),
+        format!("SELECT count() FROM {table_name} WHERE {pred};"),
+    )];
+
+    match strategy {
+        DeletionStrategy::LightweightDelete => {
+            steps.push((
+                "Mask the subject's rows".to_string(),
+                delete_subject(table_name, workload).to_sql(),
+            ));
+            steps.push((
+                "Physically remove masked rows without waiting for merges".to_string(),
+                Statement::Alter {
+                    table: table_name.clone(),
+                    command: AlterCommand::ApplyDeletedMask,
+                }
+                .to_sql(),
+            ));
+        }
+        DeletionStrategy::Mutation => {
+            steps.push((
+                "Apply the day's batch of erasures".to_string(),
+                delete_batch(table_name, workload).to_sql(),
+            ));
+        }
+        DeletionStrategy::Tombstone => {
+            let values = if workload.multi_tenant {
+                "({project_id:UInt32}, {distinct_id:String})"
+            } else {
+                "({distinct_id:String})"
+            };
+            let keys = key_columns(workload).join(", ");
+            steps.push((
+                "Record the erasure request".to_string(),
+                format!(
+                    "INSERT INTO {} ({keys}) VALUES {values};",
+                    erasure_table(table_name)
+                ),
+            ));
+            steps.push((
+                "Purge tombstoned rows (scheduled)".to_string(),
+                purge_tombstoned(table_name, workload).to_sql(),
+            ));
+        }
+    }
+
+    if strategy != DeletionStrategy::LightweightDelete {
+        steps.push((
+            "Track mutation progress".to_string(),
+            format!(
+                "SELECT mutation_id, command, parts_to_do, is_done, latest_fail_reason\n\
+                 FROM system.mutations\n\
+                 WHERE {} AND NOT is_done;",
+                table_name.system_filter("table")
+            ),
+        ));
+    }
+
+    steps.push((
+        "Verify nothing is left".to_string(),
+        format!("SELECT count() FROM {table_name} WHERE {pred};"),
+    ));
+    steps
+}
+
+// distinct_id sits at the end of the generated sort key, so a delete by user
+// can't use the primary index and has to scan every granule of every part.
+pub fn order_by_warning(order_by: &[String]) -> Option<String> {
+    let pos = order_by.iter().position(|c| c.contains("distinct_id"))?;
+    let leading = order_by.iter().take_while(|c| *c == "project_id").count();
+    if pos <= leading {
+        return None;
+    }
+    Some(format!(
+        "Warning: distinct_id is column {} of ({}), so erasures by distinct_id can't use \
+         the primary index and every delete touches all parts of the table.",
+        pos + 1,
+        order_by.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::ComplianceConfig;
+
+    fn profile(events_per_day: u64, multi_tenant: bool) -> WorkloadProfile {
+        WorkloadProfile {
+            events_per_day,
+            multi_tenant,
+            retention_days: 90,
+            ..Default::default()
+        }
+    }
+
+    fn with_compliance(requests: u32, deletion: Option<DeletionStrategy>) -> WorkloadProfile {
+        WorkloadProfile {
+            compliance: Some(ComplianceConfig {
+                erasure_requests_per_day: requests,
+                deletion,
+            }),
+            ..profile(1_000_000, false)
+        }
+    }
+
+    fn cols(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn few_requests_use_lightweight_delete() {
+        let w = profile(1_000_000, false);
+        assert_eq!(
+            choose_deletion_strategy(&w, BATCHED_MUTATION_REQUESTS, &Policy::default()),
+            DeletionStrategy::LightweightDelete
+        );
+    }
+
+    #[test]
+    fn moderate_requests_use_batched_mutation() {
+        let w = profile(1_000_000, false);
+        assert_eq!(
+            choose_deletion_strategy(&w, BATCHED_MUTATION_REQUESTS + 1, &Policy::default()),
+            DeletionStrategy::Mutation
+        );
+    }
+
+    #[test]
+    fn high_volume_table_uses_batched_mutation() {
+        let w = profile(Policy::default().thresholds.high_volume, false);
+        assert_eq!(
+            choose_deletion_strategy(&w, 1, &Policy::default()),
+            DeletionStrategy::Mutation
+        );
+    }
+
+    #[test]
+    fn many_requests_use_tombstones() {
+        let w = profile(1_000_000, false);
+        assert_eq!(
+            choose_deletion_strategy(&w, TOMBSTONE_REQUESTS + 1, &Policy::default()),
+            DeletionStrategy::Tombstone
+        );
+    }
+
+    #[test]
+    fn no_strategy_without_compliance_section() {
+        assert_eq!(
+            deletion_strategy(&profile(1_000_000, false), &Policy::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn strategy_derived_from_request_rate() {
+        assert_eq!(
+            deletion_strategy(&with_compliance(500, None), &Policy::default()),
+            Some(DeletionStrategy::Tombstone)
+        );
+    }
+
+    #[test]
+    fn configured_strategy_overrides_heuristic() {
+        assert_eq!(
+            deletion_strategy(
+                &with_compliance(500, Some(DeletionStrategy::Mutation)),
+                &Policy::default()
+            ),
+            Some(DeletionStrategy::Mutation)
+        );
+    }
+
+    #[test]
+    fn lightweight_statement_scoped_to_tenant() {
+        let w = profile(1_000_000, true);
+        let stmts = statements(
+            &TableName::new("events"),
+            &w,
+            DeletionStrategy::LightweightDelete,
+        );
+        assert_eq!(
+            stmts[0].to_sql(),
+            "DELETE FROM events WHERE project_id = {project_id:UInt32} AND distinct_id = {distinct_id:String};"
+        );
+    }
+
+    #[test]
+    fn mutation_statement_takes_batch() {
+        let w = profile(1_000_000, false);
+        let stmts = statements(&TableName::new("events"), &w, DeletionStrategy::Mutation);
+        assert_eq!(
+            stmts,
+            [Statement::Alter {
+                table: TableName::new("events"),
+                command: AlterCommand::Delete(
+                    "(distinct_id) IN {erasures:Array(String)}".to_string()
+                ),
+            }]
+        );
+        assert_eq!(
+            stmts[0].to_sql(),
+            "ALTER TABLE events DELETE WHERE (distinct_id) IN {erasures:Array(String)};"
+        );
+    }
+
+    #[test]
+    fn tombstone_generates_table_view_and_purge() {
+        let w = profile(1_000_000, true);
+        let stmts: Vec<String> =
+            statements(&TableName::new("events"), &w, DeletionStrategy::Tombstone)
+                .iter()
+                .map(Statement::to_sql)
+                .collect();
+        assert_eq!(stmts.len(), 3);
+        assert!(stmts[0].starts_with("CREATE TABLE events_erasures ("));
+        assert!(stmts[0].contains("ENGINE = ReplacingMergeTree(requested_at)"));
+        assert!(stmts[0].contains("ORDER BY (project_id, distinct_id)"));
+        assert!(stmts[1].starts_with("CREATE VIEW events_live AS"));
+        assert!(stmts[2].contains("IN (SELECT project_id, distinct_id FROM events_erasures)"));
+    }
+
+    #[test]
+    fn lightweight_runbook_applies_mask() {
+        let w = profile(1_000_000, false);
+        let steps = runbook(
+            &TableName::new("events"),
+            &w,
+            DeletionStrategy::LightweightDelete,
+        );
+        assert!(
+            steps
+                .iter()
+                .any(|(_, sql)| sql.contains("APPLY DELETED MASK"))
+        );
+        assert!(
+            !steps
+                .iter()
+                .any(|(_, sql)| sql.contains("system.mutations"))
+        );
+        assert!(steps.last().unwrap().1.starts_with("SELECT count()"));
+    }
+
+    #[test]
+    fn mutation_runbook_tracks_progress() {
+        let w = profile(1_000_000, false);
+        let steps = runbook(&TableName::new("events"), &w, DeletionStrategy::Mutation);
+        assert!(
+            steps
+                .iter()
+                .any(|(_, sql)| sql.contains("system.mutations"))
+        );
+        assert!(
+            steps
+                .iter()
+                .any(|(_, sql)| sql.contains("table = 'events'"))
+        );
+    }
+
+    #[test]
+    fn tombstone_runbook_records_request() {
+        let w = profile(1_000_000, false);
+        let steps = runbook(&TableName::new("events"), &w, DeletionStrategy::Tombstone);
+        assert!(
+            steps
+                .iter()
+                .any(|(_, sql)| sql.starts_with("INSERT INTO events_erasures"))
+        );
+    }
+
+    #[test]
+    fn warns_when_distinct_id_trails_key() {
+        let warning = order_by_warning(&cols(&["event_name", "timestamp", "distinct_id"])).unwrap();
+        assert!(warning.contains("column 3"));
+    }
+
+    #[test]
+    fn warns_for_hashed_distinct_id() {
+        let order = cols(&["project_id", "event_name", "cityHash64(distinct_id)"]);
+        assert!(order_by_warning(&order).is_some());
+    }
+
+    #[test]
+    fn no_warning_when_distinct_id_leads() {
+        assert_eq!(
+            order_by_warning(&cols(&["project_id", "distinct_id", "timestamp"])),
+            None
+        );
+        assert_eq!(order_by_warning(&cols(&["distinct_id", "timestamp"])), None);
+    }
+
+    #[test]
+    fn explain_mentions_request_rate() {
+        let w = with_compliance(500, None);
+        let explanation = explain(DeletionStrategy::Tombstone, &w, &Policy::default());
+        assert!(explanation.starts_with("Tombstone table selected: 500 erasure requests/day"));
+    }
+
+    #[test]
+    fn explain_reports_an_override() {
+        let w = with_compliance(5, Some(DeletionStrategy::Tombstone));
+        assert_eq!(
+            explain(DeletionStrategy::Tombstone, &w, &Policy::default()),
+            "Tombstone set by compliance.deletion, overriding LightweightDelete, which sharp \
+             picks for 5 erasure requests/day on 1000000 events/day."
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::config::policy::Policy;
+use crate::config::schema::{DeletionStrategy, EventSchema};
+use crate::config::workload::WorkloadProfile;
+use crate::engine::partitioning::choose_partition_strategy;
+use crate::engine::{
+    compliance, dictionaries, indexes, kafka, ordering, persons, projections, sampling, sessions,
+    ttl,
+};
+use crate::errors::SharpError;
+use crate::sql::ast::{CreateTable, Statement, TableName};
+use crate::sql::builder::build_create_table_sql;
+
+pub fn create_table(
+    schema: &EventSchema,
+    workload: &WorkloadProfile,
+    policy: &Policy,
+) -> CreateTable {
+    let partition = choose_partition_strategy(workload, policy);
+    let order_cols = ordering::choose_order_by(workload, policy);
+    build_create_table_sql(
+        schema,
+        &partition.to_sql(),
+        &order_cols,
+        sampling::suggest(workload),
+        ttl::suggest(workload),
+    )
+}
+
+// Every table `generate` emits, checked before any DDL goes out so a bad type
+// combination fails here rather than half way through a migration.
+pub fn validate(
+    schema: &EventSchema,
+    workload: &WorkloadProfile,
+    policy: &Policy,
+) -> Result<(), SharpError> {
+    let ast = create_table(schema, workload, policy);
+    let mut tables = Vec::new();
+    if let Some(kafka) = workload.ingestion.as_ref().and_then(|i| i.kafka.as_ref()) {
+        tables.push(kafka::kafka_table(&ast, kafka, workload));
+    }
+    if let Some(config) = &workload.sessions {
+        tables.push(sessions::sessions_table(&ast, workload, config, policy));
+    }
+    if workload.persons.is_some() {
+        tables.push(persons::persons_table(&ast.table_name, workload));
+        tables.push(persons::distinct_id_table(&ast.table_name, workload));
+    }
+    if compliance::deletion_strategy(workload, policy) == Some(DeletionStrategy::Tombstone) {
+        tables.push(compliance::tombstone_table(&ast.table_name, workload));
+    }
+    ast.validate()?;
+    tables.iter().try_for_each(CreateTable::validate)
+}
+
+// The schema objects `generate` emits, in dependency order: the table, its
+// projection and indexes, then anything that reads from or writes to it.
+pub fn statements(
+    schema: &EventSchema,
+    workload: &WorkloadProfile,
+    policy: &Policy,
+) -> Vec<Statement> {
+    let ast = create_table(schema, workload, policy);
+    let table = &ast.table_name;
+
+    let mut statements = vec![
+        Statement::CreateTable(ast.clone()),
+        projections::daily_event_projection(table),
+    ];
+    statements.extend(
+        schema
+            .event_table
+            .skip_indexes
+            .iter()
+            .map(|index| indexes::add_index(table, index)),
+    );
+    if let Some(kafka) = workload.ingestion.as_ref().and_then(|i| i.kafka.as_ref()) {
+        statements.push(Statement::CreateTable(kafka::kafka_table(
+            &ast, kafka, workload,
+        )));
+        statements.push(kafka::materialized_view(&ast));
+    }
+    if let Some(config) = &workload.sessions {
+        let table = sessions::sessions_table(&ast, workload, config, policy);
+        let view = sessions::materialized_view(&ast, &table);
+        statements.push(Statement::CreateTable(table));
+        statements.push(view);
+    }
+    if workload.persons.is_some() {
+        statements.push(Statement::CreateTable(persons::persons_table(
+            table, workload,
+        )));
+        statements.push(Statement::CreateTable(persons::distinct_id_table(
+            table, workload,
+        )));
+    }
+    statements.extend(
+        schema
+            .dictionaries
+            .iter()
+            .map(|d| dictionaries::create_dictionary(table, d)),
+    );
+    statements
+}
+
+// Only the CREATE statements of the erasure strategy define schema; the rest
+// delete data and belong in the runbook.
+pub fn erasure_objects(
+    table: &TableName,
+    workload: &WorkloadProfile,
+    policy: &Policy,
+) -> Vec<Statement> {
+    compliance::deletion_strategy(workload, policy)
+        .map(|strategy| {
+            compliance::statements(table, workload, strategy)
+                .into_iter()
+                .filter(Statement::creates)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// The statement up to its body, for progress output.
+pub fn label(sql: &str) -> String {
+    sql.split_whitespace()
+        .take_while(|w| {
+            !w.starts_with('(')
+                && !matches!(
+                    w.to_ascii_uppercase().as_str(),
+                    "AS" | "TO" | "TYPE" | "ENGINE"
+                )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(';')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_schema;
+
+    fn plan(yaml: &str) -> Vec<String> {
+        let schema = parse_schema(yaml).unwrap();
+        let workload = WorkloadProfile::from_schema(&schema);
+        statements(&schema, &workload, &Policy::default())
+            .iter()
+            .map(Statement::to_sql)
+            .collect()
+    }
+
+    #[test]
+    fn lists_table_then_dependent_objects() {
+        let stmts = plan(
+            "event_table:\n  name: events\n  expected_events_per_day: 1000000\n  retention_days: 30\n  \
+             skip_indexes:\n    - {name: idx_d, expression: distinct_id, type: bloom_filter}\n",
+        );
+        assert_eq!(stmts.len(), 3);
+        assert!(stmts[0].starts_with("CREATE TABLE events ("));
+        assert!(stmts[1].contains("ADD PROJECTION daily_event_counts"));
+        assert!(stmts[2].contains("ADD INDEX idx_d"));
+    }
+
+    #[test]
+    fn generated_tables_are_valid() {
+        let schema = parse_schema(
+            "event_table:\n  name: events\n  multi_tenant: true\n  expected_events_per_day: 1000000\n  \
+             retention_days: 30\n  timestamp: {precision: 3, timezone: UTC}\nsessions: {}\npersons: {}\n",
+        )
+        .unwrap();
+        let workload = WorkloadProfile::from_schema(&schema);
+        assert!(validate(&schema, &workload, &Policy::default()).is_ok());
+    }
+
+    #[test]
+    fn labels_stop_at_the_body() {
+        assert_eq!(
+            label("CREATE TABLE IF NOT EXISTS events (\n    x UInt8\n);"),
+            "CREATE TABLE IF NOT EXISTS events"
+        );
+        assert_eq!(
+            label("ALTER TABLE events\nADD PROJECTION p\n(\n    SELECT 1\n);"),
+            "ALTER TABLE events ADD PROJECTION p"
+        );
+        assert_eq!(
+            label("CREATE MATERIALIZED VIEW mv TO t (a) AS\nSELECT a;"),
+            "CREATE MATERIALIZED VIEW mv"
+        );
+        assert_eq!(
+            label("create table if not exists events (\n  x UInt8\n) engine = MergeTree"),
+            "create table if not exists events"
+        );
+    }
+}
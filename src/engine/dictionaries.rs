@@ -0,0 +1,384 @@
+use crate::config::schema::{DictionaryConfig, Refresh};
+use crate::engine::queries::CanonicalQuery;
+use crate::engine::rules::PlanContext;
+use crate::sql::ast::{
+    ColumnExpr, CreateDictionary, Identifier, Statement, TableName, string_literal,
+};
+use crate::sql::builder::columns;
+use crate::sql::types::ClickHouseType;
+
+// Reloading rebuilds the whole dictionary in memory, so big ones are not
+// reloaded more often than this whatever the refresh rate.
+const LARGE_ROWS: u64 = 1_000_000;
+const LARGE_MIN_LIFETIME: u64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    Hashed,
+    RangeHashed,
+    ComplexKeyHashed,
+    ComplexKeyRangeHashed,
+}
+
+impl Layout {
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            Layout::Hashed => "HASHED()",
+            Layout::RangeHashed => "RANGE_HASHED()",
+            Layout::ComplexKeyHashed => "COMPLEX_KEY_HASHED()",
+            Layout::ComplexKeyRangeHashed => "COMPLEX_KEY_RANGE_HASHED()",
+        }
+    }
+}
+
+// Simple-key layouts only take one UInt64 (or narrower) key; anything else
+// goes through the complex-key variants, which hash a tuple.
+fn simple_key(config: &DictionaryConfig) -> bool {
+    match config.key.as_slice() {
+        [key] => matches!(key.data_type, ClickHouseType::UInt(bits) if bits <= 64),
+        _ => false,
+    }
+}
+
+pub fn choose_layout(config: &DictionaryConfig) -> Layout {
+    match (simple_key(config), config.range.is_some()) {
+        (true, false) => Layout::Hashed,
+        (true, true) => Layout::RangeHashed,
+        (false, false) => Layout::ComplexKeyHashed,
+        (false, true) => Layout::ComplexKeyRangeHashed,
+    }
+}
+
+// LIFETIME(MIN .. MAX ..) in seconds; each replica picks a random reload time
+// in the interval so they don't all hit the source at once. None never
+// reloads.
+pub fn lifetime(config: &DictionaryConfig) -> Option<(u64, u64)> {
+    let (min, max) = match config.refresh {
+        Refresh::Minutely => (30, 60),
+        Refresh::Hourly => (300, 600),
+        Refresh::Daily => (3600, 7200),
+        Refresh::Never => return None,
+    };
+    if config.rows >= LARGE_ROWS {
+        Some((min.max(LARGE_MIN_LIFETIME), max.max(2 * LARGE_MIN_LIFETIME)))
+    } else {
+        Some((min, max))
+    }
+}
+
+fn source_table(config: &DictionaryConfig) -> &str {
+    config.source_table.as_deref().unwrap_or(&config.name)
+}
+
+// Names without a database live next to the event table.
+fn in_table_database(events: &TableName, name: &str) -> TableName {
+    let mut name = TableName::new(name);
+    if name.database.is_none() {
+        name.database = events.database.clone();
+    }
+    name
+}
+
+pub fn dictionary_name(events: &TableName, config: &DictionaryConfig) -> TableName {
+    in_table_database(events, &config.name)
+}
+
+// The SOURCE(...) argument: the source table, in the event table's database
+// unless it names its own.
+fn source(events: &TableName, config: &DictionaryConfig) -> String {
+    let table = in_table_database(events, source_table(config));
+    let db = table
+        .database
+        .map(|db| format!("DB {} ", string_literal(db.as_str())))
+        .unwrap_or_default();
+    format!(
+        "CLICKHOUSE({db}TABLE {})",
+        string_literal(table.name.as_str())
+    )
+}
+
+fn column(name: &str, data_type: &ClickHouseType) -> ColumnExpr {
+    ColumnExpr {
+        name: Identifier::new(name),
+        data_type: data_type.clone(),
+        default: None,
+    }
+}
+
+pub fn create_dictionary(events: &TableName, config: &DictionaryConfig) -> Statement {
+    let mut columns: Vec<ColumnExpr> = config
+        .key
+        .iter()
+        .chain(&config.attributes)
+        .map(|c| column(&c.name, &c.data_type))
+        .collect();
+    if let Some(range) = &config.range {
+        columns.push(column(&range.min, &range.data_type));
+        columns.push(column(&range.max, &range.data_type));
+    }
+    Statement::CreateDictionary(CreateDictionary {
+        name: dictionary_name(events, config),
+        columns,
+        primary_key: config
+            .key
+            .iter()
+            .map(|c| Identifier::new(&c.name))
+            .collect(),
+        source: source(events, config),
+        lifetime: lifetime(config),
+        layout: choose_layout(config).to_sql().to_string(),
+        range: config
+            .range
+            .as_ref()
+            .map(|r| (Identifier::new(&r.min), Identifier::new(&r.max))),
+    })
+}
+
+pub fn explain(config: &DictionaryConfig) -> String {
+    let range = config
+        .range
+        .as_ref()
+        .map(|r| format!(", looked up by {}..{}", r.min, r.max))
+        .unwrap_or_default();
+    let layout = match choose_layout(config) {
+        Layout::Hashed => "HASHED: a single unsigned integer key hashes directly.".to_string(),
+        Layout::RangeHashed => format!(
+            "RANGE_HASHED: a single unsigned integer key whose attributes change over time{range}."
+        ),
+        Layout::ComplexKeyHashed => {
+            "COMPLEX_KEY_HASHED: the key is composite or not an unsigned integer.".to_string()
+        }
+        Layout::ComplexKeyRangeHashed => format!(
+            "COMPLEX_KEY_RANGE_HASHED: a composite or non-integer key with validity ranges{range}."
+        ),
+    };
+    let reload = match (lifetime(config), config.refresh) {
+        (None, _) => "Never reloaded: the source is static; SYSTEM RELOAD DICTIONARY after \
+                      changing it."
+            .to_string(),
+        (Some((min, max)), refresh) => {
+            let large = if config.rows >= LARGE_ROWS {
+                format!(
+                    " (at least {LARGE_MIN_LIFETIME}s at {} rows, since every reload rebuilds it)",
+                    config.rows
+                )
+            } else {
+                String::new()
+            };
+            format!(
+                "Reloaded every {min}–{max}s for {} updates{large}.",
+                format!("{refresh:?}").to_lowercase()
+            )
+        }
+    };
+    format!(
+        "{layout} {reload} Lookups with dictGet read from memory instead of joining {} per query.",
+        source_table(config)
+    )
+}
+
+// dictGet's key argument: the key columns as they appear in the event table,
+// or None when the key isn't on the events.
+fn lookup_key(ctx: &PlanContext, config: &DictionaryConfig) -> Option<String> {
+    let event_columns = columns(ctx.schema);
+    if !config
+        .key
+        .iter()
+        .all(|k| event_columns.iter().any(|c| c.name.as_str() == k.name))
+    {
+        return None;
+    }
+    let names: Vec<&str> = config.key.iter().map(|k| k.name.as_str()).collect();
+    Some(match (names.as_slice(), choose_layout(config)) {
+        ([name], Layout::Hashed | Layout::RangeHashed) => name.to_string(),
+        _ => format!("({})", names.join(", ")),
+    })
+}
+
+// `dictGet('<dictionary>', '<attribute>', <key>)` for the event being read.
+// Range dictionaries take the point in time as a last argument, in the range
+// columns' type.
+fn dict_get(ctx: &PlanContext, config: &DictionaryConfig, attribute: &str) -> Option<String> {
+    let key = lookup_key(ctx, config)?;
+    let at = match &config.range {
+        Some(range) if range.data_type == ClickHouseType::Date => ", toDate(timestamp)",
+        Some(_) => ", timestamp",
+        None => "",
+    };
+    // dictGet takes the name as a string, `database.dictionary` unquoted.
+    let name = dictionary_name(&ctx.table(), config);
+    let name = match &name.database {
+        Some(db) => format!("{}.{}", db.as_str(), name.name.as_str()),
+        None => name.name.as_str().to_string(),
+    };
+    Some(format!(
+        "dictGet({}, '{attribute}', {key}{at})",
+        string_literal(&name)
+    ))
+}
+
+// The first dictionary that can look `attribute` up from an event, with the
+// dictGet call that does it. Example queries use this in place of a JOIN.
+pub fn lookup<'a>(ctx: &'a PlanContext, attribute: &str) -> Option<(&'a DictionaryConfig, String)> {
+    ctx.schema
+        .dictionaries
+        .iter()
+        .filter(|d| d.attributes.iter().any(|a| a.name == attribute))
+        .find_map(|d| Some((d, dict_get(ctx, d, attribute)?)))
+}
+
+// The breakdown one would otherwise write as `JOIN <source> USING (key)`.
+fn breakdown(ctx: &PlanContext, config: &DictionaryConfig) -> Option<CanonicalQuery> {
+    let attribute = &config.attributes[0].name;
+    let lookup = dict_get(ctx, config, attribute)?;
+    let mut conditions = Vec::new();
+    if ctx.workload.multi_tenant {
+        conditions.push("project_id = {project_id:UInt32}");
+    }
+    conditions.push("timestamp >= {from:DateTime}");
+    conditions.push("timestamp < {to:DateTime}");
+    let sql = format!(
+        "SELECT\n    {lookup} AS {attribute},\n    count() AS events\n\
+         FROM {table}\nWHERE {}\nGROUP BY {attribute}\nORDER BY events DESC;",
+        conditions.join("\n  AND "),
+        table = ctx.table()
+    );
+    Some(CanonicalQuery {
+        name: "Events by dimension attribute",
+        sql,
+        uses: vec![format!(
+            "dictionary {} ({}) instead of a JOIN against {}",
+            dictionary_name(&ctx.table(), config),
+            choose_layout(config).to_sql(),
+            source_table(config)
+        )],
+    })
+}
+
+pub fn queries(ctx: &PlanContext) -> Vec<CanonicalQuery> {
+    ctx.schema
+        .dictionaries
+        .iter()
+        .filter_map(|d| breakdown(ctx, d))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_schema;
+    use crate::config::policy::Policy;
+    use crate::config::workload::WorkloadProfile;
+
+    const SCHEMA: &str = "\
+event_table:
+  name: events
+  multi_tenant: true
+  expected_events_per_day: 1000000
+  retention_days: 30
+dictionaries:
+  - name: tenant_plans
+    source_table: tenants
+    key: [{name: project_id, type: UInt64}]
+    attributes: [{name: plan, type: String}]
+    rows: 5000
+    refresh: hourly
+  - name: event_definitions
+    key: [{name: project_id, type: UInt64}, {name: event_name, type: String}]
+    attributes: [{name: category, type: String}]
+    rows: 2000000
+    refresh: minutely
+  - name: plan_history
+    key: [{name: project_id, type: UInt64}]
+    attributes: [{name: plan, type: String}]
+    rows: 100
+    refresh: never
+    range: {min: valid_from, max: valid_to}
+";
+
+    #[test]
+    fn layout_and_lifetime_follow_key_size_and_refresh() {
+        let schema = parse_schema(SCHEMA).unwrap();
+        let [plans, definitions, history] = schema.dictionaries.as_slice() else {
+            panic!("expected three dictionaries");
+        };
+        assert_eq!(choose_layout(plans), Layout::Hashed);
+        assert_eq!(choose_layout(definitions), Layout::ComplexKeyHashed);
+        assert_eq!(choose_layout(history), Layout::RangeHashed);
+        assert_eq!(lifetime(plans), Some((300, 600)));
+        assert_eq!(lifetime(definitions), Some((600, 1200)));
+        assert_eq!(lifetime(history), None);
+    }
+
+    #[test]
+    fn renders_create_dictionary() {
+        let schema = parse_schema(SCHEMA).unwrap();
+        let events = TableName::new("events");
+        assert_eq!(
+            create_dictionary(&events, &schema.dictionaries[0]).to_sql(),
+            "CREATE DICTIONARY tenant_plans (\n    project_id UInt64,\n    plan String\n)\n\
+             PRIMARY KEY project_id\nSOURCE(CLICKHOUSE(TABLE 'tenants'))\n\
+             LIFETIME(MIN 300 MAX 600)\nLAYOUT(HASHED());"
+        );
+        let history = create_dictionary(&events, &schema.dictionaries[2]).to_sql();
+        assert!(history.contains("    valid_to Date\n)"));
+        assert!(
+            history.ends_with(
+                "LIFETIME(0)\nLAYOUT(RANGE_HASHED())\nRANGE(MIN valid_from MAX valid_to);"
+            )
+        );
+    }
+
+    #[test]
+    fn qualifies_names_with_the_event_database() {
+        let schema =
+            parse_schema(&SCHEMA.replace("name: events", "name: analytics.events")).unwrap();
+        let events = TableName::new(&schema.event_table.name);
+        let sql = create_dictionary(&events, &schema.dictionaries[0]).to_sql();
+        assert!(sql.starts_with("CREATE DICTIONARY analytics.tenant_plans ("));
+        assert!(sql.contains("SOURCE(CLICKHOUSE(DB 'analytics' TABLE 'tenants'))"));
+
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        assert!(
+            queries(&ctx)[0]
+                .sql
+                .contains("dictGet('analytics.tenant_plans', 'plan', project_id)")
+        );
+    }
+
+    #[test]
+    fn wide_integer_keys_use_complex_layouts() {
+        let mut schema = parse_schema(SCHEMA).unwrap();
+        let tenants = &mut schema.dictionaries[0];
+        tenants.key[0].data_type = ClickHouseType::UInt(128);
+        assert_eq!(choose_layout(tenants), Layout::ComplexKeyHashed);
+        tenants.key[0].data_type = ClickHouseType::UInt(64);
+        assert_eq!(choose_layout(tenants), Layout::Hashed);
+    }
+
+    #[test]
+    fn breakdowns_use_dictget() {
+        let schema = parse_schema(SCHEMA).unwrap();
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let queries = queries(&ctx);
+
+        assert!(
+            queries[0]
+                .sql
+                .contains("dictGet('tenant_plans', 'plan', project_id) AS plan")
+        );
+        assert!(queries[1].sql.contains(
+            "dictGet('event_definitions', 'category', (project_id, event_name)) AS category"
+        ));
+        assert!(
+            queries[2]
+                .sql
+                .contains("project_id, toDate(timestamp)) AS plan")
+        );
+        assert!(queries[0].uses[0].contains("instead of a JOIN against tenants"));
+    }
+}
@@ -0,0 +1,659 @@
+use crate::config::schema::SkipIndexConfig;
+use crate::engine::{indexes, projections};
+use crate::errors::SharpError;
+use crate::sql::ast::{AlterCommand, ColumnExpr, CreateTable, Identifier, Statement, TableName};
+use crate::sql::types::ClickHouseType;
+use serde_json::Value;
+
+// What a server reports about one table.
+#[derive(Debug)]
+pub struct LiveTable {
+    pub table: CreateTable,
+    pub indexes: Vec<SkipIndexConfig>,
+    pub projections: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Fix {
+    Sql(String),
+    // Would drop something the plan doesn't know about; printed commented out.
+    Review(String),
+    // Sort and partition keys (and the engine) are fixed at CREATE time. Holds
+    // the INSERT that copies the rows into the rebuilt table.
+    Rebuild(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Difference {
+    pub object: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    pub fix: Fix,
+}
+
+pub fn tables_query(table: &TableName) -> String {
+    format!(
+        "SELECT engine, engine_full, partition_key, sorting_key, sampling_key FROM system.tables \
+         WHERE {} FORMAT JSONEachRow",
+        table.system_filter("name")
+    )
+}
+
+pub fn columns_query(table: &TableName) -> String {
+    format!(
+        "SELECT name, type, default_kind, default_expression FROM system.columns \
+         WHERE {} ORDER BY position FORMAT JSONEachRow",
+        table.system_filter("table")
+    )
+}
+
+pub fn indexes_query(table: &TableName) -> String {
+    format!(
+        "SELECT name, expr, type_full, granularity FROM system.data_skipping_indices \
+         WHERE {} FORMAT JSONEachRow",
+        table.system_filter("table")
+    )
+}
+
+pub fn projections_query(table: &TableName) -> String {
+    format!(
+        "SELECT name FROM system.projections WHERE {} FORMAT JSONEachRow",
+        table.system_filter("table")
+    )
+}
+
+fn rows(body: &str) -> Result<Vec<Value>, SharpError> {
+    body.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            serde_json::from_str(l).map_err(|e| SharpError::ServerError {
+                code: None,
+                message: format!("unexpected response: {e}"),
+            })
+        })
+        .collect()
+}
+
+fn text(row: &Value, key: &str) -> String {
+    row[key].as_str().unwrap_or_default().to_string()
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+// Splits on commas outside parentheses and quotes.
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut in_quote = false;
+    let mut current = String::new();
+    for c in list.chars() {
+        match c {
+            '\'' => in_quote = !in_quote,
+            '(' if !in_quote => depth += 1,
+            ')' if !in_quote => depth -= 1,
+            ',' if !in_quote && depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        parts.push(current.trim().to_string());
+    }
+    parts
+}
+
+// engine_full is the whole ENGINE clause; system.tables has no TTL column.
+fn ttl_of(engine_full: &str) -> Option<String> {
+    let (_, rest) = engine_full.split_once(" TTL ")?;
+    let ttl = rest.split(" SETTINGS ").next().unwrap_or(rest);
+    Some(ttl.trim().to_string())
+}
+
+fn live_column(row: &Value) -> Result<ColumnExpr, SharpError> {
+    let name = text(row, "name");
+    let data_type = ClickHouseType::parse(&text(row, "type"))
+        .map_err(|e| SharpError::InvalidType(format!("of live column {name}: {e}")))?;
+    let default = (text(row, "default_kind") == "DEFAULT").then(|| text(row, "default_expression"));
+    Ok(ColumnExpr {
+        name: Identifier::new(name),
+        data_type,
+        default,
+    })
+}
+
+// `run` sends one query and returns the response body, so tests can replay
+// recorded responses. Ok(None) when the table doesn't exist.
+pub fn introspect(
+    run: impl Fn(&str) -> Result<String, SharpError>,
+    table: &TableName,
+) -> Result<Option<LiveTable>, SharpError> {
+    let Some(meta) = rows(&run(&tables_query(table))?)?.into_iter().next() else {
+        return Ok(None);
+    };
+    let columns = rows(&run(&columns_query(table))?)?
+        .iter()
+        .map(live_column)
+        .collect::<Result<_, _>>()?;
+    let indexes = rows(&run(&indexes_query(table))?)?
+        .iter()
+        .map(|row| SkipIndexConfig {
+            name: text(row, "name"),
+            expression: text(row, "expr"),
+            index_type: text(row, "type_full"),
+            // 64-bit integers are quoted in JSON output by default.
+            granularity: match &row["granularity"] {
+                Value::Number(n) => n.as_u64().unwrap_or_default() as u32,
+                Value::String(s) => s.parse().unwrap_or_default(),
+                _ => 0,
+            },
+        })
+        .collect();
+    let projections = rows(&run(&projections_query(table))?)?
+        .iter()
+        .map(|row| text(row, "name"))
+        .collect();
+
+    let engine_full = text(&meta, "engine_full");
+    Ok(Some(LiveTable {
+        table: CreateTable {
+            table_name: table.clone(),
+            columns,
+            engine: text(&meta, "engine"),
+            partition_by: non_empty(text(&meta, "partition_key")),
+            order_by: split_top_level(&text(&meta, "sorting_key")),
+            sample_by: non_empty(text(&meta, "sampling_key")),
+            ttl: ttl_of(&engine_full),
+            settings: vec![],
+        },
+        indexes,
+        projections,
+    }))
+}
+
+fn interval_function(unit: &str) -> Option<&'static str> {
+    Some(match unit.to_ascii_uppercase().as_str() {
+        "SECOND" => "toIntervalSecond",
+        "MINUTE" => "toIntervalMinute",
+        "HOUR" => "toIntervalHour",
+        "DAY" => "toIntervalDay",
+        "WEEK" => "toIntervalWeek",
+        "MONTH" => "toIntervalMonth",
+        "YEAR" => "toIntervalYear",
+        _ => return None,
+    })
+}
+
+// ClickHouse stores expressions in its own canonical form: `INTERVAL 90 DAY`
+// comes back as `toIntervalDay(90)` and `tuple(a, b)` as `(a, b)`.
+pub fn normalize(expr: &str) -> String {
+    let words: Vec<&str> = expr.split_whitespace().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        if words[i].eq_ignore_ascii_case("INTERVAL")
+            && let (Some(n), Some(unit)) = (words.get(i + 1), words.get(i + 2))
+            && let Some(function) = interval_function(unit.trim_end_matches(')'))
+        {
+            let closing = &unit[unit.trim_end_matches(')').len()..];
+            out.push(format!("{function}({n}){closing}"));
+            i += 3;
+            continue;
+        }
+        out.push(words[i].to_string());
+        i += 1;
+    }
+    let joined = out.join("");
+    match joined.strip_prefix("tuple(") {
+        Some(rest) => format!("({rest}"),
+        None => joined,
+    }
+}
+
+fn same(expected: Option<&str>, actual: Option<&str>) -> bool {
+    expected.map(normalize) == actual.map(normalize)
+}
+
+fn alter(table: &TableName, command: AlterCommand) -> String {
+    Statement::alter(table, command).to_sql()
+}
+
+// Copies the columns both tables have, by name, so a column added or dropped
+// by the plan doesn't shift the others.
+fn rebuild(expected: &CreateTable, live: &CreateTable) -> Fix {
+    let shared = expected
+        .columns
+        .iter()
+        .filter(|c| live.columns.iter().any(|l| l.name == c.name))
+        .map(|c| c.name.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Fix::Rebuild(format!(
+        "INSERT INTO {} ({shared}) SELECT {shared} FROM {};",
+        expected.table_name.with_suffix("_new"),
+        expected.table_name
+    ))
+}
+
+// Whether `expr` refers to `column`, matching whole identifiers.
+fn mentions(expr: &str, column: &Identifier) -> bool {
+    expr.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|word| word == column.as_str())
+}
+
+fn diff_columns(expected: &CreateTable, live: &CreateTable) -> Vec<Difference> {
+    let t = &expected.table_name;
+    // ClickHouse refuses to change the type of a column its keys read.
+    let keyed = |column: &Identifier| {
+        live.order_by
+            .iter()
+            .chain(&live.partition_by)
+            .chain(&live.sample_by)
+            .any(|expr| mentions(expr, column))
+    };
+    let mut out = Vec::new();
+    for column in &expected.columns {
+        let definition = column.definition();
+        match live.columns.iter().find(|c| c.name == column.name) {
+            None => out.push(Difference {
+                object: format!("column {}", column.name),
+                expected: Some(definition.clone()),
+                actual: None,
+                fix: Fix::Sql(alter(t, AlterCommand::AddColumn(column.clone()))),
+            }),
+            Some(found)
+                if found.data_type != column.data_type
+                    || !same(column.default.as_deref(), found.default.as_deref()) =>
+            {
+                let fix = if found.data_type != column.data_type && keyed(&column.name) {
+                    rebuild(expected, live)
+                } else {
+                    Fix::Sql(alter(t, AlterCommand::ModifyColumn(column.clone())))
+                };
+                out.push(Difference {
+                    object: format!("column {}", column.name),
+                    expected: Some(definition.clone()),
+                    actual: Some(found.definition()),
+                    fix,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    for column in &live.columns {
+        if !expected.columns.iter().any(|c| c.name == column.name) {
+            out.push(Difference {
+                object: format!("column {}", column.name),
+                expected: None,
+                actual: Some(column.definition()),
+                fix: Fix::Review(alter(t, AlterCommand::DropColumn(column.name.clone()))),
+            });
+        }
+    }
+    out
+}
+
+fn diff_clauses(expected: &CreateTable, live: &CreateTable) -> Vec<Difference> {
+    let t = &expected.table_name;
+    let mut out = Vec::new();
+    let engine = |e: &str| e.split('(').next().unwrap_or(e).trim().to_string();
+    if engine(&expected.engine) != engine(&live.engine) {
+        out.push(Difference {
+            object: "ENGINE".to_string(),
+            expected: Some(expected.engine.clone()),
+            actual: Some(live.engine.clone()),
+            fix: rebuild(expected, live),
+        });
+    }
+    if !same(
+        expected.partition_by.as_deref(),
+        live.partition_by.as_deref(),
+    ) {
+        out.push(Difference {
+            object: "PARTITION BY".to_string(),
+            expected: expected.partition_by.clone(),
+            actual: live.partition_by.clone(),
+            fix: rebuild(expected, live),
+        });
+    }
+    let order = |cols: &[String]| cols.iter().map(|c| normalize(c)).collect::<Vec<_>>();
+    if order(&expected.order_by) != order(&live.order_by) {
+        out.push(Difference {
+            object: "ORDER BY".to_string(),
+            expected: Some(format!("({})", expected.order_by.join(", "))),
+            actual: Some(format!("({})", live.order_by.join(", "))),
+            fix: rebuild(expected, live),
+        });
+    }
+    if !same(expected.sample_by.as_deref(), live.sample_by.as_deref()) {
+        out.push(Difference {
+            object: "SAMPLE BY".to_string(),
+            expected: expected.sample_by.clone(),
+            actual: live.sample_by.clone(),
+            fix: match &expected.sample_by {
+                Some(s) => Fix::Sql(alter(t, AlterCommand::ModifySampleBy(s.clone()))),
+                None => Fix::Sql(alter(t, AlterCommand::RemoveSampleBy)),
+            },
+        });
+    }
+    if !same(expected.ttl.as_deref(), live.ttl.as_deref()) {
+        out.push(Difference {
+            object: "TTL".to_string(),
+            expected: expected.ttl.clone(),
+            actual: live.ttl.clone(),
+            fix: match &expected.ttl {
+                Some(ttl) => Fix::Sql(alter(t, AlterCommand::ModifyTtl(ttl.clone()))),
+                None => Fix::Review(alter(t, AlterCommand::RemoveTtl)),
+            },
+        });
+    }
+    out
+}
+
+fn index_definition(index: &SkipIndexConfig) -> String {
+    format!(
+        "{} TYPE {} GRANULARITY {}",
+        index.expression, index.index_type, index.granularity
+    )
+}
+
+fn diff_indexes(
+    table: &TableName,
+    expected: &[SkipIndexConfig],
+    live: &[SkipIndexConfig],
+) -> Vec<Difference> {
+    let mut out = Vec::new();
+    for index in expected {
+        let found = live.iter().find(|i| i.name == index.name);
+        let matches = found.is_some_and(|f| {
+            normalize(&f.expression) == normalize(&index.expression)
+                && normalize(&f.index_type) == normalize(&index.index_type)
+                && f.granularity == index.granularity
+        });
+        if matches {
+            continue;
+        }
+        // Like projections, the index only covers parts written after it is
+        // added until it is materialized.
+        let name = Identifier::new(&index.name);
+        let mut fix = vec![
+            indexes::add_index(table, index).to_sql(),
+            alter(table, AlterCommand::MaterializeIndex(name.clone())),
+        ];
+        if found.is_some() {
+            fix.insert(0, alter(table, AlterCommand::DropIndex(name)));
+        }
+        out.push(Difference {
+            object: format!("index {}", index.name),
+            expected: Some(index_definition(index)),
+            actual: found.map(index_definition),
+            fix: Fix::Sql(fix.join("\n")),
+        });
+    }
+    for index in live {
+        if !expected.iter().any(|i| i.name == index.name) {
+            out.push(Difference {
+                object: format!("index {}", index.name),
+                expected: None,
+                actual: Some(index_definition(index)),
+                fix: Fix::Review(alter(
+                    table,
+                    AlterCommand::DropIndex(Identifier::new(&index.name)),
+                )),
+            });
+        }
+    }
+    out
+}
+
+fn diff_projections(table: &TableName, live: &[String]) -> Vec<Difference> {
+    let name = projections::DAILY_EVENT_PROJECTION;
+    let mut out = Vec::new();
+    if !live.iter().any(|p| p == name) {
+        // Parts written before the projection existed need MATERIALIZE.
+        out.push(Difference {
+            object: format!("projection {name}"),
+            expected: Some(name.to_string()),
+            actual: None,
+            fix: Fix::Sql(format!(
+                "{}\n{}",
+                projections::daily_event_projection(table).to_sql(),
+                alter(
+                    table,
+                    AlterCommand::MaterializeProjection(Identifier::new(name))
+                )
+            )),
+        });
+    }
+    for projection in live.iter().filter(|p| *p != name) {
+        out.push(Difference {
+            object: format!("projection {projection}"),
+            expected: None,
+            actual: Some(projection.clone()),
+            fix: Fix::Review(alter(
+                table,
+                AlterCommand::DropProjection(Identifier::new(projection)),
+            )),
+        });
+    }
+    out
+}
+
+pub fn diff(
+    expected: &CreateTable,
+    expected_indexes: &[SkipIndexConfig],
+    live: &LiveTable,
+) -> Vec<Difference> {
+    let table = &expected.table_name;
+    let mut out = diff_clauses(expected, &live.table);
+    out.extend(diff_columns(expected, &live.table));
+    out.extend(diff_indexes(table, expected_indexes, &live.indexes));
+    out.extend(diff_projections(table, &live.projections));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::builder::build_create_table_sql;
+
+    // Recorded from a 24.8 server for a table created from an older plan:
+    // DateTime timestamp, no TTL, an ad-hoc index and no projection.
+    const TABLES: &str = r#"{"engine":"MergeTree","engine_full":"MergeTree PARTITION BY toYYYYMM(timestamp) ORDER BY (project_id, event_name, timestamp, distinct_id) SETTINGS index_granularity = 8192","partition_key":"toYYYYMM(timestamp)","sorting_key":"project_id, event_name, timestamp, distinct_id","sampling_key":""}"#;
+    const COLUMNS: &str = r#"{"name":"project_id","type":"UInt32","default_kind":"","default_expression":""}
+{"name":"timestamp","type":"DateTime","default_kind":"","default_expression":""}
+{"name":"event_name","type":"LowCardinality(String)","default_kind":"","default_expression":""}
+{"name":"distinct_id","type":"String","default_kind":"","default_expression":""}
+{"name":"properties","type":"JSON","default_kind":"","default_expression":""}
+{"name":"legacy_flag","type":"Nullable(UInt8)","default_kind":"","default_expression":""}
+"#;
+    const INDEXES: &str =
+        r#"{"name":"idx_legacy","expr":"legacy_flag","type_full":"minmax","granularity":"1"}"#;
+
+    fn replay(query: &str) -> Result<String, SharpError> {
+        Ok(if query.contains("system.tables") {
+            TABLES
+        } else if query.contains("system.columns") {
+            COLUMNS
+        } else if query.contains("system.data_skipping_indices") {
+            INDEXES
+        } else {
+            ""
+        }
+        .to_string())
+    }
+
+    fn planned() -> CreateTable {
+        let mut schema = crate::config::schema::EventSchema::default();
+        schema.event_table.name = "events".to_string();
+        schema.event_table.timestamp.precision = 3;
+        build_create_table_sql(
+            &schema,
+            "toYYYYMM(timestamp)",
+            &[
+                "project_id".to_string(),
+                "event_name".to_string(),
+                "timestamp".to_string(),
+                "distinct_id".to_string(),
+            ],
+            None,
+            Some("toDateTime(timestamp) + INTERVAL 30 DAY".to_string()),
+        )
+    }
+
+    #[test]
+    fn reconstructs_create_table() {
+        let live = introspect(replay, &TableName::new("events"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(live.table.engine, "MergeTree");
+        assert_eq!(live.table.order_by.len(), 4);
+        assert_eq!(live.table.ttl, None);
+        let legacy = live.table.columns.last().unwrap();
+        assert!(legacy.data_type.is_nullable());
+        assert_eq!(legacy.data_type, ClickHouseType::UInt(8).nullable());
+        assert_eq!(live.indexes[0].granularity, 1);
+        assert!(live.projections.is_empty());
+    }
+
+    #[test]
+    fn missing_table_is_none() {
+        assert!(
+            introspect(|_| Ok(String::new()), &TableName::new("events"))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn reports_structured_differences() {
+        let live = introspect(replay, &TableName::new("events"))
+            .unwrap()
+            .unwrap();
+        let diffs = diff(&planned(), &[], &live);
+        let objects: Vec<&str> = diffs.iter().map(|d| d.object.as_str()).collect();
+        assert_eq!(
+            objects,
+            [
+                "TTL",
+                "column timestamp",
+                "column legacy_flag",
+                "index idx_legacy",
+                "projection daily_event_counts"
+            ]
+        );
+        assert_eq!(
+            diffs[0].fix,
+            Fix::Sql(
+                "ALTER TABLE events MODIFY TTL toDateTime(timestamp) + INTERVAL 30 DAY;"
+                    .to_string()
+            )
+        );
+        // timestamp is in the sort and partition keys, so its type can't be
+        // altered in place.
+        assert!(matches!(diffs[1].fix, Fix::Rebuild(_)));
+        assert!(matches!(diffs[2].fix, Fix::Review(_)));
+        assert_eq!(
+            diffs[2].actual.as_deref(),
+            Some("legacy_flag Nullable(UInt8)")
+        );
+    }
+
+    #[test]
+    fn sort_key_change_needs_rebuild() {
+        let live = introspect(replay, &TableName::new("events"))
+            .unwrap()
+            .unwrap();
+        let mut expected = planned();
+        expected.order_by.swap(1, 2);
+        let diffs = diff(&expected, &[], &live);
+        let order = diffs.iter().find(|d| d.object == "ORDER BY").unwrap();
+        // legacy_flag isn't planned and the planned columns all exist live.
+        assert_eq!(
+            order.fix,
+            Fix::Rebuild(
+                "INSERT INTO events_new (project_id, timestamp, event_name, distinct_id, \
+                 properties) SELECT project_id, timestamp, event_name, distinct_id, properties \
+                 FROM events;"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn key_column_type_change_needs_rebuild() {
+        let live = introspect(replay, &TableName::new("events"))
+            .unwrap()
+            .unwrap();
+        let mut expected = planned();
+        let project_id = expected
+            .columns
+            .iter_mut()
+            .find(|c| c.name.as_str() == "project_id")
+            .unwrap();
+        project_id.data_type = ClickHouseType::UInt(64);
+        let diffs = diff(&expected, &[], &live);
+        let column = diffs
+            .iter()
+            .find(|d| d.object == "column project_id")
+            .unwrap();
+        assert!(matches!(column.fix, Fix::Rebuild(_)));
+        assert!(mentions(
+            "toYYYYMM(timestamp)",
+            &Identifier::new("timestamp")
+        ));
+        assert!(!mentions("cityHash64(distinct_id)", &Identifier::new("id")));
+    }
+
+    #[test]
+    fn changed_index_is_rebuilt_and_materialized() {
+        let live = SkipIndexConfig {
+            name: "idx_event_name".to_string(),
+            expression: "event_name".to_string(),
+            index_type: "set(100)".to_string(),
+            granularity: 4,
+        };
+        let expected = SkipIndexConfig {
+            granularity: 1,
+            ..live.clone()
+        };
+        let diffs = diff_indexes(&TableName::new("events"), &[expected], &[live]);
+        let Fix::Sql(sql) = &diffs[0].fix else {
+            panic!("expected SQL");
+        };
+        assert!(sql.starts_with("ALTER TABLE events DROP INDEX idx_event_name;\n"));
+        assert!(sql.contains("ADD INDEX idx_event_name event_name TYPE set(100) GRANULARITY 1"));
+        assert!(sql.ends_with("\nALTER TABLE events MATERIALIZE INDEX idx_event_name;"));
+    }
+
+    #[test]
+    fn normalizes_clickhouse_canonical_forms() {
+        assert_eq!(
+            normalize("toDateTime(timestamp) + INTERVAL 90 DAY"),
+            normalize("toDateTime(timestamp) + toIntervalDay(90)")
+        );
+        assert_eq!(
+            normalize("tuple(project_id, toYYYYMMDD(timestamp))"),
+            normalize("(project_id, toYYYYMMDD(timestamp))")
+        );
+        assert_eq!(
+            ttl_of(
+                "MergeTree ORDER BY x TTL timestamp + toIntervalDay(7) SETTINGS index_granularity = 8192"
+            ),
+            Some("timestamp + toIntervalDay(7)".to_string())
+        );
+    }
+
+    #[test]
+    fn splits_sort_keys_at_top_level() {
+        assert_eq!(
+            split_top_level("project_id, cityHash64(distinct_id, 'a,b'), timestamp"),
+            ["project_id", "cityHash64(distinct_id, 'a,b')", "timestamp"]
+        );
+    }
+}
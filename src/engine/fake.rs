@@ -0,0 +1,486 @@
+use crate::config::schema::{EventSchema, PropertyShape, PropertyType};
+use crate::engine::timestamp::civil_from_days;
+use crate::errors::SharpError;
+use crate::sql::builder;
+use std::io::{self, Write};
+
+// Used when the schema has no data_profile (or leaves a field out).
+const DEFAULT_TENANTS: u32 = 100;
+const DEFAULT_TENANT_SKEW: f64 = 1.0;
+const DEFAULT_EVENT_NAMES: &[(&str, f64)] = &[
+    ("pageview", 0.6),
+    ("click", 0.3),
+    ("signup", 0.07),
+    ("purchase", 0.03),
+];
+// About ten events per user per day, within sane bounds.
+const MIN_USERS: u64 = 100;
+const MAX_USERS: u64 = 10_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    JsonEachRow,
+    CsvWithNames,
+    TsvWithNames,
+}
+
+impl Format {
+    pub fn clickhouse_name(self) -> &'static str {
+        match self {
+            Self::JsonEachRow => "JSONEachRow",
+            Self::CsvWithNames => "CSVWithNames",
+            Self::TsvWithNames => "TSVWithNames",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub days: u32,
+    // Unix seconds of the first generated event.
+    pub start: i64,
+    pub seed: u64,
+    pub format: Format,
+}
+
+// SplitMix64: tiny, fast, and the same sequence on every platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+}
+
+struct Weighted {
+    cdf: Vec<f64>,
+}
+
+impl Weighted {
+    fn new(weights: impl IntoIterator<Item = f64>) -> Self {
+        let mut total = 0.0;
+        let mut cdf: Vec<f64> = weights
+            .into_iter()
+            .map(|w| {
+                total += w.max(0.0);
+                total
+            })
+            .collect();
+        if total > 0.0 {
+            cdf.iter_mut().for_each(|c| *c /= total);
+        }
+        Self { cdf }
+    }
+
+    fn zipf(n: u32, s: f64) -> Self {
+        Self::new((1..=n).map(|k| f64::from(k).powf(-s)))
+    }
+
+    fn pick(&self, rng: &mut Rng) -> usize {
+        let u = rng.next_f64();
+        self.cdf
+            .partition_point(|&c| c <= u)
+            .min(self.cdf.len().saturating_sub(1))
+    }
+}
+
+enum Field {
+    Int(u64),
+    Str(String),
+    // Already-serialized JSON, written bare in JSONEachRow and as a string
+    // elsewhere.
+    Json(String),
+}
+
+struct Generator<'a> {
+    rng: Rng,
+    tenants: Weighted,
+    event_names: Vec<String>,
+    event_weights: Weighted,
+    properties: &'a [PropertyShape],
+    users: u64,
+    precision: u32,
+    // The row's user, so session_id can follow distinct_id.
+    user: u64,
+}
+
+impl Generator<'_> {
+    fn timestamp(&self, ticks: u64) -> String {
+        let unit = 10u64.pow(self.precision);
+        let (secs, frac) = ((ticks / unit) as i64, ticks % unit);
+        let (y, mo, d) = civil_from_days(secs.div_euclid(86_400));
+        let tod = secs.rem_euclid(86_400);
+        let text = format!(
+            "{y:04}-{mo:02}-{d:02} {:02}:{:02}:{:02}",
+            tod / 3600,
+            tod / 60 % 60,
+            tod % 60
+        );
+        match self.precision {
+            0 => text,
+            p => format!("{text}.{frac:0width$}", width = p as usize),
+        }
+    }
+
+    // Values are drawn uniformly from `cardinality` distinct ones per property.
+    fn properties(&mut self) -> String {
+        let mut object = serde_json::Map::new();
+        for shape in self.properties {
+            if self.rng.next_f64() >= shape.presence {
+                continue;
+            }
+            let k = self.rng.below(shape.cardinality);
+            let value = match shape.kind {
+                PropertyType::String => format!("{}_{k}", shape.name).into(),
+                PropertyType::Int => k.into(),
+                PropertyType::Float => (k as f64 * 0.25).into(),
+                PropertyType::Bool => k.is_multiple_of(2).into(),
+            };
+            object.insert(shape.name.clone(), value);
+        }
+        serde_json::Value::Object(object).to_string()
+    }
+
+    fn field(&mut self, column: &str, ticks: u64) -> Field {
+        match column {
+            "project_id" => Field::Int(self.tenants.pick(&mut self.rng) as u64 + 1),
+            "timestamp" => Field::Str(self.timestamp(ticks)),
+            "event_name" => {
+                let i = self.event_weights.pick(&mut self.rng);
+                Field::Str(self.event_names[i].clone())
+            }
+            "distinct_id" => {
+                self.user = self.rng.below(self.users);
+                Field::Str(format!("user_{}", self.user))
+            }
+            // A user's events within the same half hour share a session.
+            "session_id" => {
+                let slot = ticks / 10u64.pow(self.precision) / 1800;
+                Field::Str(format!("session_{}_{slot}", self.user))
+            }
+            "properties" => Field::Json(self.properties()),
+            _ => Field::Str(String::new()),
+        }
+    }
+}
+
+fn csv_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+fn tsv_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn write_row(
+    out: &mut impl Write,
+    format: Format,
+    columns: &[String],
+    fields: &[Field],
+) -> io::Result<()> {
+    match format {
+        Format::JsonEachRow => {
+            let cells: Vec<String> = columns
+                .iter()
+                .zip(fields)
+                .map(|(name, field)| {
+                    let value = match field {
+                        Field::Int(v) => v.to_string(),
+                        Field::Str(s) => serde_json::Value::from(s.as_str()).to_string(),
+                        Field::Json(j) => j.clone(),
+                    };
+                    format!("\"{name}\":{value}")
+                })
+                .collect();
+            writeln!(out, "{{{}}}", cells.join(","))
+        }
+        Format::CsvWithNames | Format::TsvWithNames => {
+            let cells: Vec<String> = fields
+                .iter()
+                .map(|field| match (field, format) {
+                    (Field::Int(v), _) => v.to_string(),
+                    (Field::Str(s) | Field::Json(s), Format::CsvWithNames) => csv_quote(s),
+                    (Field::Str(s) | Field::Json(s), _) => tsv_escape(s),
+                })
+                .collect();
+            let separator = if format == Format::CsvWithNames {
+                ","
+            } else {
+                "\t"
+            };
+            writeln!(out, "{}", cells.join(separator))
+        }
+    }
+}
+
+pub fn row_count(schema: &EventSchema, days: u32) -> Result<u64, SharpError> {
+    let per_day = schema.event_table.expected_events_per_day;
+    per_day.checked_mul(u64::from(days)).ok_or_else(|| {
+        SharpError::InvalidSchema(format!(
+            "{per_day} events/day over {days} days is more rows than fit in a u64"
+        ))
+    })
+}
+
+// Rows are written in timestamp order, spread evenly over `days` with jitter,
+// so memory use is flat however many rows the schema's volume implies.
+pub fn generate(schema: &EventSchema, options: &Options, out: &mut impl Write) -> io::Result<u64> {
+    let table = &schema.event_table;
+    let profile = schema.data_profile.clone().unwrap_or_default();
+
+    let tenants = if table.multi_tenant {
+        profile.tenants.unwrap_or(DEFAULT_TENANTS).max(1)
+    } else {
+        1
+    };
+    let skew = profile.tenant_skew.unwrap_or(DEFAULT_TENANT_SKEW);
+    let (event_names, weights): (Vec<String>, Vec<f64>) = if profile.event_names.is_empty() {
+        DEFAULT_EVENT_NAMES
+            .iter()
+            .map(|(n, w)| (n.to_string(), *w))
+            .unzip()
+    } else {
+        profile.event_names.into_iter().unzip()
+    };
+
+    let mut generator = Generator {
+        rng: Rng(options.seed),
+        tenants: Weighted::zipf(tenants, skew),
+        event_names,
+        event_weights: Weighted::new(weights),
+        properties: schema
+            .data_profile
+            .as_ref()
+            .map_or(&[], |p| p.properties.as_slice()),
+        users: (table.expected_events_per_day / 10).clamp(MIN_USERS, MAX_USERS),
+        precision: u32::from(table.timestamp.precision),
+        user: 0,
+    };
+
+    // Columns filled by DEFAULT (ingested_at) are left to the server.
+    let columns: Vec<String> = builder::columns(schema)
+        .into_iter()
+        .filter(|c| c.default.is_none())
+        .map(|c| c.name.as_str().to_string())
+        .collect();
+    match options.format {
+        Format::CsvWithNames => writeln!(out, "{}", columns.join(","))?,
+        Format::TsvWithNames => writeln!(out, "{}", columns.join("\t"))?,
+        Format::JsonEachRow => {}
+    }
+
+    let rows = row_count(schema, options.days).map_err(io::Error::other)?;
+    let unit = 10f64.powi(generator.precision as i32);
+    let start_ticks = options.start as f64 * unit;
+    let step = f64::from(options.days) * 86_400.0 * unit / rows.max(1) as f64;
+
+    for i in 0..rows {
+        let jitter = generator.rng.next_f64();
+        let ticks = (start_ticks + (i as f64 + jitter) * step) as u64;
+        let fields: Vec<Field> = columns.iter().map(|c| generator.field(c, ticks)).collect();
+        write_row(out, options.format, &columns, &fields)?;
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::{DataProfile, EventTable, TimestampConfig};
+
+    // 2024-03-01 00:00:00 UTC
+    const START: i64 = 1_709_251_200;
+
+    fn schema(events_per_day: u64, profile: Option<DataProfile>) -> EventSchema {
+        EventSchema {
+            event_table: EventTable {
+                name: "events".to_string(),
+                multi_tenant: true,
+                expected_events_per_day: events_per_day,
+                retention_days: 30,
+                timestamp: TimestampConfig {
+                    precision: 3,
+                    ingested_at: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            data_profile: profile,
+            ..Default::default()
+        }
+    }
+
+    fn run(schema: &EventSchema, seed: u64, format: Format) -> String {
+        let options = Options {
+            days: 2,
+            start: START,
+            seed,
+            format,
+        };
+        let mut out = Vec::new();
+        generate(schema, &options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn rows(output: &str) -> Vec<serde_json::Value> {
+        output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn rejects_row_counts_that_overflow() {
+        let schema = schema(u64::MAX / 2, None);
+        assert_eq!(row_count(&schema, 2).unwrap(), u64::MAX - 1);
+        let err = row_count(&schema, 3).unwrap_err();
+        assert!(err.to_string().contains("more rows than fit"));
+        let options = Options {
+            days: 3,
+            start: START,
+            seed: 1,
+            format: Format::JsonEachRow,
+        };
+        assert!(generate(&schema, &options, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn is_deterministic_per_seed() {
+        let schema = schema(100, None);
+        assert_eq!(
+            run(&schema, 7, Format::JsonEachRow),
+            run(&schema, 7, Format::JsonEachRow)
+        );
+        assert_ne!(
+            run(&schema, 7, Format::JsonEachRow),
+            run(&schema, 8, Format::JsonEachRow)
+        );
+    }
+
+    #[test]
+    fn emits_table_columns_in_time_order() {
+        let output = run(&schema(100, None), 1, Format::JsonEachRow);
+        let rows = rows(&output);
+        assert_eq!(rows.len(), 200);
+
+        let keys: Vec<&String> = rows[0].as_object().unwrap().keys().collect();
+        assert_eq!(
+            keys,
+            [
+                "distinct_id",
+                "event_name",
+                "project_id",
+                "properties",
+                "timestamp"
+            ]
+        );
+        let stamps: Vec<&str> = rows
+            .iter()
+            .map(|r| r["timestamp"].as_str().unwrap())
+            .collect();
+        assert!(stamps.windows(2).all(|w| w[0] <= w[1]));
+        assert!(stamps[0].starts_with("2024-03-01 00:"));
+        assert!(stamps[199].starts_with("2024-03-02 23:"));
+        assert_eq!(stamps[0].len(), "2024-03-01 00:00:00.000".len());
+    }
+
+    #[test]
+    fn honours_tenant_skew_and_event_weights() {
+        let profile = DataProfile {
+            tenants: Some(10),
+            tenant_skew: Some(1.0),
+            event_names: [("a".to_string(), 0.8), ("b".to_string(), 0.2)].into(),
+            ..Default::default()
+        };
+        let rows = rows(&run(&schema(5_000, Some(profile)), 3, Format::JsonEachRow));
+        let share = |pred: &dyn Fn(&serde_json::Value) -> bool| {
+            rows.iter().filter(|r| pred(r)).count() as f64 / rows.len() as f64
+        };
+        // 1 / H(10) ≈ 0.341
+        assert!((share(&|r| r["project_id"] == 1) - 0.341).abs() < 0.03);
+        assert!((share(&|r| r["event_name"] == "a") - 0.8).abs() < 0.03);
+    }
+
+    #[test]
+    fn honours_property_shapes() {
+        let profile = DataProfile {
+            properties: vec![
+                PropertyShape {
+                    name: "plan".to_string(),
+                    kind: PropertyType::String,
+                    cardinality: 3,
+                    presence: 0.5,
+                },
+                PropertyShape {
+                    name: "n".to_string(),
+                    kind: PropertyType::Int,
+                    cardinality: 10,
+                    presence: 1.0,
+                },
+            ],
+            ..Default::default()
+        };
+        let rows = rows(&run(&schema(2_000, Some(profile)), 5, Format::JsonEachRow));
+        let with_plan: Vec<&serde_json::Value> = rows
+            .iter()
+            .filter_map(|r| r["properties"].get("plan"))
+            .collect();
+        let presence = with_plan.len() as f64 / rows.len() as f64;
+        assert!((presence - 0.5).abs() < 0.05);
+        assert!(
+            with_plan
+                .iter()
+                .all(|v| ["plan_0", "plan_1", "plan_2"].contains(&v.as_str().unwrap()))
+        );
+        assert!(
+            rows.iter()
+                .all(|r| r["properties"]["n"].as_u64().unwrap() < 10)
+        );
+    }
+
+    #[test]
+    fn csv_has_header_and_quoted_json() {
+        let profile = DataProfile {
+            properties: vec![PropertyShape {
+                name: "plan".to_string(),
+                kind: PropertyType::String,
+                cardinality: 1,
+                presence: 1.0,
+            }],
+            ..Default::default()
+        };
+        let output = run(&schema(1, Some(profile)), 1, Format::CsvWithNames);
+        let mut lines = output.lines();
+        assert_eq!(
+            lines.next(),
+            Some("project_id,timestamp,event_name,distinct_id,properties")
+        );
+        assert!(
+            lines
+                .next()
+                .unwrap()
+                .ends_with(",\"{\"\"plan\"\":\"\"plan_0\"\"}\"")
+        );
+    }
+
+    #[test]
+    fn tsv_escapes_control_characters() {
+        assert_eq!(tsv_escape("a\tb\\c\n"), "a\\tb\\\\c\\n");
+    }
+}
@@ -0,0 +1,313 @@
+use crate::errors::SharpError;
+use crate::sql::ast::TableName;
+use serde::{Deserialize, Deserializer, Serialize};
+
+// ClickHouse quotes 64-bit integers in JSON output by default; snapshots
+// written by sharp itself use plain numbers. Accept both.
+fn lenient_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number {
+        Int(u64),
+        Float(f64),
+        Text(String),
+    }
+    match Number::deserialize(deserializer)? {
+        Number::Int(n) => Ok(n),
+        Number::Float(f) => Ok(f as u64),
+        Number::Text(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PartitionRow {
+    pub partition_id: String,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub parts: u64,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub rows: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ColumnRow {
+    pub name: String,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub data_compressed_bytes: u64,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub data_uncompressed_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MergeRow {
+    pub elapsed: f64,
+    pub progress: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MutationRow {
+    pub mutation_id: String,
+    pub command: String,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub age_seconds: u64,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub parts_to_do: u64,
+    #[serde(default)]
+    pub latest_fail_reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReplicaRow {
+    pub replica_name: String,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub is_readonly: u64,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub absolute_delay: u64,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub queue_size: u64,
+}
+
+// Everything `health` reads, as captured from the system tables. Written with
+// --save-snapshot and read back with --snapshot to work offline.
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub table: String,
+    #[serde(default)]
+    pub partitions: Vec<PartitionRow>,
+    #[serde(default)]
+    pub columns: Vec<ColumnRow>,
+    #[serde(default)]
+    pub merges: Vec<MergeRow>,
+    #[serde(default)]
+    pub mutations: Vec<MutationRow>,
+    #[serde(default)]
+    pub replicas: Vec<ReplicaRow>,
+}
+
+fn queries(table: &TableName) -> [String; 5] {
+    let filter = table.system_filter("table");
+    [
+        format!(
+            "SELECT partition_id, count() AS parts, sum(rows) AS rows FROM system.parts \
+             WHERE active AND {filter} GROUP BY partition_id ORDER BY partition_id FORMAT JSONEachRow"
+        ),
+        format!(
+            "SELECT name, data_compressed_bytes, data_uncompressed_bytes FROM system.columns \
+             WHERE {filter} FORMAT JSONEachRow"
+        ),
+        format!("SELECT elapsed, progress FROM system.merges WHERE {filter} FORMAT JSONEachRow"),
+        format!(
+            "SELECT mutation_id, command, dateDiff('second', create_time, now()) AS age_seconds, \
+             parts_to_do, latest_fail_reason FROM system.mutations \
+             WHERE NOT is_done AND {filter} FORMAT JSONEachRow"
+        ),
+        format!(
+            "SELECT replica_name, is_readonly, absolute_delay, queue_size FROM system.replicas \
+             WHERE {filter} FORMAT JSONEachRow"
+        ),
+    ]
+}
+
+fn rows<T: for<'de> Deserialize<'de>>(body: &str) -> Result<Vec<T>, SharpError> {
+    body.lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| {
+            serde_json::from_str(l).map_err(|e| SharpError::ServerError {
+                code: None,
+                message: format!("unexpected response: {e}"),
+            })
+        })
+        .collect()
+}
+
+// The other system tables just return no rows for a table that isn't there.
+fn exists_query(table: &TableName) -> String {
+    format!(
+        "SELECT name FROM system.tables WHERE {} FORMAT JSONEachRow",
+        table.system_filter("name")
+    )
+}
+
+// `run` sends one query and returns the response body.
+pub fn capture(
+    run: impl Fn(&str) -> Result<String, SharpError>,
+    table: &TableName,
+) -> Result<Snapshot, SharpError> {
+    if run(&exists_query(table))?.trim().is_empty() {
+        return Err(SharpError::TableNotFound(table.to_string()));
+    }
+    let [partitions, columns, merges, mutations, replicas] = queries(table);
+    Ok(Snapshot {
+        table: table.to_string(),
+        partitions: rows(&run(&partitions)?)?,
+        columns: rows(&run(&columns)?)?,
+        merges: rows(&run(&merges)?)?,
+        mutations: rows(&run(&mutations)?)?,
+        replicas: rows(&run(&replicas)?)?,
+    })
+}
+
+// Every table has columns, so a snapshot without any was taken of one that
+// doesn't exist.
+pub fn parse_snapshot(contents: &str) -> Result<Snapshot, SharpError> {
+    let snapshot: Snapshot =
+        serde_json::from_str(contents).map_err(|e| SharpError::SnapshotError(e.to_string()))?;
+    if snapshot.columns.is_empty() {
+        return Err(SharpError::TableNotFound(snapshot.table));
+    }
+    Ok(snapshot)
+}
+
+pub fn to_json(snapshot: &Snapshot) -> String {
+    serde_json::to_string_pretty(snapshot).expect("snapshot is always serializable")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnCompression {
+    pub name: String,
+    pub compressed: u64,
+    pub uncompressed: u64,
+}
+
+impl ColumnCompression {
+    pub fn ratio(&self) -> f64 {
+        self.uncompressed as f64 / self.compressed.max(1) as f64
+    }
+}
+
+// Derived metrics the live rules judge.
+#[derive(Debug, Default)]
+pub struct TableHealth {
+    pub table: String,
+    pub partitions: usize,
+    pub active_parts: u64,
+    pub rows: u64,
+    pub busiest_partition: Option<PartitionRow>,
+    pub compressed_bytes: u64,
+    pub uncompressed_bytes: u64,
+    // Largest first.
+    pub columns: Vec<ColumnCompression>,
+    pub merges_running: usize,
+    pub pending_mutations: Vec<MutationRow>,
+    // Empty for non-replicated tables.
+    pub replicas: Vec<ReplicaRow>,
+}
+
+impl TableHealth {
+    pub fn parts_per_partition(&self) -> f64 {
+        self.active_parts as f64 / self.partitions.max(1) as f64
+    }
+
+    pub fn compression_ratio(&self) -> f64 {
+        self.uncompressed_bytes as f64 / self.compressed_bytes.max(1) as f64
+    }
+
+    pub fn max_replica_delay(&self) -> u64 {
+        self.replicas
+            .iter()
+            .map(|r| r.absolute_delay)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+pub fn summarize(snapshot: &Snapshot) -> TableHealth {
+    let mut columns: Vec<ColumnCompression> = snapshot
+        .columns
+        .iter()
+        .map(|c| ColumnCompression {
+            name: c.name.clone(),
+            compressed: c.data_compressed_bytes,
+            uncompressed: c.data_uncompressed_bytes,
+        })
+        .collect();
+    columns.sort_by(|a, b| b.compressed.cmp(&a.compressed).then(a.name.cmp(&b.name)));
+
+    TableHealth {
+        table: snapshot.table.clone(),
+        partitions: snapshot.partitions.len(),
+        active_parts: snapshot.partitions.iter().map(|p| p.parts).sum(),
+        rows: snapshot.partitions.iter().map(|p| p.rows).sum(),
+        busiest_partition: snapshot.partitions.iter().max_by_key(|p| p.parts).cloned(),
+        compressed_bytes: columns.iter().map(|c| c.compressed).sum(),
+        uncompressed_bytes: columns.iter().map(|c| c.uncompressed).sum(),
+        columns,
+        merges_running: snapshot.merges.len(),
+        pending_mutations: snapshot.mutations.clone(),
+        replicas: snapshot.replicas.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARTS: &str = r#"{"partition_id":"20240301","parts":"412","rows":"90000000"}
+{"partition_id":"20240302","parts":"8","rows":"10000000"}
+"#;
+    const COLUMNS: &str = r#"{"name":"properties","data_compressed_bytes":"900","data_uncompressed_bytes":"1200"}
+{"name":"timestamp","data_compressed_bytes":"100","data_uncompressed_bytes":"800"}
+"#;
+    const MUTATIONS: &str = r#"{"mutation_id":"mutation_7.txt","command":"DELETE WHERE distinct_id = 'u'","age_seconds":"7200","parts_to_do":"31","latest_fail_reason":""}"#;
+
+    fn replay(query: &str) -> Result<String, SharpError> {
+        Ok(if query.contains("system.tables") {
+            r#"{"name":"events"}"#
+        } else if query.contains("system.parts") {
+            PARTS
+        } else if query.contains("system.columns") {
+            COLUMNS
+        } else if query.contains("system.mutations") {
+            MUTATIONS
+        } else {
+            ""
+        }
+        .to_string())
+    }
+
+    #[test]
+    fn captures_and_summarizes() {
+        let snapshot = capture(replay, &TableName::new("events")).unwrap();
+        let health = summarize(&snapshot);
+        assert_eq!(health.partitions, 2);
+        assert_eq!(health.active_parts, 420);
+        assert_eq!(health.parts_per_partition(), 210.0);
+        assert_eq!(
+            health.busiest_partition.as_ref().unwrap().partition_id,
+            "20240301"
+        );
+        assert_eq!(health.compression_ratio(), 2.0);
+        assert_eq!(health.columns[0].name, "properties");
+        assert_eq!(health.pending_mutations[0].parts_to_do, 31);
+        assert!(health.replicas.is_empty());
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let snapshot = capture(replay, &TableName::new("events")).unwrap();
+        assert_eq!(parse_snapshot(&to_json(&snapshot)).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn rejects_malformed_snapshots() {
+        let err = parse_snapshot("{\"partitions\": []}").unwrap_err();
+        assert!(err.to_string().contains("table"));
+    }
+
+    #[test]
+    fn missing_tables_are_errors() {
+        let err = capture(|_| Ok(String::new()), &TableName::new("nope")).unwrap_err();
+        assert_eq!(err.to_string(), "table 'nope' does not exist");
+        let err = parse_snapshot(r#"{"table": "nope", "partitions": []}"#).unwrap_err();
+        assert!(matches!(err, SharpError::TableNotFound(ref t) if t == "nope"));
+    }
+
+    #[test]
+    fn queries_filter_on_the_table() {
+        for query in queries(&TableName::new("user's")) {
+            assert!(query.contains("database = currentDatabase() AND table = 'user\\'s'"));
+        }
+        for query in queries(&TableName::new("analytics.events")) {
+            assert!(query.contains("database = 'analytics' AND table = 'events'"));
+        }
+    }
+}
@@ -1,93 +1,657 @@
-use crate::config::schema::EventSchema;
-use crate::config::workload::WorkloadProfile;
-
-const HIGH_VOLUME_THRESHOLD: u64 = 100_000_000;
-const SHARDING_THRESHOLD: u64 = 500_000_000;
-const TENANT_PROJECTION_THRESHOLD: u64 = 50_000_000;
-
-pub fn analyze(_schema: &EventSchema, workload: &WorkloadProfile) -> Vec<String> {
-    let mut recommendations = Vec::new();
-
-    // Retention policy
-    if workload.retention_days > 0 {
-        recommendations.push(format!(
-            "Enable TTL to auto-expire old data (`TTL timestamp + INTERVAL {} DAY`)",
-            workload.retention_days
-        ));
-    } else {
-        recommendations.push(
-            "Warn: data will grow unbounded without a retention policy".to_string(),
+use crate::config::policy::human;
+use crate::config::schema::SkipIndexConfig;
+use crate::engine::health::TableHealth;
+use crate::engine::queries::key_prefix;
+use crate::engine::rules::{Category, Finding, LiveRule, PlanContext, Rule, Severity};
+use crate::engine::{indexes, inserts, patterns, projections, ttl};
+use crate::sql::ast::{AlterCommand, Identifier, Statement, TableName, string_literal};
+
+pub fn registry() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(RetentionTtl),
+        Box::new(UnboundedRetention),
+        Box::new(WideParts),
+        Box::new(Sharding),
+        Box::new(TenantProjection),
+        Box::new(TenantLowCardinality),
+        Box::new(TooManyParts),
+        Box::new(SmallInserts),
+        Box::new(InsertBatchSize),
+        Box::new(UnservedQueryPattern),
+        Box::new(Compression),
+    ]
+}
+
+// Rules that judge a running table; only `sharp health` runs them.
+pub fn live_registry() -> Vec<Box<dyn LiveRule>> {
+    vec![
+        Box::new(LivePartsPerPartition),
+        Box::new(LiveCompression),
+        Box::new(MutationBacklog),
+        Box::new(ReplicationLag),
+    ]
+}
+
+fn alter(ctx: &PlanContext, command: AlterCommand) -> String {
+    Statement::alter(&ctx.table(), command).to_sql()
+}
+
+pub fn analyze(ctx: &PlanContext) -> Vec<Finding> {
+    registry()
+        .iter()
+        .filter(|rule| ctx.schema.rules.get(rule.id()) != Some(&false))
+        .filter_map(|rule| rule.evaluate(ctx))
+        .collect()
+}
+
+pub fn analyze_health(table: &TableName, health: &TableHealth) -> Vec<Finding> {
+    live_registry()
+        .iter()
+        .filter_map(|rule| rule.evaluate(table, health))
+        .collect()
+}
+
+// Retention policy
+struct RetentionTtl;
+
+impl Rule for RetentionTtl {
+    fn id(&self) -> &'static str {
+        "retention-ttl"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        &["retention_days"]
+    }
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+    fn category(&self) -> Category {
+        Category::Retention
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        let ttl = ttl::suggest(ctx.workload)?;
+        let finding = self.finding(
+            format!("Enable TTL to auto-expire old data (`TTL {ttl}`)"),
+            "Expired parts are dropped whole during merges, which is far cheaper than \
+             scheduled DELETE jobs.",
         );
+        Some(finding.with_sql(alter(ctx, AlterCommand::ModifyTtl(ttl))))
+    }
+}
+
+struct UnboundedRetention;
+
+impl Rule for UnboundedRetention {
+    fn id(&self) -> &'static str {
+        "unbounded-retention"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        &["retention_days"]
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn category(&self) -> Category {
+        Category::Retention
     }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        if ctx.workload.retention_days > 0 {
+            return None;
+        }
+        Some(self.finding(
+            "Warn: data will grow unbounded without a retention policy".to_string(),
+            "retention_days is 0, so storage and part counts only ever go up.",
+        ))
+    }
+}
 
-    // High-throughput ingestion
-    if workload.events_per_day >= HIGH_VOLUME_THRESHOLD {
-        recommendations.push(
-            "Enable `wide_parts_only` merge-tree setting for high-throughput ingestion".to_string(),
+// High-throughput ingestion
+struct WideParts;
+
+impl Rule for WideParts {
+    fn id(&self) -> &'static str {
+        "wide-parts"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        &["expected_events_per_day"]
+    }
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+    fn category(&self) -> Category {
+        Category::Storage
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        let threshold = ctx.policy.thresholds.high_volume;
+        if ctx.workload.events_per_day < threshold {
+            return None;
+        }
+        let finding = self.finding(
+            "Set `min_bytes_for_wide_part = 0` to write wide parts for high-throughput ingestion"
+                .to_string(),
+            &format!(
+                "At {}+ events/day compact parts are rewritten almost immediately; writing \
+                 wide parts up front saves a merge round [{}].",
+                human(threshold),
+                ctx.policy.cite("high_volume", threshold)
+            ),
         );
+        Some(finding.with_sql(alter(
+            ctx,
+            AlterCommand::ModifySetting("min_bytes_for_wide_part".to_string(), "0".to_string()),
+        )))
     }
+}
+
+// Sharding
+struct Sharding;
 
-    // Sharding
-    if workload.events_per_day >= SHARDING_THRESHOLD {
-        recommendations.push(
+impl Rule for Sharding {
+    fn id(&self) -> &'static str {
+        "sharding"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        &["expected_events_per_day"]
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn category(&self) -> Category {
+        Category::Scaling
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        let threshold = ctx.policy.thresholds.sharding;
+        if ctx.workload.events_per_day < threshold {
+            return None;
+        }
+        Some(self.finding(
             "Consider sharding across multiple ClickHouse nodes".to_string(),
-        );
+            &format!(
+                "At {}+ events/day a single node's merge and disk bandwidth becomes the \
+                 ceiling for both ingestion and queries [{}].",
+                human(threshold),
+                ctx.policy.cite("sharding", threshold)
+            ),
+        ))
     }
+}
+
+// Multi-tenant optimizations
+struct TenantProjection;
 
-    // Multi-tenant optimizations
-    if workload.multi_tenant && workload.events_per_day >= TENANT_PROJECTION_THRESHOLD {
-        recommendations.push(
+impl Rule for TenantProjection {
+    fn id(&self) -> &'static str {
+        "tenant-projection"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        &["multi_tenant", "expected_events_per_day"]
+    }
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+    fn category(&self) -> Category {
+        Category::MultiTenancy
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        let threshold = ctx.policy.thresholds.tenant_projection;
+        if !ctx.workload.multi_tenant || ctx.workload.events_per_day < threshold {
+            return None;
+        }
+        let finding = self.finding(
             "Add a projection on `(project_id, toDate(timestamp))` for per-tenant dashboards"
                 .to_string(),
+            &format!(
+                "Per-tenant daily counts are the most common dashboard query; at {}+ \
+                 events/day a pre-aggregated projection answers them without scanning raw \
+                 rows [{}].",
+                human(threshold),
+                ctx.policy.cite("tenant_projection", threshold)
+            ),
         );
+        Some(finding.with_sql(projections::daily_event_projection(&ctx.table()).to_sql()))
     }
+}
+
+struct TenantLowCardinality;
 
-    if workload.multi_tenant {
-        recommendations.push(
+impl Rule for TenantLowCardinality {
+    fn id(&self) -> &'static str {
+        "tenant-low-cardinality"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        &["multi_tenant"]
+    }
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+    fn category(&self) -> Category {
+        Category::MultiTenancy
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        if !ctx.workload.multi_tenant {
+            return None;
+        }
+        let finding = self.finding(
             "Ensure `project_id` has `LowCardinality(String)` type for efficient filtering"
                 .to_string(),
+            "Every tenant-scoped query filters on project_id; dictionary-encoded values \
+             compare as integers.",
+        );
+        Some(finding)
+    }
+}
+
+// Ingestion batching
+const INGESTION_KEYS: &[&str] = &[
+    "expected_events_per_day",
+    "ingestion",
+    "producers",
+    "insert_interval_ms",
+    "rows_per_insert",
+];
+
+struct TooManyParts;
+
+impl Rule for TooManyParts {
+    fn id(&self) -> &'static str {
+        "too-many-parts"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        INGESTION_KEYS
+    }
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+    fn category(&self) -> Category {
+        Category::Ingestion
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        let message = inserts::parts_warning(ctx.workload, &ctx.partition)?;
+        Some(self.finding(
+            message,
+            "Every INSERT creates at least one part; once merges fall behind, ClickHouse \
+             delays and then rejects inserts.",
+        ))
+    }
+}
+
+struct SmallInserts;
+
+impl Rule for SmallInserts {
+    fn id(&self) -> &'static str {
+        "small-inserts"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        INGESTION_KEYS
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn category(&self) -> Category {
+        Category::Ingestion
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        let message = inserts::small_insert_advice(ctx.workload)?;
+        Some(self.finding(
+            message,
+            "Inserts of a few hundred rows pay the full cost of a part for very little data.",
+        ))
+    }
+}
+
+struct InsertBatchSize;
+
+impl Rule for InsertBatchSize {
+    fn id(&self) -> &'static str {
+        "insert-batch-size"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        INGESTION_KEYS
+    }
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+    fn category(&self) -> Category {
+        Category::Ingestion
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        let message = inserts::batch_advice(ctx.workload, &ctx.partition)?;
+        Some(self.finding(
+            message,
+            "Batching in the client is the cheapest fix: fewer, larger parts and no extra \
+             server-side buffering.",
+        ))
+    }
+}
+
+// Query patterns (from `sharp infer-patterns`)
+struct UnservedQueryPattern;
+
+// Patterns below this share of the logged workload aren't worth an index.
+const MIN_PATTERN_SHARE: f64 = 0.1;
+
+impl Rule for UnservedQueryPattern {
+    fn id(&self) -> &'static str {
+        "unserved-query-pattern"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        &["query_patterns"]
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn category(&self) -> Category {
+        Category::Queries
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        // Patterns are sorted by frequency, so this is the busiest offender.
+        // A pattern without filters reads everything whatever the key is.
+        let pattern = ctx.workload.query_patterns.iter().find(|p| {
+            let filters = patterns::filters(p);
+            p.share >= MIN_PATTERN_SHARE
+                && !filters.is_empty()
+                && key_prefix(&ctx.order_by, &filters).is_empty()
+        })?;
+        let columns: Vec<&str> = pattern
+            .equality
+            .iter()
+            .chain(&pattern.range)
+            .map(String::as_str)
+            .collect();
+        let columns = columns.join(", ");
+        let finding = self.finding(
+            format!(
+                "{:.0}% of logged queries filter on ({columns}) without using the ORDER BY prefix \
+                 (p95 {} ms)",
+                pattern.share * 100.0,
+                pattern.p95_ms
+            ),
+            "These queries read every granule of the partitions they touch. A bloom filter skip \
+             index lets equality lookups on a non-key column skip most granules without \
+             changing the sort key the rest of the workload relies on.",
+        );
+        // None of the pattern's columns lead the key, so any plain equality
+        // column is a candidate; JSON subcolumns can't be indexed directly.
+        match pattern.equality.iter().find(|c| !c.contains('.')) {
+            Some(col) => Some(
+                finding.with_sql(
+                    indexes::add_index(
+                        &ctx.table(),
+                        &SkipIndexConfig {
+                            name: format!("idx_{col}"),
+                            expression: col.clone(),
+                            index_type: "bloom_filter(0.01)".to_string(),
+                            granularity: 4,
+                        },
+                    )
+                    .to_sql(),
+                ),
+            ),
+            None => Some(finding),
+        }
+    }
+}
+
+// Always recommend compression guidance
+struct Compression;
+
+impl Rule for Compression {
+    fn id(&self) -> &'static str {
+        "compression"
+    }
+    fn keys(&self) -> &'static [&'static str] {
+        &["event_table"]
+    }
+    fn severity(&self) -> Severity {
+        Severity::Info
+    }
+    fn category(&self) -> Category {
+        Category::Storage
+    }
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+        let finding = self.finding(
+            "Use `LZ4` compression (ClickHouse default) — switch to `ZSTD` if storage-constrained"
+                .to_string(),
+            "LZ4 decompresses fastest; ZSTD trades some CPU for a noticeably smaller \
+             footprint on JSON properties.",
+        );
+        Some(finding.with_sql(alter(
+            ctx,
+            AlterCommand::ModifyColumnCodec {
+                column: Identifier::new("properties"),
+                codec: "ZSTD(3)".to_string(),
+            },
+        )))
+    }
+}
+
+// Live table health (from `sharp health`)
+struct LivePartsPerPartition;
+
+// Merges usually keep a partition to a few dozen active parts; hundreds mean
+// inserts are outpacing them on the way to parts_to_delay_insert.
+const MAX_HEALTHY_PARTS_PER_PARTITION: u64 = 300;
+
+impl LiveRule for LivePartsPerPartition {
+    fn id(&self) -> &'static str {
+        "live-parts-per-partition"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+    fn category(&self) -> Category {
+        Category::Ingestion
+    }
+    fn evaluate(&self, _table: &TableName, health: &TableHealth) -> Option<Finding> {
+        let busiest = health
+            .busiest_partition
+            .as_ref()
+            .filter(|p| p.parts >= MAX_HEALTHY_PARTS_PER_PARTITION)?;
+        Some(self.finding(
+            format!(
+                "Partition {} has {} active parts ({:.0} per partition on average, {} merges \
+                 running)",
+                busiest.partition_id,
+                busiest.parts,
+                health.parts_per_partition(),
+                health.merges_running
+            ),
+            "Inserts are creating parts faster than merges combine them. Batch inserts \
+             (or enable async_insert) before ClickHouse starts delaying and rejecting them.",
+        ))
+    }
+}
+
+struct LiveCompression;
+
+// Event data normally compresses 5-10x; under this a column is worth a codec.
+const MIN_COMPRESSION_RATIO: f64 = 2.0;
+// Small columns aren't worth the ALTER.
+const MIN_COMPRESSED_BYTES: u64 = 64 * 1024 * 1024;
+
+impl LiveRule for LiveCompression {
+    fn id(&self) -> &'static str {
+        "live-compression"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn category(&self) -> Category {
+        Category::Storage
+    }
+    fn evaluate(&self, table: &TableName, health: &TableHealth) -> Option<Finding> {
+        // Columns are sorted largest first, so this is the costliest one.
+        let column = health
+            .columns
+            .iter()
+            .find(|c| c.compressed >= MIN_COMPRESSED_BYTES && c.ratio() < MIN_COMPRESSION_RATIO)?;
+        let codec = if column.name == "timestamp" || column.name == "ingested_at" {
+            "Delta, ZSTD(1)"
+        } else {
+            "ZSTD(3)"
+        };
+        let finding = self.finding(
+            format!(
+                "Column `{}` compresses only {:.1}x ({} MiB on disk)",
+                column.name,
+                column.ratio(),
+                column.compressed / (1024 * 1024)
+            ),
+            "The default LZ4 codec favours speed over size; ZSTD, and Delta for \
+             monotonic values, shrink poorly compressing columns at a small CPU cost. \
+             Existing parts are recompressed as they merge.",
+        );
+        Some(
+            finding.with_sql(
+                Statement::alter(
+                    table,
+                    AlterCommand::ModifyColumnCodec {
+                        column: Identifier::new(&column.name),
+                        codec: codec.to_string(),
+                    },
+                )
+                .to_sql(),
+            ),
+        )
+    }
+}
+
+struct MutationBacklog;
+
+// A mutation still running after an hour is competing with merges.
+const MAX_MUTATION_AGE_SECS: u64 = 3600;
+
+impl LiveRule for MutationBacklog {
+    fn id(&self) -> &'static str {
+        "live-mutation-backlog"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+    fn category(&self) -> Category {
+        Category::Scaling
+    }
+    fn evaluate(&self, table: &TableName, health: &TableHealth) -> Option<Finding> {
+        let pending = &health.pending_mutations;
+        let stuck: Vec<_> = pending
+            .iter()
+            .filter(|m| m.age_seconds > MAX_MUTATION_AGE_SECS || !m.latest_fail_reason.is_empty())
+            .collect();
+        let oldest = stuck.iter().max_by_key(|m| m.age_seconds)?;
+        let parts: u64 = pending.iter().map(|m| m.parts_to_do).sum();
+        let finding = self.finding(
+            format!(
+                "{} pending mutation(s) with {parts} parts left; {} has run for {}h",
+                pending.len(),
+                oldest.mutation_id,
+                oldest.age_seconds / 3600
+            ),
+            "Mutations rewrite whole parts and share the merge pool, so a backlog slows \
+             merges and ingestion. Failing mutations retry forever until killed.",
         );
+        match stuck.iter().find(|m| !m.latest_fail_reason.is_empty()) {
+            Some(failed) => Some(finding.with_sql(format!(
+                "KILL MUTATION WHERE {} AND mutation_id = {};",
+                table.system_filter("table"),
+                string_literal(&failed.mutation_id)
+            ))),
+            None => Some(finding),
+        }
     }
+}
 
-    // Always recommend compression guidance
-    recommendations.push(
-        "Use `LZ4` compression (ClickHouse default) — switch to `ZSTD` if storage-constrained"
-            .to_string(),
-    );
+struct ReplicationLag;
 
-    recommendations
+// Beyond five minutes, replicas serve visibly stale dashboards.
+const MAX_REPLICA_DELAY_SECS: u64 = 300;
+
+impl LiveRule for ReplicationLag {
+    fn id(&self) -> &'static str {
+        "live-replication-lag"
+    }
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+    fn category(&self) -> Category {
+        Category::Scaling
+    }
+    fn evaluate(&self, table: &TableName, health: &TableHealth) -> Option<Finding> {
+        if let Some(replica) = health.replicas.iter().find(|r| r.is_readonly != 0) {
+            let finding = self.finding(
+                format!("Replica {} is read-only", replica.replica_name),
+                "A read-only replica has lost its Keeper session or metadata and accepts \
+                 no inserts until it reconnects.",
+            );
+            return Some(finding.with_sql(format!("SYSTEM RESTART REPLICA {table};")));
+        }
+        let delay = health.max_replica_delay();
+        if delay <= MAX_REPLICA_DELAY_SECS {
+            return None;
+        }
+        let queue: u64 = health.replicas.iter().map(|r| r.queue_size).sum();
+        Some(self.finding(
+            format!("Replication is {delay}s behind with {queue} entries queued"),
+            "Lagging replicas answer queries with stale data. A growing queue usually means \
+             fetches or merges can't keep up: check network, disk and background pool size.",
+        ))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::schema::{EventSchema, EventTable};
+    use crate::config::policy::{Policy, Thresholds};
+    use crate::config::schema::{EventSchema, EventTable, IngestionConfig, QueryPattern};
+    use crate::config::workload::WorkloadProfile;
+    use crate::engine::health::{
+        ColumnCompression, MutationRow, PartitionRow, ReplicaRow, TableHealth,
+    };
+
+    fn thresholds() -> Thresholds {
+        Thresholds::default()
+    }
 
-    fn make_workload(events_per_day: u64, multi_tenant: bool, retention_days: u32) -> (EventSchema, WorkloadProfile) {
+    fn make_workload(
+        events_per_day: u64,
+        multi_tenant: bool,
+        retention_days: u32,
+    ) -> (EventSchema, WorkloadProfile) {
         let schema = EventSchema {
             event_table: EventTable {
                 name: "events".to_string(),
                 multi_tenant,
                 expected_events_per_day: events_per_day,
                 retention_days,
+                ..Default::default()
             },
+            ..Default::default()
         };
         let workload = WorkloadProfile {
             events_per_day,
             multi_tenant,
             retention_days,
+            ..Default::default()
         };
         (schema, workload)
     }
 
+    fn messages(schema: &EventSchema, workload: &WorkloadProfile) -> Vec<String> {
+        analyze(&PlanContext::new(schema, workload, &Policy::default()))
+            .iter()
+            .map(|f| f.to_string())
+            .collect()
+    }
+
     #[test]
     fn low_volume_single_tenant_with_retention() {
         let (schema, workload) = make_workload(1_000_000, false, 30);
-        let recs = analyze(&schema, &workload);
+        let recs = messages(&schema, &workload);
 
-        assert!(recs.iter().any(|r| r.contains("TTL") && r.contains("30 DAY")));
+        assert!(
+            recs.iter()
+                .any(|r| r.contains("TTL") && r.contains("30 DAY"))
+        );
         assert!(recs.iter().any(|r| r.contains("LZ4")));
-        assert!(!recs.iter().any(|r| r.contains("wide_parts_only")));
+        assert!(!recs.iter().any(|r| r.contains("min_bytes_for_wide_part")));
         assert!(!recs.iter().any(|r| r.contains("sharding")));
         assert!(!recs.iter().any(|r| r.contains("project_id")));
     }
@@ -95,7 +659,7 @@ mod tests {
     #[test]
     fn no_retention_warns_unbounded() {
         let (schema, workload) = make_workload(1_000_000, false, 0);
-        let recs = analyze(&schema, &workload);
+        let recs = messages(&schema, &workload);
 
         assert!(recs.iter().any(|r| r.contains("unbounded")));
         assert!(!recs.iter().any(|r| r.contains("TTL timestamp")));
@@ -103,26 +667,26 @@ mod tests {
 
     #[test]
     fn high_volume_enables_wide_parts() {
-        let (schema, workload) = make_workload(HIGH_VOLUME_THRESHOLD, false, 90);
-        let recs = analyze(&schema, &workload);
+        let (schema, workload) = make_workload(thresholds().high_volume, false, 90);
+        let recs = messages(&schema, &workload);
 
-        assert!(recs.iter().any(|r| r.contains("wide_parts_only")));
+        assert!(recs.iter().any(|r| r.contains("min_bytes_for_wide_part")));
         assert!(!recs.iter().any(|r| r.contains("sharding")));
     }
 
     #[test]
     fn very_high_volume_recommends_sharding() {
-        let (schema, workload) = make_workload(SHARDING_THRESHOLD, false, 90);
-        let recs = analyze(&schema, &workload);
+        let (schema, workload) = make_workload(thresholds().sharding, false, 90);
+        let recs = messages(&schema, &workload);
 
-        assert!(recs.iter().any(|r| r.contains("wide_parts_only")));
+        assert!(recs.iter().any(|r| r.contains("min_bytes_for_wide_part")));
         assert!(recs.iter().any(|r| r.contains("sharding")));
     }
 
     #[test]
     fn multi_tenant_low_volume_gets_low_cardinality_only() {
         let (schema, workload) = make_workload(1_000_000, true, 90);
-        let recs = analyze(&schema, &workload);
+        let recs = messages(&schema, &workload);
 
         assert!(recs.iter().any(|r| r.contains("LowCardinality")));
         assert!(!recs.iter().any(|r| r.contains("projection")));
@@ -130,50 +694,269 @@ mod tests {
 
     #[test]
     fn multi_tenant_above_projection_threshold() {
-        let (schema, workload) = make_workload(TENANT_PROJECTION_THRESHOLD, true, 90);
-        let recs = analyze(&schema, &workload);
+        let (schema, workload) = make_workload(thresholds().tenant_projection, true, 90);
+        let recs = messages(&schema, &workload);
 
-        assert!(recs.iter().any(|r| r.contains("projection") && r.contains("project_id")));
+        assert!(
+            recs.iter()
+                .any(|r| r.contains("projection") && r.contains("project_id"))
+        );
         assert!(recs.iter().any(|r| r.contains("LowCardinality")));
     }
 
     #[test]
     fn compression_always_recommended() {
         let (schema, workload) = make_workload(1, false, 0);
-        let recs = analyze(&schema, &workload);
+        let recs = messages(&schema, &workload);
 
         assert!(recs.iter().any(|r| r.contains("LZ4") && r.contains("ZSTD")));
     }
 
     #[test]
     fn below_threshold_no_high_volume_recs() {
-        let (schema, workload) = make_workload(HIGH_VOLUME_THRESHOLD - 1, false, 90);
-        let recs = analyze(&schema, &workload);
+        let (schema, workload) = make_workload(thresholds().high_volume - 1, false, 90);
+        let recs = messages(&schema, &workload);
 
-        assert!(!recs.iter().any(|r| r.contains("wide_parts_only")));
+        assert!(!recs.iter().any(|r| r.contains("min_bytes_for_wide_part")));
         assert!(!recs.iter().any(|r| r.contains("sharding")));
     }
 
     #[test]
     fn multi_tenant_below_projection_threshold() {
-        let (schema, workload) = make_workload(TENANT_PROJECTION_THRESHOLD - 1, true, 90);
-        let recs = analyze(&schema, &workload);
+        let (schema, workload) = make_workload(thresholds().tenant_projection - 1, true, 90);
+        let recs = messages(&schema, &workload);
 
         assert!(recs.iter().any(|r| r.contains("LowCardinality")));
         assert!(!recs.iter().any(|r| r.contains("projection")));
     }
 
+    #[test]
+    fn small_inserts_add_batching_advice() {
+        let (schema, mut workload) = make_workload(1_000_000, false, 30);
+        workload.ingestion = Some(IngestionConfig {
+            producers: Some(4),
+            insert_interval_ms: Some(100),
+            ..Default::default()
+        });
+        let recs = messages(&schema, &workload);
+
+        assert!(recs.iter().any(|r| r.contains("Too many parts")));
+        assert!(recs.iter().any(|r| r.contains("async_insert")));
+    }
+
     #[test]
     fn all_rules_fire_for_max_workload() {
-        let (schema, workload) = make_workload(SHARDING_THRESHOLD, true, 365);
-        let recs = analyze(&schema, &workload);
+        let (schema, workload) = make_workload(thresholds().sharding, true, 365);
+        let recs = messages(&schema, &workload);
 
         assert!(recs.iter().any(|r| r.contains("TTL")));
-        assert!(recs.iter().any(|r| r.contains("wide_parts_only")));
+        assert!(recs.iter().any(|r| r.contains("min_bytes_for_wide_part")));
         assert!(recs.iter().any(|r| r.contains("sharding")));
         assert!(recs.iter().any(|r| r.contains("projection")));
         assert!(recs.iter().any(|r| r.contains("LowCardinality")));
         assert!(recs.iter().any(|r| r.contains("LZ4")));
         assert_eq!(recs.len(), 6);
     }
+
+    #[test]
+    fn rule_ids_are_unique() {
+        let mut ids: Vec<&str> = registry().iter().map(|r| r.id()).collect();
+        ids.extend(live_registry().iter().map(|r| r.id()));
+        let total = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), total);
+    }
+
+    #[test]
+    fn disabled_rules_are_skipped() {
+        let (mut schema, workload) = make_workload(thresholds().sharding, false, 90);
+        schema.rules.insert("sharding".to_string(), false);
+        schema.rules.insert("compression".to_string(), true);
+        let recs = messages(&schema, &workload);
+
+        assert!(!recs.iter().any(|r| r.contains("sharding")));
+        assert!(recs.iter().any(|r| r.contains("min_bytes_for_wide_part")));
+        assert!(recs.iter().any(|r| r.contains("LZ4")));
+    }
+
+    #[test]
+    fn findings_carry_structured_data() {
+        let (schema, workload) = make_workload(thresholds().tenant_projection, true, 30);
+        let findings = analyze(&PlanContext::new(&schema, &workload, &Policy::default()));
+
+        let ttl = findings
+            .iter()
+            .find(|f| f.rule_id == "retention-ttl")
+            .unwrap();
+        assert_eq!(ttl.severity, Severity::Info);
+        assert_eq!(ttl.category, Category::Retention);
+        assert!(!ttl.rationale.is_empty());
+        assert_eq!(
+            ttl.suggested_sql.as_deref(),
+            Some("ALTER TABLE events MODIFY TTL timestamp + INTERVAL 30 DAY;")
+        );
+
+        let projection = findings
+            .iter()
+            .find(|f| f.rule_id == "tenant-projection")
+            .unwrap();
+        assert!(
+            projection
+                .suggested_sql
+                .as_ref()
+                .unwrap()
+                .contains("ADD PROJECTION")
+        );
+    }
+
+    #[test]
+    fn unbounded_retention_is_a_warning() {
+        let (schema, workload) = make_workload(1_000_000, false, 0);
+        let findings = analyze(&PlanContext::new(&schema, &workload, &Policy::default()));
+        let unbounded = findings
+            .iter()
+            .find(|f| f.rule_id == "unbounded-retention")
+            .unwrap();
+        assert_eq!(unbounded.severity, Severity::Warning);
+    }
+
+    #[test]
+    fn policy_overrides_sharding_threshold() {
+        let (schema, workload) = make_workload(300_000_000, false, 90);
+        let mut policy = Policy::default();
+        policy.thresholds.sharding = 250_000_000;
+        let findings = analyze(&PlanContext::new(&schema, &workload, &policy));
+
+        let sharding = findings.iter().find(|f| f.rule_id == "sharding").unwrap();
+        assert!(sharding.rationale.contains("250M+"));
+        assert!(
+            sharding
+                .rationale
+                .contains("thresholds.sharding = 250000000")
+        );
+    }
+
+    #[test]
+    fn unserved_query_pattern_suggests_skip_index() {
+        let (schema, mut workload) = make_workload(1_000_000, true, 30);
+        workload.query_patterns = vec![
+            QueryPattern {
+                equality: vec!["project_id".to_string()],
+                share: 0.7,
+                ..Default::default()
+            },
+            QueryPattern {
+                equality: vec!["distinct_id".to_string()],
+                share: 0.3,
+                p95_ms: 1200,
+                ..Default::default()
+            },
+        ];
+        let findings = analyze(&PlanContext::new(&schema, &workload, &Policy::default()));
+        let unserved = findings
+            .iter()
+            .find(|f| f.rule_id == "unserved-query-pattern")
+            .unwrap();
+        assert!(
+            unserved
+                .message
+                .starts_with("30% of logged queries filter on (distinct_id)")
+        );
+        assert!(
+            unserved
+                .suggested_sql
+                .as_deref()
+                .unwrap()
+                .contains("ADD INDEX idx_distinct_id distinct_id TYPE bloom_filter(0.01)")
+        );
+    }
+
+    #[test]
+    fn rare_unserved_patterns_are_ignored() {
+        let (schema, mut workload) = make_workload(1_000_000, true, 30);
+        workload.query_patterns = vec![QueryPattern {
+            equality: vec!["distinct_id".to_string()],
+            share: 0.05,
+            ..Default::default()
+        }];
+        let recs = messages(&schema, &workload);
+        assert!(!recs.iter().any(|r| r.contains("logged queries")));
+    }
+
+    #[test]
+    fn patterns_without_filters_are_not_unserved() {
+        let (schema, mut workload) = make_workload(1_000_000, true, 30);
+        workload.query_patterns = vec![QueryPattern {
+            share: 0.5,
+            ..Default::default()
+        }];
+        let recs = messages(&schema, &workload);
+        assert!(!recs.iter().any(|r| r.contains("logged queries")));
+    }
+
+    fn live_findings(health: &TableHealth) -> Vec<Finding> {
+        analyze_health(&TableName::new("events"), health)
+    }
+
+    #[test]
+    fn live_rules_pass_a_quiet_table() {
+        assert!(live_findings(&TableHealth::default()).is_empty());
+    }
+
+    #[test]
+    fn live_rules_flag_unhealthy_tables() {
+        let health = TableHealth {
+            busiest_partition: Some(PartitionRow {
+                partition_id: "20240301".to_string(),
+                parts: 450,
+                rows: 1,
+            }),
+            columns: vec![ColumnCompression {
+                name: "properties".to_string(),
+                compressed: 900 * 1024 * 1024,
+                uncompressed: 1200 * 1024 * 1024,
+            }],
+            pending_mutations: vec![MutationRow {
+                mutation_id: "mutation_9'.txt".to_string(),
+                command: "DELETE WHERE 1".to_string(),
+                age_seconds: 60,
+                parts_to_do: 12,
+                latest_fail_reason: "Memory limit exceeded".to_string(),
+            }],
+            replicas: vec![ReplicaRow {
+                replica_name: "r2".to_string(),
+                is_readonly: 0,
+                absolute_delay: 900,
+                queue_size: 40,
+            }],
+            ..Default::default()
+        };
+        let findings = live_findings(&health);
+        let ids: Vec<&str> = findings.iter().map(|f| f.rule_id).collect();
+        assert_eq!(
+            ids,
+            [
+                "live-parts-per-partition",
+                "live-compression",
+                "live-mutation-backlog",
+                "live-replication-lag"
+            ]
+        );
+        assert_eq!(
+            findings[1].suggested_sql.as_deref(),
+            Some("ALTER TABLE events MODIFY COLUMN properties CODEC(ZSTD(3));")
+        );
+        assert_eq!(
+            findings[2].suggested_sql.as_deref(),
+            Some(
+                "KILL MUTATION WHERE database = currentDatabase() AND table = 'events' \
+                 AND mutation_id = 'mutation_9\\'.txt';"
+            )
+        );
+        assert_eq!(
+            findings[3].message,
+            "Replication is 900s behind with 40 entries queued"
+        );
+    }
 }
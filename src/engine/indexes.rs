@@ -0,0 +1,70 @@
+use crate::config::schema::SkipIndexConfig;
+use crate::engine::queries::Filter;
+use crate::sql::ast::{AlterCommand, Identifier, Statement, TableName};
+
+pub fn add_index(table_name: &TableName, index: &SkipIndexConfig) -> Statement {
+    Statement::Alter {
+        table: table_name.clone(),
+        command: AlterCommand::AddIndex {
+            name: Identifier::new(&index.name),
+            expression: index.expression.clone(),
+            index_type: index.index_type.clone(),
+            granularity: index.granularity,
+        },
+    }
+}
+
+// minmax keeps per-block bounds, so it helps ranges; the set and bloom filter
+// families only answer "might this block contain the value?".
+fn supports_range(index_type: &str) -> bool {
+    index_type.starts_with("minmax")
+}
+
+pub fn covers(index: &SkipIndexConfig, filter: &Filter) -> bool {
+    match filter {
+        Filter::Eq(col) => index.expression == *col,
+        Filter::Range(col) => index.expression == *col && supports_range(&index.index_type),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(expression: &str, index_type: &str) -> SkipIndexConfig {
+        SkipIndexConfig {
+            name: "idx".to_string(),
+            expression: expression.to_string(),
+            index_type: index_type.to_string(),
+            granularity: 4,
+        }
+    }
+
+    #[test]
+    fn renders_add_index() {
+        assert_eq!(
+            add_index(
+                &TableName::new("events"),
+                &index("distinct_id", "bloom_filter(0.01)")
+            )
+            .to_sql(),
+            "ALTER TABLE events\nADD INDEX idx distinct_id TYPE bloom_filter(0.01) GRANULARITY 4;"
+        );
+    }
+
+    #[test]
+    fn bloom_filter_covers_equality_only() {
+        let idx = index("distinct_id", "bloom_filter(0.01)");
+        assert!(covers(&idx, &Filter::Eq("distinct_id")));
+        assert!(!covers(&idx, &Filter::Range("distinct_id")));
+        assert!(!covers(&idx, &Filter::Eq("event_name")));
+    }
+
+    #[test]
+    fn minmax_covers_ranges() {
+        assert!(covers(
+            &index("ingested_at", "minmax"),
+            &Filter::Range("ingested_at")
+        ));
+    }
+}
@@ -0,0 +1,510 @@
+use crate::config::schema::{
+    DataProfile, EventTable, PropertyShape, PropertyType, TimestampConfig,
+};
+use crate::engine::timestamp::days_from_civil;
+use crate::errors::SharpError;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::BufRead;
+
+const TIMESTAMP_FIELDS: &[&str] = &["timestamp", "ts", "time", "event_time", "created_at"];
+const EVENT_FIELDS: &[&str] = &["event_name", "event", "name", "type"];
+const TENANT_FIELDS: &[&str] = &["project_id", "tenant_id", "team_id", "org_id", "account_id"];
+const USER_FIELDS: &[&str] = &["distinct_id", "user_id", "anonymous_id"];
+// Past this many distinct values per property we stop tracking and report the
+// cardinality as a lower bound; the exact figure no longer changes the schema.
+const MAX_TRACKED_VALUES: usize = 10_000;
+const MAX_EVENT_NAMES: usize = 50;
+// Retention can't be read off a sample; this is a placeholder to edit.
+const PLACEHOLDER_RETENTION_DAYS: u32 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimestampFormat {
+    EpochSeconds,
+    EpochMillis,
+    EpochMicros,
+    EpochNanos,
+    Iso8601,
+}
+
+impl TimestampFormat {
+    fn describe(self) -> &'static str {
+        match self {
+            Self::EpochSeconds => "epoch seconds",
+            Self::EpochMillis => "epoch milliseconds",
+            Self::EpochMicros => "epoch microseconds",
+            Self::EpochNanos => "epoch nanoseconds",
+            Self::Iso8601 => "ISO 8601",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ParsedTimestamp {
+    secs: f64,
+    format: TimestampFormat,
+    precision: u8,
+    // Z or +00:00 suffix.
+    utc: bool,
+}
+
+#[derive(Debug)]
+pub struct Inference {
+    pub table: EventTable,
+    pub profile: DataProfile,
+    pub events: u64,
+    pub span_secs: f64,
+    // What was detected and what was guessed, for the YAML header.
+    pub notes: Vec<String>,
+}
+
+#[derive(Default)]
+struct PropertyStats {
+    values: HashSet<String>,
+    saturated: bool,
+    seen: u64,
+    kind: Option<PropertyType>,
+}
+
+fn sample_error(message: impl Into<String>) -> SharpError {
+    SharpError::SampleError(message.into())
+}
+
+fn parse_iso(s: &str) -> Option<ParsedTimestamp> {
+    let num = |range: std::ops::Range<usize>| s.get(range)?.parse::<u32>().ok();
+    if s.len() < 19 || s.as_bytes()[4] != b'-' || !matches!(s.as_bytes()[10], b'T' | b' ') {
+        return None;
+    }
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+
+    let mut rest = &s[19..];
+    let mut fraction = 0.0;
+    let mut precision = 0u8;
+    if let Some(frac) = rest.strip_prefix('.') {
+        let digits = frac.chars().take_while(char::is_ascii_digit).count();
+        fraction = format!("0.{}", &frac[..digits]).parse().ok()?;
+        precision = digits.min(9) as u8;
+        rest = &frac[digits..];
+    }
+
+    let (utc, offset_secs) = match rest {
+        "" => (false, 0),
+        "Z" | "+00:00" | "+0000" => (true, 0),
+        _ => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let digits: String = rest[1..].chars().filter(char::is_ascii_digit).collect();
+            let hours: i64 = digits.get(0..2)?.parse().ok()?;
+            let minutes: i64 = digits.get(2..4).unwrap_or("0").parse().ok()?;
+            (false, sign * (hours * 3600 + minutes * 60))
+        }
+    };
+
+    let days = days_from_civil(i64::from(year), month, day);
+    let secs = days * 86_400 + i64::from(hour * 3600 + minute * 60 + second) - offset_secs;
+    Some(ParsedTimestamp {
+        secs: secs as f64 + fraction,
+        format: TimestampFormat::Iso8601,
+        precision,
+        utc,
+    })
+}
+
+// Epoch values are told apart by magnitude: seconds stay below 1e11 until
+// the year 5138.
+fn parse_epoch(value: f64, fraction_digits: u8) -> ParsedTimestamp {
+    let (format, divisor, precision) = match value.abs() {
+        v if v < 1e11 => (TimestampFormat::EpochSeconds, 1.0, fraction_digits),
+        v if v < 1e14 => (TimestampFormat::EpochMillis, 1e3, 3),
+        v if v < 1e17 => (TimestampFormat::EpochMicros, 1e6, 6),
+        _ => (TimestampFormat::EpochNanos, 1e9, 9),
+    };
+    ParsedTimestamp {
+        secs: value / divisor,
+        format,
+        precision: precision.min(9),
+        utc: true,
+    }
+}
+
+fn parse_timestamp(value: &Value) -> Option<ParsedTimestamp> {
+    match value {
+        Value::Number(n) => {
+            let text = n.to_string();
+            let digits = text.split_once('.').map_or(0, |(_, f)| f.len()) as u8;
+            Some(parse_epoch(n.as_f64()?, digits))
+        }
+        Value::String(s) => match s.parse::<f64>() {
+            Ok(v) => {
+                let digits = s.split_once('.').map_or(0, |(_, f)| f.len()) as u8;
+                Some(parse_epoch(v, digits))
+            }
+            Err(_) => parse_iso(s),
+        },
+        _ => None,
+    }
+}
+
+fn value_kind(value: &Value) -> PropertyType {
+    match value {
+        Value::Bool(_) => PropertyType::Bool,
+        Value::Number(n) if n.is_i64() || n.is_u64() => PropertyType::Int,
+        Value::Number(_) => PropertyType::Float,
+        _ => PropertyType::String,
+    }
+}
+
+// Ints widen to floats; any other mix is stored as a string.
+fn merge_kind(a: PropertyType, b: PropertyType) -> PropertyType {
+    match (a, b) {
+        (a, b) if a == b => a,
+        (PropertyType::Int, PropertyType::Float) | (PropertyType::Float, PropertyType::Int) => {
+            PropertyType::Float
+        }
+        _ => PropertyType::String,
+    }
+}
+
+fn find_field<'a>(
+    object: &'a Map<String, Value>,
+    candidates: &[&'static str],
+) -> Option<(&'static str, &'a Value)> {
+    candidates
+        .iter()
+        .find_map(|name| object.get(*name).map(|v| (*name, v)))
+}
+
+fn key_of(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// Zipf exponent s for which the biggest of `n` tenants holds `top_share` of
+// events (share of rank 1 is 1 / H(n, s)).
+fn fit_zipf(top_share: f64, n: usize) -> f64 {
+    if n <= 1 || top_share <= 1.0 / n as f64 {
+        return 0.0;
+    }
+    let share = |s: f64| 1.0 / (1..=n).map(|k| (k as f64).powf(-s)).sum::<f64>();
+    let (mut lo, mut hi) = (0.0, 5.0);
+    for _ in 0..50 {
+        let mid = (lo + hi) / 2.0;
+        if share(mid) < top_share {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    ((lo + hi) / 2.0 * 100.0).round() / 100.0
+}
+
+fn round3(v: f64) -> f64 {
+    (v * 1000.0).round() / 1000.0
+}
+
+pub fn infer(reader: impl BufRead, name: &str) -> Result<Inference, SharpError> {
+    let mut events = 0u64;
+    let mut bytes = 0u64;
+    let mut min_ts = f64::MAX;
+    let mut max_ts = f64::MIN;
+    let mut ts_field = None;
+    let mut ts_format = None;
+    let mut precision = 0u8;
+    let mut all_utc = true;
+    let mut event_field = None;
+    let mut tenant_field = None;
+    let mut event_counts: HashMap<String, u64> = HashMap::new();
+    let mut tenant_counts: HashMap<String, u64> = HashMap::new();
+    let mut properties: BTreeMap<String, PropertyStats> = BTreeMap::new();
+
+    for (lineno, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| sample_error(format!("line {}: {e}", lineno + 1)))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row: Value = serde_json::from_str(&line)
+            .map_err(|e| sample_error(format!("line {}: {e}", lineno + 1)))?;
+        let Value::Object(object) = row else {
+            return Err(sample_error(format!(
+                "line {}: not a JSON object",
+                lineno + 1
+            )));
+        };
+        events += 1;
+        bytes += line.len() as u64;
+
+        if let Some((field, value)) = find_field(&object, TIMESTAMP_FIELDS) {
+            let ts = parse_timestamp(value).ok_or_else(|| {
+                sample_error(format!(
+                    "line {}: can't read `{field}` as a timestamp",
+                    lineno + 1
+                ))
+            })?;
+            ts_field = Some(field);
+            ts_format.get_or_insert(ts.format);
+            precision = precision.max(ts.precision);
+            all_utc &= ts.utc;
+            min_ts = min_ts.min(ts.secs);
+            max_ts = max_ts.max(ts.secs);
+        }
+        if let Some((field, value)) = find_field(&object, EVENT_FIELDS) {
+            event_field = Some(field);
+            *event_counts.entry(key_of(value)).or_default() += 1;
+        }
+        if let Some((field, value)) = find_field(&object, TENANT_FIELDS) {
+            tenant_field = Some(field);
+            *tenant_counts.entry(key_of(value)).or_default() += 1;
+        }
+
+        // A nested `properties` object is the usual shape; otherwise every
+        // field we didn't recognise is treated as a property.
+        let props: Vec<(&String, &Value)> = match object.get("properties") {
+            Some(Value::Object(p)) => p.iter().collect(),
+            _ => object
+                .iter()
+                .filter(|(k, _)| {
+                    ![TIMESTAMP_FIELDS, EVENT_FIELDS, TENANT_FIELDS, USER_FIELDS]
+                        .iter()
+                        .any(|known| known.contains(&k.as_str()))
+                })
+                .collect(),
+        };
+        for (key, value) in props {
+            if value.is_null() {
+                continue;
+            }
+            let stats = properties.entry(key.clone()).or_default();
+            stats.seen += 1;
+            let kind = value_kind(value);
+            stats.kind = Some(stats.kind.map_or(kind, |k| merge_kind(k, kind)));
+            if !stats.saturated {
+                stats.values.insert(key_of(value));
+                stats.saturated = stats.values.len() >= MAX_TRACKED_VALUES;
+            }
+        }
+    }
+
+    if events == 0 {
+        return Err(sample_error("no events in sample"));
+    }
+    let (Some(ts_field), Some(ts_format)) = (ts_field, ts_format) else {
+        return Err(sample_error(format!(
+            "no timestamp field found (looked for {})",
+            TIMESTAMP_FIELDS.join(", ")
+        )));
+    };
+
+    let mut notes = vec![format!(
+        "timestamp: `{ts_field}` as {}, {} fractional digit(s)",
+        ts_format.describe(),
+        precision
+    )];
+
+    let span_secs = max_ts - min_ts;
+    let events_per_day = if span_secs >= 1.0 {
+        (events as f64 * 86_400.0 / span_secs).round() as u64
+    } else {
+        notes.push("all events share one timestamp; events/day is the sample size".to_string());
+        events
+    };
+    if (1.0..3600.0).contains(&span_secs) {
+        notes.push(format!(
+            "sample spans only {span_secs:.0}s; expected_events_per_day is a rough extrapolation"
+        ));
+    }
+
+    match event_field {
+        Some(field) => notes.push(format!("event name: `{field}`")),
+        None => notes.push("no event name field found".to_string()),
+    }
+    let multi_tenant = tenant_counts.len() > 1;
+    if let Some(field) = tenant_field {
+        notes.push(format!(
+            "tenant: `{field}` ({} distinct)",
+            tenant_counts.len()
+        ));
+    }
+    notes.push(format!(
+        "retention_days is a placeholder ({PLACEHOLDER_RETENTION_DAYS}); set it to your policy"
+    ));
+
+    let mut event_names: Vec<(String, u64)> = event_counts.into_iter().collect();
+    event_names.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    event_names.truncate(MAX_EVENT_NAMES);
+
+    let tenant_skew = if multi_tenant {
+        let top = *tenant_counts.values().max().unwrap_or(&0) as f64 / events as f64;
+        Some(fit_zipf(top, tenant_counts.len()))
+    } else {
+        None
+    };
+
+    let properties = properties
+        .into_iter()
+        .map(|(name, stats)| PropertyShape {
+            name,
+            kind: stats.kind.unwrap_or(PropertyType::String),
+            cardinality: stats.values.len() as u64,
+            presence: round3(stats.seen as f64 / events as f64),
+        })
+        .collect();
+
+    let timestamp = TimestampConfig {
+        precision,
+        timezone: all_utc.then(|| "UTC".to_string()),
+        ingested_at: false,
+    };
+
+    Ok(Inference {
+        table: EventTable {
+            name: name.to_string(),
+            multi_tenant,
+            expected_events_per_day: events_per_day,
+            retention_days: PLACEHOLDER_RETENTION_DAYS,
+            timestamp,
+            ..Default::default()
+        },
+        profile: DataProfile {
+            avg_row_bytes: Some(bytes / events),
+            tenants: multi_tenant.then_some(tenant_counts.len() as u32),
+            tenant_skew,
+            event_names: event_names
+                .into_iter()
+                .map(|(name, count)| (name, round3(count as f64 / events as f64)))
+                .collect(),
+            properties,
+        },
+        events,
+        span_secs,
+        notes,
+    })
+}
+
+#[derive(Serialize)]
+struct Draft<'a> {
+    event_table: &'a EventTable,
+    data_profile: &'a DataProfile,
+}
+
+pub fn to_yaml(inference: &Inference, source: &str) -> String {
+    let draft = Draft {
+        event_table: &inference.table,
+        data_profile: &inference.profile,
+    };
+    let yaml = serde_yaml::to_string(&draft).expect("draft schema is always serializable");
+    let mut header = format!(
+        "# Draft inferred by `sharp infer` from {source}: {} events over {:.1}h\n",
+        inference.events,
+        inference.span_secs / 3600.0
+    );
+    for note in &inference.notes {
+        header.push_str(&format!("# - {note}\n"));
+    }
+    format!("{header}{yaml}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_schema;
+
+    fn run(sample: &str) -> Inference {
+        infer(sample.as_bytes(), "events").unwrap()
+    }
+
+    #[test]
+    fn parses_iso_timestamps() {
+        let ts = parse_iso("2024-03-01T12:00:00.123Z").unwrap();
+        assert_eq!(ts.secs, 1_709_294_400.123);
+        assert_eq!(ts.precision, 3);
+        assert!(ts.utc);
+
+        let offset = parse_iso("2024-03-01 14:00:00+02:00").unwrap();
+        assert_eq!(offset.secs, 1_709_294_400.0);
+        assert!(!offset.utc);
+    }
+
+    #[test]
+    fn detects_epoch_units_by_magnitude() {
+        assert_eq!(
+            parse_epoch(1_709_294_400.0, 0).format,
+            TimestampFormat::EpochSeconds
+        );
+        let millis = parse_epoch(1_709_294_400_123.0, 0);
+        assert_eq!(millis.format, TimestampFormat::EpochMillis);
+        assert_eq!(millis.precision, 3);
+        assert_eq!(parse_epoch(1_709_294_400_123_456.0, 0).precision, 6);
+    }
+
+    #[test]
+    fn extrapolates_events_per_day_from_span() {
+        // 4 events over 6 hours -> 16/day
+        let sample = "\
+{\"ts\": 1709251200000, \"event\": \"view\"}
+{\"ts\": 1709258400000, \"event\": \"view\"}
+{\"ts\": 1709265600000, \"event\": \"click\"}
+{\"ts\": 1709272800000, \"event\": \"view\"}
+";
+        let inference = run(sample);
+        assert_eq!(inference.table.expected_events_per_day, 16);
+        assert_eq!(inference.table.timestamp.precision, 3);
+        assert_eq!(inference.table.timestamp.timezone.as_deref(), Some("UTC"));
+        assert_eq!(inference.profile.event_names["view"], 0.75);
+    }
+
+    #[test]
+    fn profiles_properties() {
+        let sample = "\
+{\"timestamp\": \"2024-03-01T00:00:00Z\", \"project_id\": 1, \"properties\": {\"plan\": \"pro\", \"items\": 2}}
+{\"timestamp\": \"2024-03-01T01:00:00Z\", \"project_id\": 1, \"properties\": {\"plan\": \"free\", \"items\": 2.5}}
+{\"timestamp\": \"2024-03-01T02:00:00Z\", \"project_id\": 2, \"properties\": {\"plan\": \"pro\", \"beta\": true}}
+";
+        let inference = run(sample);
+        let props = &inference.profile.properties;
+        let plan = props.iter().find(|p| p.name == "plan").unwrap();
+        assert_eq!(plan.kind, PropertyType::String);
+        assert_eq!(plan.cardinality, 2);
+        assert_eq!(plan.presence, 1.0);
+        let items = props.iter().find(|p| p.name == "items").unwrap();
+        assert_eq!(items.kind, PropertyType::Float);
+        assert_eq!(items.presence, 0.667);
+
+        assert!(inference.table.multi_tenant);
+        assert_eq!(inference.profile.tenants, Some(2));
+    }
+
+    #[test]
+    fn zipf_fit_recovers_uniform_and_skewed() {
+        assert_eq!(fit_zipf(0.25, 4), 0.0);
+        // For s = 1 and n = 10, the top tenant holds 1 / H(10) ≈ 0.3414.
+        assert_eq!(fit_zipf(0.3414, 10), 1.0);
+    }
+
+    #[test]
+    fn requires_a_timestamp() {
+        let err = infer("{\"event\": \"x\"}\n".as_bytes(), "events").unwrap_err();
+        assert!(err.to_string().contains("no timestamp field"));
+    }
+
+    #[test]
+    fn draft_parses_as_schema() {
+        let sample = "\
+{\"ts\": \"2024-03-01 00:00:00.123456\", \"event\": \"a\", \"tenant_id\": \"t1\", \"path\": \"/\"}
+{\"ts\": \"2024-03-01 00:30:00.5\", \"event\": \"b\", \"tenant_id\": \"t2\", \"path\": \"/x\"}
+";
+        let yaml = to_yaml(&run(sample), "sample.ndjson");
+        let schema = parse_schema(&yaml).unwrap();
+        assert_eq!(schema.event_table.timestamp.precision, 6);
+        assert_eq!(schema.event_table.timestamp.timezone, None);
+        let profile = schema.data_profile.unwrap();
+        assert_eq!(profile.properties[0].name, "path");
+        assert_eq!(profile.tenants, Some(2));
+    }
+}
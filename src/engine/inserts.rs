@@ -0,0 +1,231 @@
+use crate::config::workload::WorkloadProfile;
+use crate::engine::partitioning::PartitionStrategy;
+
+// ClickHouse writes a new part for every INSERT (per partition touched).
+// Merges keep up with roughly one insert per second per table; beyond that the
+// active part count climbs towards parts_to_delay_insert / parts_to_throw_insert.
+const MAX_INSERTS_PER_SEC: f64 = 1.0;
+// Tenant partitioning multiplies parts by the tenants in each batch, so the
+// safe insert rate is lower.
+const MAX_TENANT_PARTITIONED_INSERTS_PER_SEC: f64 = 0.5;
+const PARTS_TO_DELAY_INSERT: u32 = 1_000;
+const PARTS_TO_THROW_INSERT: u32 = 3_000;
+// Below this many rows an INSERT costs more in part overhead than in data.
+const SMALL_INSERT_ROWS: u64 = 1_000;
+// With this many producers, per-connection async inserts each hold a server
+// thread waiting for the flush; a Buffer table absorbs them in memory instead.
+const MANY_PRODUCERS: u32 = 50;
+
+#[derive(Debug, PartialEq)]
+pub struct InsertRate {
+    pub inserts_per_sec: f64,
+    pub rows_per_insert: u64,
+}
+
+pub fn insert_rate(workload: &WorkloadProfile) -> Option<InsertRate> {
+    let ingestion = workload.ingestion.as_ref()?;
+    let interval_ms = ingestion.insert_interval_ms?;
+    let producers = ingestion.producers.unwrap_or(1);
+
+    let inserts_per_sec = f64::from(producers) * 1000.0 / interval_ms as f64;
+    let rows_per_insert = ingestion.rows_per_insert.unwrap_or_else(|| {
+        let events_per_sec = workload.events_per_day as f64 / 86_400.0;
+        (events_per_sec / inserts_per_sec).ceil() as u64
+    });
+
+    Some(InsertRate {
+        inserts_per_sec,
+        rows_per_insert,
+    })
+}
+
+fn producers(workload: &WorkloadProfile) -> u32 {
+    workload
+        .ingestion
+        .as_ref()
+        .and_then(|i| i.producers)
+        .unwrap_or(1)
+}
+
+fn max_inserts_per_sec(partition: &PartitionStrategy) -> f64 {
+    match partition {
+        PartitionStrategy::DailyWithTenant => MAX_TENANT_PARTITIONED_INSERTS_PER_SEC,
+        _ => MAX_INSERTS_PER_SEC,
+    }
+}
+
+pub fn parts_warning(workload: &WorkloadProfile, partition: &PartitionStrategy) -> Option<String> {
+    let rate = insert_rate(workload)?;
+
+    let parts_per_insert = match partition {
+        PartitionStrategy::DailyWithTenant => "one part per tenant in the batch",
+        _ => "one part",
+    };
+
+    if rate.inserts_per_sec <= max_inserts_per_sec(partition) {
+        return None;
+    }
+
+    let parts_per_day = (rate.inserts_per_sec * 86_400.0) as u64;
+    Some(format!(
+        "Warn: {:.1} inserts/sec at {parts_per_insert} each (~{parts_per_day} parts/day per \
+         partition) is likely to outrun merges and hit `parts_to_delay_insert` \
+         ({PARTS_TO_DELAY_INSERT}) / `parts_to_throw_insert` ({PARTS_TO_THROW_INSERT}) — \
+         \"Too many parts\"",
+        rate.inserts_per_sec
+    ))
+}
+
+pub fn small_insert_advice(workload: &WorkloadProfile) -> Option<String> {
+    let rate = insert_rate(workload)?;
+    if rate.rows_per_insert >= SMALL_INSERT_ROWS {
+        return None;
+    }
+
+    let producers = producers(workload);
+    if producers >= MANY_PRODUCERS {
+        Some(format!(
+            "Put a `Buffer` table in front of the events table: {producers} producers sending \
+             ~{} rows per insert would tie up a server thread each with async inserts",
+            rate.rows_per_insert
+        ))
+    } else {
+        Some(format!(
+            "Enable `async_insert = 1, wait_for_async_insert = 1` so the server batches \
+             ~{} row inserts into larger parts (tune `async_insert_busy_timeout_ms`)",
+            rate.rows_per_insert
+        ))
+    }
+}
+
+// The flush interval per producer that keeps all producers together at the
+// rate parts_warning accepts for this partitioning.
+fn batch_interval_ms(workload: &WorkloadProfile, partition: &PartitionStrategy) -> u64 {
+    (f64::from(producers(workload)) * 1000.0 / max_inserts_per_sec(partition)).ceil() as u64
+}
+
+pub fn batch_advice(workload: &WorkloadProfile, partition: &PartitionStrategy) -> Option<String> {
+    let rate = insert_rate(workload)?;
+    let max_inserts_per_sec = max_inserts_per_sec(partition);
+    if rate.inserts_per_sec <= max_inserts_per_sec && rate.rows_per_insert >= SMALL_INSERT_ROWS {
+        return None;
+    }
+
+    let producers = producers(workload);
+    let interval_ms = batch_interval_ms(workload, partition);
+    let events_per_sec = workload.events_per_day as f64 / 86_400.0;
+    let batch =
+        ((events_per_sec / f64::from(producers)) * interval_ms as f64 / 1000.0).ceil() as u64;
+    Some(format!(
+        "Batch client-side: flush each of the {producers} producer(s) every {interval_ms} ms \
+         (~{} rows per insert) to stay under {max_inserts_per_sec} insert(s)/sec",
+        batch.max(SMALL_INSERT_ROWS)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::IngestionConfig;
+
+    fn profile(
+        events_per_day: u64,
+        producers: u32,
+        insert_interval_ms: u64,
+        rows_per_insert: Option<u64>,
+    ) -> WorkloadProfile {
+        WorkloadProfile {
+            events_per_day,
+            multi_tenant: false,
+            retention_days: 90,
+            ingestion: Some(IngestionConfig {
+                producers: Some(producers),
+                insert_interval_ms: Some(insert_interval_ms),
+                rows_per_insert,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_rate_without_ingestion_profile() {
+        let w = WorkloadProfile {
+            events_per_day: 1_000_000,
+            ..Default::default()
+        };
+        assert_eq!(insert_rate(&w), None);
+        assert_eq!(parts_warning(&w, &PartitionStrategy::Daily), None);
+        assert_eq!(small_insert_advice(&w), None);
+        assert_eq!(batch_advice(&w, &PartitionStrategy::Daily), None);
+    }
+
+    #[test]
+    fn derives_rows_per_insert_from_volume() {
+        // 86.4M/day = 1000 events/sec, 2 producers flushing every second
+        let rate = insert_rate(&profile(86_400_000, 2, 1000, None)).unwrap();
+        assert_eq!(rate.inserts_per_sec, 2.0);
+        assert_eq!(rate.rows_per_insert, 500);
+    }
+
+    #[test]
+    fn healthy_batching_has_no_recommendations() {
+        let w = profile(86_400_000, 1, 10_000, None);
+        assert_eq!(parts_warning(&w, &PartitionStrategy::Daily), None);
+        assert_eq!(small_insert_advice(&w), None);
+        assert_eq!(batch_advice(&w, &PartitionStrategy::Daily), None);
+    }
+
+    #[test]
+    fn frequent_inserts_flag_parts_limit() {
+        let w = profile(86_400_000, 10, 1000, Some(5_000));
+        let warning = parts_warning(&w, &PartitionStrategy::Daily).unwrap();
+        assert!(warning.contains("Too many parts"));
+        assert!(
+            batch_advice(&w, &PartitionStrategy::Daily)
+                .unwrap()
+                .contains("every 10000 ms")
+        );
+        assert_eq!(small_insert_advice(&w), None);
+    }
+
+    #[test]
+    fn tenant_partitioning_flags_lower_rates() {
+        let w = profile(86_400_000, 1, 1500, Some(5_000));
+        assert_eq!(parts_warning(&w, &PartitionStrategy::Daily), None);
+        let warning = parts_warning(&w, &PartitionStrategy::DailyWithTenant).unwrap();
+        assert!(warning.contains("one part per tenant"));
+    }
+
+    #[test]
+    fn tenant_partitioned_batches_satisfy_the_parts_warning() {
+        let tenant = PartitionStrategy::DailyWithTenant;
+        let w = profile(86_400_000, 4, 1000, Some(5_000));
+        let advice = batch_advice(&w, &tenant).unwrap();
+        assert!(advice.contains("every 8000 ms"));
+        assert!(advice.contains("under 0.5 insert(s)/sec"));
+
+        // Following the advice leaves nothing for parts_warning to flag.
+        let interval = batch_interval_ms(&w, &tenant);
+        let batched = profile(86_400_000, 4, interval, None);
+        assert_eq!(parts_warning(&batched, &tenant), None);
+        assert_eq!(batch_advice(&batched, &tenant), None);
+    }
+
+    #[test]
+    fn small_inserts_get_async_insert() {
+        let w = profile(1_000_000, 2, 5000, Some(10));
+        let advice = small_insert_advice(&w).unwrap();
+        assert!(advice.contains("async_insert = 1"));
+        assert!(!advice.contains("Buffer"));
+        assert!(batch_advice(&w, &PartitionStrategy::Daily).is_some());
+    }
+
+    #[test]
+    fn many_small_producers_get_buffer_table() {
+        let w = profile(1_000_000, MANY_PRODUCERS, 60_000, Some(10));
+        let advice = small_insert_advice(&w).unwrap();
+        assert!(advice.contains("Buffer"));
+        assert!(!advice.contains("async_insert = 1"));
+    }
+}
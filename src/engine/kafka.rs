@@ -0,0 +1,329 @@
+use crate::config::schema::KafkaConfig;
+use crate::config::workload::WorkloadProfile;
+use crate::engine::partitioning::PartitionStrategy;
+use crate::sql::ast::{ColumnExpr, CreateTable, Statement, string_literal};
+
+// Traffic is rarely flat: size consumers for the busy hours, not the daily
+// average.
+const PEAK_FACTOR: u64 = 3;
+// Rough rows/sec a single consumer thread parses from JSONEachRow.
+const EVENTS_PER_CONSUMER_PER_SEC: u64 = 50_000;
+// ClickHouse advises against more consumers than physical cores; 16 is a
+// conservative ceiling for a single ingest node.
+const MAX_CONSUMERS: u64 = 16;
+// kafka_flush_interval_ms default: a block is flushed when it fills up or
+// when this much time has passed, whichever comes first.
+const FLUSH_INTERVAL_SECS: f64 = 7.5;
+const DEFAULT_BLOCK_SIZE: u64 = 65_536;
+const MAX_BLOCK_SIZE: u64 = 1_048_576;
+
+fn peak_events_per_sec(workload: &WorkloadProfile) -> u64 {
+    workload
+        .events_per_day
+        .saturating_mul(PEAK_FACTOR)
+        .div_ceil(86_400)
+}
+
+pub fn recommend_num_consumers(workload: &WorkloadProfile) -> u32 {
+    peak_events_per_sec(workload)
+        .div_ceil(EVENTS_PER_CONSUMER_PER_SEC)
+        .clamp(1, MAX_CONSUMERS) as u32
+}
+
+// Bigger blocks mean fewer, larger inserts. Aim for one block per consumer per
+// flush interval at peak, so blocks fill up right as the flush timer fires.
+pub fn recommend_max_block_size(workload: &WorkloadProfile, num_consumers: u32) -> u64 {
+    let per_consumer = peak_events_per_sec(workload) / u64::from(num_consumers.max(1));
+    let rows_per_flush = (per_consumer as f64 * FLUSH_INTERVAL_SECS) as u64;
+    rows_per_flush
+        .next_power_of_two()
+        .clamp(DEFAULT_BLOCK_SIZE, MAX_BLOCK_SIZE)
+}
+
+pub fn num_consumers(workload: &WorkloadProfile, kafka: &KafkaConfig) -> u32 {
+    kafka
+        .num_consumers
+        .unwrap_or_else(|| recommend_num_consumers(workload))
+}
+
+// The Kafka table mirrors the target's columns minus those filled by DEFAULT
+// (e.g. ingested_at), which the materialized view leaves to the target table.
+fn source_columns(target: &CreateTable) -> Vec<&ColumnExpr> {
+    target
+        .columns
+        .iter()
+        .filter(|c| c.default.is_none())
+        .collect()
+}
+
+pub fn kafka_table(
+    target: &CreateTable,
+    kafka: &KafkaConfig,
+    workload: &WorkloadProfile,
+) -> CreateTable {
+    let consumers = num_consumers(workload, kafka);
+    let columns = source_columns(target)
+        .into_iter()
+        .map(|c| ColumnExpr {
+            name: c.name.clone(),
+            data_type: c.data_type.clone(),
+            default: None,
+        })
+        .collect();
+
+    CreateTable {
+        table_name: target.table_name.with_suffix("_kafka"),
+        columns,
+        engine: "Kafka".to_string(),
+        partition_by: None,
+        order_by: vec![],
+        sample_by: None,
+        ttl: None,
+        settings: vec![
+            (
+                "kafka_broker_list".to_string(),
+                string_literal(&kafka.brokers.join(",")),
+            ),
+            ("kafka_topic_list".to_string(), string_literal(&kafka.topic)),
+            (
+                "kafka_group_name".to_string(),
+                string_literal(&kafka.consumer_group),
+            ),
+            ("kafka_format".to_string(), string_literal(&kafka.format)),
+            ("kafka_num_consumers".to_string(), consumers.to_string()),
+            (
+                "kafka_max_block_size".to_string(),
+                recommend_max_block_size(workload, consumers).to_string(),
+            ),
+        ],
+    }
+}
+
+// The SELECT names the target's columns, so the view needs no column list of
+// its own (one after TO would have to repeat every type).
+pub fn materialized_view(target: &CreateTable) -> Statement {
+    let select = source_columns(target)
+        .iter()
+        .map(|c| c.name.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Statement::CreateMaterializedView {
+        name: target.table_name.with_suffix("_kafka_mv"),
+        to: target.table_name.clone(),
+        columns: vec![],
+        select: format!(
+            "SELECT {select}\nFROM {}",
+            target.table_name.with_suffix("_kafka")
+        ),
+    }
+}
+
+pub fn explain(
+    workload: &WorkloadProfile,
+    kafka: &KafkaConfig,
+    partition: &PartitionStrategy,
+) -> String {
+    let consumers = num_consumers(workload, kafka);
+    let recommended = recommend_num_consumers(workload);
+    let block_size = recommend_max_block_size(workload, consumers);
+    let peak = peak_events_per_sec(workload);
+
+    let sizing = if consumers == recommended {
+        format!(
+            "{consumers} consumer(s) cover a peak of ~{peak} events/sec \
+             ({EVENTS_PER_CONSUMER_PER_SEC}/sec each)."
+        )
+    } else {
+        format!(
+            "{consumers} consumer(s) configured; ~{peak} peak events/sec suggests {recommended}."
+        )
+    };
+
+    // Each flushed block is one INSERT into the target, which writes one part
+    // per partition the block touches.
+    let per_consumer = peak as f64 / f64::from(consumers);
+    let flushes_per_sec = (per_consumer / block_size as f64).max(1.0 / FLUSH_INTERVAL_SECS);
+    let inserts_per_sec = flushes_per_sec * f64::from(consumers);
+    let parts = match partition {
+        PartitionStrategy::DailyWithTenant => format!(
+            " Partitioning by (project_id, day) writes one part per tenant present in each block, \
+             so each of the ~{inserts_per_sec:.1} inserts/sec creates as many parts as there are \
+             active tenants; keep an eye on background merges."
+        ),
+        _ => format!(
+            " Time-based partitioning keeps it at ~1 part per insert (2 around midnight), \
+             so ~{inserts_per_sec:.1} new parts/sec."
+        ),
+    };
+
+    format!(
+        "{sizing} kafka_max_block_size = {block_size} so blocks fill at peak before the flush timer.{parts}"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::ast::{Identifier, TableName};
+    use crate::sql::types::ClickHouseType;
+
+    fn profile(events_per_day: u64) -> WorkloadProfile {
+        WorkloadProfile {
+            events_per_day,
+            multi_tenant: true,
+            retention_days: 90,
+            ..Default::default()
+        }
+    }
+
+    fn kafka(num_consumers: Option<u32>) -> KafkaConfig {
+        KafkaConfig {
+            brokers: vec!["kafka-1:9092".to_string(), "kafka-2:9092".to_string()],
+            topic: "events".to_string(),
+            consumer_group: "sharp".to_string(),
+            format: "JSONEachRow".to_string(),
+            num_consumers,
+        }
+    }
+
+    fn target() -> CreateTable {
+        CreateTable {
+            table_name: TableName::new("user_events"),
+            columns: vec![
+                ColumnExpr {
+                    name: Identifier::new("timestamp"),
+                    data_type: ClickHouseType::DateTime(None),
+                    default: None,
+                },
+                ColumnExpr {
+                    name: Identifier::new("event_name"),
+                    data_type: ClickHouseType::String,
+                    default: None,
+                },
+                ColumnExpr {
+                    name: Identifier::new("ingested_at"),
+                    data_type: ClickHouseType::DateTime(None),
+                    default: Some("now()".to_string()),
+                },
+            ],
+            engine: "MergeTree".to_string(),
+            partition_by: Some("toYYYYMMDD(timestamp)".to_string()),
+            order_by: vec!["timestamp".to_string()],
+            sample_by: None,
+            ttl: None,
+            settings: vec![],
+        }
+    }
+
+    #[test]
+    fn low_volume_needs_one_consumer() {
+        assert_eq!(recommend_num_consumers(&profile(1_000_000)), 1);
+    }
+
+    #[test]
+    fn consumers_scale_with_peak_rate() {
+        // 5B/day * 3 / 86400 ≈ 173.6k events/sec at peak → 4 consumers
+        assert_eq!(recommend_num_consumers(&profile(5_000_000_000)), 4);
+    }
+
+    #[test]
+    fn consumers_capped() {
+        assert_eq!(
+            recommend_num_consumers(&profile(1_000_000_000_000)),
+            MAX_CONSUMERS as u32
+        );
+    }
+
+    #[test]
+    fn block_size_never_below_default() {
+        assert_eq!(
+            recommend_max_block_size(&profile(1_000_000), 1),
+            DEFAULT_BLOCK_SIZE
+        );
+    }
+
+    #[test]
+    fn block_size_grows_with_rate() {
+        // 500M/day peak ≈ 17.4k/sec, × 7.5s ≈ 130k rows → 131072
+        assert_eq!(recommend_max_block_size(&profile(500_000_000), 1), 131_072);
+    }
+
+    #[test]
+    fn block_size_capped() {
+        assert_eq!(
+            recommend_max_block_size(&profile(1_000_000_000_000), 1),
+            MAX_BLOCK_SIZE
+        );
+    }
+
+    #[test]
+    fn configured_consumers_win() {
+        assert_eq!(num_consumers(&profile(1_000_000), &kafka(Some(6))), 6);
+    }
+
+    #[test]
+    fn kafka_table_uses_engine_settings() {
+        let sql = kafka_table(&target(), &kafka(None), &profile(1_000_000)).to_sql();
+        assert!(sql.starts_with("CREATE TABLE user_events_kafka ("));
+        assert!(sql.contains("ENGINE = Kafka"));
+        assert!(sql.contains("kafka_broker_list = 'kafka-1:9092,kafka-2:9092'"));
+        assert!(sql.contains("kafka_topic_list = 'events'"));
+        assert!(sql.contains("kafka_group_name = 'sharp'"));
+        assert!(sql.contains("kafka_format = 'JSONEachRow'"));
+        assert!(sql.contains("kafka_num_consumers = 1"));
+        assert!(sql.contains("kafka_max_block_size = 65536"));
+        assert!(!sql.contains("ORDER BY"));
+        assert!(!sql.contains("PARTITION BY"));
+    }
+
+    #[test]
+    fn kafka_table_skips_default_columns() {
+        let table = kafka_table(&target(), &kafka(None), &profile(1_000_000));
+        assert_eq!(table.columns.len(), 2);
+        assert!(!table.to_sql().contains("ingested_at"));
+    }
+
+    #[test]
+    fn quotes_are_escaped() {
+        assert_eq!(string_literal("it's"), "'it\\'s'");
+    }
+
+    #[test]
+    fn materialized_view_targets_table() {
+        let sql = materialized_view(&target()).to_sql();
+        assert_eq!(
+            sql,
+            "CREATE MATERIALIZED VIEW user_events_kafka_mv TO user_events AS\n\
+             SELECT timestamp, event_name\n\
+             FROM user_events_kafka;"
+        );
+    }
+
+    #[test]
+    fn explain_time_partitioned_parts() {
+        let explanation = explain(&profile(1_000_000), &kafka(None), &PartitionStrategy::Daily);
+        assert!(explanation.contains("1 consumer(s)"));
+        assert!(explanation.contains("~1 part per insert"));
+    }
+
+    #[test]
+    fn explain_tenant_partitioned_parts() {
+        let explanation = explain(
+            &profile(500_000_000),
+            &kafka(None),
+            &PartitionStrategy::DailyWithTenant,
+        );
+        assert!(explanation.contains("one part per tenant"));
+    }
+
+    #[test]
+    fn explain_flags_configured_consumer_mismatch() {
+        let explanation = explain(
+            &profile(1_000_000),
+            &kafka(Some(8)),
+            &PartitionStrategy::Daily,
+        );
+        assert!(explanation.contains("8 consumer(s) configured"));
+        assert!(explanation.contains("suggests 1"));
+    }
+}
@@ -0,0 +1,353 @@
+use crate::engine::heuristics::registry;
+use crate::engine::rules::{Category, Finding, PlanContext, Severity};
+use crate::errors::SharpError;
+
+// Exit codes are part of the CI contract: keep them stable.
+pub const EXIT_CLEAN: i32 = 0;
+pub const EXIT_ERRORS: i32 = 1;
+pub const EXIT_DENIED_WARNINGS: i32 = 2;
+pub const EXIT_INVALID_INPUT: i32 = 3;
+
+const ALLOW_MARKER: &str = "sharp:allow(";
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Passed,
+    Fired(Finding),
+    // Fired, but silenced by a `# sharp:allow(rule-id)` comment.
+    Suppressed(Finding),
+    // Switched off under `rules:` in the schema.
+    Disabled,
+}
+
+#[derive(Debug)]
+pub struct RuleResult {
+    pub rule_id: &'static str,
+    pub severity: Severity,
+    pub category: Category,
+    pub status: Status,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Clean,
+    Errors,
+    DeniedWarnings,
+}
+
+impl Outcome {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Outcome::Clean => EXIT_CLEAN,
+            Outcome::Errors => EXIT_ERRORS,
+            Outcome::DeniedWarnings => EXIT_DENIED_WARNINGS,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LintReport {
+    pub results: Vec<RuleResult>,
+}
+
+impl LintReport {
+    pub fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.results.iter().filter_map(|r| match r.status {
+            Status::Fired(ref f) => Some(f),
+            _ => None,
+        })
+    }
+
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings().filter(|f| f.severity == severity).count()
+    }
+
+    pub fn suppressed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|r| matches!(r.status, Status::Suppressed(_)))
+            .count()
+    }
+
+    pub fn outcome(&self, deny_warnings: bool) -> Outcome {
+        if self.count(Severity::Error) > 0 {
+            Outcome::Errors
+        } else if deny_warnings && self.count(Severity::Warning) > 0 {
+            Outcome::DeniedWarnings
+        } else {
+            Outcome::Clean
+        }
+    }
+}
+
+// A `# sharp:allow(rule-id)` comment and the YAML key it is attached to.
+#[derive(Debug, PartialEq)]
+pub struct Suppression {
+    pub rule_id: String,
+    pub key: String,
+}
+
+// The comment part of a YAML line. A `#` only starts a comment outside quotes
+// and at the start of the line or after whitespace.
+fn comment(line: &str) -> Option<&str> {
+    let mut quote = None;
+    let mut prev = ' ';
+    let mut chars = line.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match quote {
+            Some('"') if c == '\\' => {
+                chars.next();
+            }
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '#' && prev.is_whitespace() => return Some(&line[i + 1..]),
+            None if (c == '"' || c == '\'') && (prev.is_whitespace() || prev == ':') => {
+                quote = Some(c)
+            }
+            None => {}
+        }
+        prev = c;
+    }
+    None
+}
+
+// The mapping key a line starts, if any: `name` for `  name: events` and for
+// `- name: events`.
+fn key(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    let line = line.strip_prefix("- ").unwrap_or(line).trim_start();
+    let (key, rest) = line.split_once(':')?;
+    let key = key.trim();
+    if key.is_empty() || key.starts_with('#') || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some(key)
+}
+
+// Collects `# sharp:allow(rule-a, rule-b)` comments. A trailing comment applies
+// to the key on its own line, a comment line to the next key below it, and the
+// rule must judge that key: `sharp:allow(sharding)` belongs on
+// expected_events_per_day. Ids and keys are checked against the registry so a
+// typo can't silently suppress nothing.
+pub fn suppressions(source: &str) -> Result<Vec<Suppression>, SharpError> {
+    let rules = registry();
+    let known: Vec<&str> = rules.iter().map(|r| r.id()).collect();
+    let lines: Vec<&str> = source.lines().collect();
+    let mut allowed = Vec::new();
+
+    for (lineno, line) in lines.iter().enumerate() {
+        let Some(comment) = comment(line) else {
+            continue;
+        };
+        let Some(start) = comment.find(ALLOW_MARKER) else {
+            continue;
+        };
+        let rest = &comment[start + ALLOW_MARKER.len()..];
+        let Some(end) = rest.find(')') else {
+            return Err(SharpError::InvalidSchema(format!(
+                "line {}: unterminated `sharp:allow(` comment",
+                lineno + 1
+            )));
+        };
+        let target = key(line).or_else(|| lines[lineno + 1..].iter().find_map(|l| key(l)));
+        let Some(target) = target else {
+            return Err(SharpError::InvalidSchema(format!(
+                "line {}: sharp:allow must sit on or above the key it suppresses",
+                lineno + 1
+            )));
+        };
+
+        for id in rest[..end]
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+        {
+            let Some(rule) = rules.iter().find(|r| r.id() == id) else {
+                return Err(SharpError::InvalidSchema(format!(
+                    "line {}: unknown rule '{id}' in sharp:allow (known: {})",
+                    lineno + 1,
+                    known.join(", ")
+                )));
+            };
+            if !rule.keys().contains(&target) {
+                return Err(SharpError::InvalidSchema(format!(
+                    "line {}: '{id}' doesn't judge `{target}`; put sharp:allow({id}) on {}",
+                    lineno + 1,
+                    rule.keys().join(" or ")
+                )));
+            }
+            allowed.push(Suppression {
+                rule_id: id.to_string(),
+                key: target.to_string(),
+            });
+        }
+    }
+    Ok(allowed)
+}
+
+pub fn lint(ctx: &PlanContext, allowed: &[Suppression]) -> LintReport {
+    let results = registry()
+        .iter()
+        .map(|rule| {
+            let status = if ctx.schema.rules.get(rule.id()) == Some(&false) {
+                Status::Disabled
+            } else {
+                match rule.evaluate(ctx) {
+                    None => Status::Passed,
+                    Some(f) if allowed.iter().any(|s| s.rule_id == rule.id()) => {
+                        Status::Suppressed(f)
+                    }
+                    Some(f) => Status::Fired(f),
+                }
+            };
+            RuleResult {
+                rule_id: rule.id(),
+                severity: rule.severity(),
+                category: rule.category(),
+                status,
+            }
+        })
+        .collect();
+    LintReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_schema;
+    use crate::config::policy::Policy;
+    use crate::config::workload::WorkloadProfile;
+
+    const NOISY: &str = "\
+event_table:
+  name: events
+  multi_tenant: true
+  expected_events_per_day: 600000000
+  retention_days: 0
+";
+
+    fn report(source: &str) -> LintReport {
+        let schema = parse_schema(source).unwrap();
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        lint(&ctx, &suppressions(source).unwrap())
+    }
+
+    fn allowed(source: &str) -> Vec<(String, String)> {
+        suppressions(source)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.rule_id, s.key))
+            .collect()
+    }
+
+    #[test]
+    fn parses_allow_comments() {
+        let source = "\
+event_table: # sharp:allow(compression)
+  # sharp:allow(sharding, wide-parts)
+
+  expected_events_per_day: 800000000
+";
+        assert_eq!(
+            allowed(source),
+            [
+                ("compression".into(), "event_table".into()),
+                ("sharding".into(), "expected_events_per_day".into()),
+                ("wide-parts".into(), "expected_events_per_day".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn hash_inside_quotes_is_not_a_comment() {
+        let source = "event_table:\n  name: \"events # sharp:allow(sharding)\"\n";
+        assert!(allowed(source).is_empty());
+        let source = "event_table: 'a#b' # sharp:allow(compression)\n";
+        assert_eq!(
+            allowed(source),
+            [("compression".into(), "event_table".into())]
+        );
+    }
+
+    #[test]
+    fn suppression_must_sit_on_a_key_the_rule_judges() {
+        let err = suppressions("  retention_days: 30 # sharp:allow(sharding)\n").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("line 1: 'sharding' doesn't judge `retention_days`")
+        );
+        let err = suppressions("rules: {}\n# sharp:allow(sharding)\n").unwrap_err();
+        assert!(err.to_string().contains("line 2: sharp:allow must sit on"));
+    }
+
+    #[test]
+    fn marker_outside_comment_is_ignored() {
+        assert!(
+            suppressions("name: sharp:allow(sharding)\n")
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn unknown_allowed_rule_is_rejected() {
+        let err = suppressions("# sharp:allow(shardin)\nevent_table:\n").unwrap_err();
+        assert!(err.to_string().contains("line 1: unknown rule 'shardin'"));
+    }
+
+    #[test]
+    fn warnings_only_fail_when_denied() {
+        let report = report(NOISY);
+        assert_eq!(report.count(Severity::Error), 0);
+        assert!(report.count(Severity::Warning) > 0);
+        assert_eq!(report.outcome(false), Outcome::Clean);
+        assert_eq!(report.outcome(true), Outcome::DeniedWarnings);
+        assert_eq!(report.outcome(true).exit_code(), EXIT_DENIED_WARNINGS);
+    }
+
+    #[test]
+    fn suppressed_warnings_do_not_fail() {
+        let source = NOISY
+            .replace("600000000", "600000000 # sharp:allow(sharding)")
+            .replace(
+                "  retention_days: 0",
+                "  # sharp:allow(unbounded-retention)\n  retention_days: 0",
+            );
+        let report = report(&source);
+        assert_eq!(report.count(Severity::Warning), 0);
+        assert_eq!(report.suppressed(), 2);
+        assert_eq!(report.outcome(true), Outcome::Clean);
+    }
+
+    #[test]
+    fn disabled_rules_are_reported_as_disabled() {
+        let source = format!("{NOISY}rules:\n  sharding: false\n");
+        let report = report(&source);
+        let sharding = report
+            .results
+            .iter()
+            .find(|r| r.rule_id == "sharding")
+            .unwrap();
+        assert_eq!(sharding.status, Status::Disabled);
+    }
+
+    #[test]
+    fn error_findings_fail_without_deny() {
+        let source = "\
+event_table:
+  name: events
+  multi_tenant: true
+  expected_events_per_day: 86400000
+  retention_days: 30
+ingestion:
+  producers: 20
+  insert_interval_ms: 1000
+  rows_per_insert: 5000
+";
+        let report = report(source);
+        assert!(report.count(Severity::Error) > 0);
+        assert_eq!(report.outcome(false).exit_code(), EXIT_ERRORS);
+    }
+}
@@ -1,5 +1,25 @@
+pub mod compliance;
+pub mod ddl;
+pub mod dictionaries;
+pub mod drift;
+pub mod fake;
+pub mod health;
 pub mod heuristics;
+pub mod indexes;
+pub mod infer;
+pub mod inserts;
+pub mod kafka;
+pub mod lint;
 pub mod ordering;
 pub mod partitioning;
+pub mod patterns;
+pub mod persons;
 pub mod projections;
+pub mod queries;
+pub mod query_check;
+pub mod rules;
+pub mod sampling;
+pub mod sessions;
+pub mod sizing;
+pub mod timestamp;
 pub mod ttl;
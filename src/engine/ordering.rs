@@ -1,8 +1,9 @@
+use crate::config::policy::{Policy, human};
 use crate::config::workload::WorkloadProfile;
+use crate::engine::patterns::{key_coverage, key_depth};
+use crate::engine::sampling::SAMPLING_EXPR;
 
-const HIGH_VOLUME_THRESHOLD: u64 = 100_000_000;
-
-pub fn choose_order_by(workload: &WorkloadProfile) -> Vec<String> {
+pub fn choose_order_by(workload: &WorkloadProfile, policy: &Policy) -> Vec<String> {
     let mut cols: Vec<String> = if workload.multi_tenant {
         ["project_id", "event_name", "timestamp", "distinct_id"]
             .into_iter()
@@ -15,13 +16,33 @@ pub fn choose_order_by(workload: &WorkloadProfile) -> Vec<String> {
             .collect()
     };
 
-    if workload.events_per_day > HIGH_VOLUME_THRESHOLD {
+    if workload.events_per_day > policy.thresholds.high_volume {
         promote_timestamp(&mut cols);
     }
 
+    // Logged queries outrank the volume guess: keep event_name and timestamp
+    // in whichever order lets them use more of the key.
+    let swapped = swap_event_and_time(&cols);
+    if key_depth(&workload.query_patterns, &swapped) > key_depth(&workload.query_patterns, &cols) {
+        cols = swapped;
+    }
+
+    if workload.sampling {
+        hash_distinct_id(&mut cols);
+    }
+
     cols
 }
 
+// The sampling expression must be part of the sort key. Swapping the raw
+// distinct_id for its hash keeps the key the same length and distinct_id was
+// already the last column, so no leading prefix is lost.
+fn hash_distinct_id(cols: &mut [String]) {
+    if let Some(col) = cols.iter_mut().find(|c| *c == "distinct_id") {
+        *col = SAMPLING_EXPR.to_string();
+    }
+}
+
 fn promote_timestamp(cols: &mut Vec<String>) {
     if let Some(pos) = cols.iter().position(|c| c == "timestamp") {
         cols.remove(pos);
@@ -33,47 +54,132 @@ fn promote_timestamp(cols: &mut Vec<String>) {
     }
 }
 
+fn swap_event_and_time(cols: &[String]) -> Vec<String> {
+    cols.iter()
+        .map(|c| match c.as_str() {
+            "event_name" => "timestamp".to_string(),
+            "timestamp" => "event_name".to_string(),
+            _ => c.clone(),
+        })
+        .collect()
+}
+
+fn timestamp_first(cols: &[String]) -> bool {
+    let pos = |name| cols.iter().position(|c| c == name);
+    pos("timestamp") < pos("event_name")
+}
+
+// Tables read by key lookups (sessions, persons) are keyed on that lookup,
+// behind the tenant when there is one.
+pub fn lookup_order_by(workload: &WorkloadProfile, key: &[&str]) -> Vec<String> {
+    let tenant = workload.multi_tenant.then_some("project_id");
+    tenant
+        .into_iter()
+        .chain(key.iter().copied())
+        .map(String::from)
+        .collect()
+}
+
+// Session rollups are read per user and merged per session; time pruning
+// comes from the partition.
+pub fn session_order_by(workload: &WorkloadProfile) -> Vec<String> {
+    lookup_order_by(workload, &["distinct_id", "session_id"])
+}
+
 pub fn order_by_sql(cols: &[String]) -> String {
     format!("ORDER BY ({})", cols.join(", "))
 }
 
-pub fn explain(workload: &WorkloadProfile, cols: &[String]) -> String {
+pub fn explain(workload: &WorkloadProfile, cols: &[String], policy: &Policy) -> String {
     let base = if workload.multi_tenant {
         "Multi-tenant: project_id leads the key for tenant isolation."
     } else {
         "Single-tenant: no project_id prefix needed."
     };
 
-    let volume = if workload.events_per_day > HIGH_VOLUME_THRESHOLD {
-        " High volume (>100M events/day): timestamp promoted earlier for time-range pruning."
+    let high_volume = policy.thresholds.high_volume;
+    let cite = policy.cite("high_volume", high_volume);
+    let volume = if workload.events_per_day > high_volume {
+        format!(
+            " High volume (>{} events/day): timestamp promoted earlier for time-range pruning [{cite}].",
+            human(high_volume)
+        )
+    } else {
+        format!(
+            " Standard volume: event_name before timestamp optimizes per-event-type queries [{cite}]."
+        )
+    };
+
+    let sampling = if workload.sampling {
+        format!(
+            " Sampling: distinct_id is keyed as {SAMPLING_EXPR} (UInt64) so SAMPLE BY is covered \
+             by the sort key; lookups by raw distinct_id cannot use that key column."
+        )
     } else {
-        " Standard volume: event_name before timestamp optimizes per-event-type queries."
+        String::new()
     };
 
-    format!("{base}{volume} Final key: ({}).", cols.join(", "))
+    let patterns = if workload.query_patterns.is_empty() {
+        String::new()
+    } else {
+        let overridden = if timestamp_first(cols) != (workload.events_per_day > high_volume) {
+            let (first, second) = if timestamp_first(cols) {
+                ("timestamp", "event_name")
+            } else {
+                ("event_name", "timestamp")
+            };
+            format!(
+                " They override the volume default: {first} goes before {second} because more of them use it."
+            )
+        } else {
+            String::new()
+        };
+        format!(
+            " Logged query patterns: {:.0}% of queries filter on a prefix of this key.{overridden}",
+            key_coverage(&workload.query_patterns, cols) * 100.0
+        )
+    };
+
+    format!(
+        "{base}{volume}{sampling}{patterns} Final key: ({}).",
+        cols.join(", ")
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::schema::QueryPattern;
 
     fn profile(events_per_day: u64, multi_tenant: bool) -> WorkloadProfile {
         WorkloadProfile {
             events_per_day,
             multi_tenant,
             retention_days: 90,
+            ..Default::default()
+        }
+    }
+
+    fn choose_order_by_default(workload: &WorkloadProfile) -> Vec<String> {
+        choose_order_by(workload, &Policy::default())
+    }
+
+    fn sampled(events_per_day: u64, multi_tenant: bool) -> WorkloadProfile {
+        WorkloadProfile {
+            sampling: true,
+            ..profile(events_per_day, multi_tenant)
         }
     }
 
     #[test]
     fn single_tenant_default() {
-        let cols = choose_order_by(&profile(1_000_000, false));
+        let cols = choose_order_by_default(&profile(1_000_000, false));
         assert_eq!(cols, ["event_name", "timestamp", "distinct_id"]);
     }
 
     #[test]
     fn multi_tenant_default() {
-        let cols = choose_order_by(&profile(1_000_000, true));
+        let cols = choose_order_by_default(&profile(1_000_000, true));
         assert_eq!(
             cols,
             ["project_id", "event_name", "timestamp", "distinct_id"]
@@ -82,13 +188,13 @@ mod tests {
 
     #[test]
     fn high_volume_single_tenant_promotes_timestamp() {
-        let cols = choose_order_by(&profile(200_000_000, false));
+        let cols = choose_order_by_default(&profile(200_000_000, false));
         assert_eq!(cols, ["timestamp", "event_name", "distinct_id"]);
     }
 
     #[test]
     fn high_volume_multi_tenant_promotes_timestamp_after_project_id() {
-        let cols = choose_order_by(&profile(200_000_000, true));
+        let cols = choose_order_by_default(&profile(200_000_000, true));
         assert_eq!(
             cols,
             ["project_id", "timestamp", "event_name", "distinct_id"]
@@ -97,13 +203,13 @@ mod tests {
 
     #[test]
     fn boundary_100m_no_promotion() {
-        let cols = choose_order_by(&profile(100_000_000, false));
+        let cols = choose_order_by_default(&profile(100_000_000, false));
         assert_eq!(cols, ["event_name", "timestamp", "distinct_id"]);
     }
 
     #[test]
     fn boundary_just_above_100m_promotes() {
-        let cols = choose_order_by(&profile(100_000_001, false));
+        let cols = choose_order_by_default(&profile(100_000_001, false));
         assert_eq!(cols, ["timestamp", "event_name", "distinct_id"]);
     }
 
@@ -116,8 +222,8 @@ mod tests {
     #[test]
     fn explain_single_tenant_standard_volume() {
         let w = profile(1_000_000, false);
-        let cols = choose_order_by(&w);
-        let explanation = explain(&w, &cols);
+        let cols = choose_order_by(&w, &Policy::default());
+        let explanation = explain(&w, &cols, &Policy::default());
         assert!(explanation.contains("Single-tenant"));
         assert!(explanation.contains("Standard volume"));
         assert!(explanation.contains("event_name before timestamp"));
@@ -126,8 +232,8 @@ mod tests {
     #[test]
     fn explain_multi_tenant_standard_volume() {
         let w = profile(1_000_000, true);
-        let cols = choose_order_by(&w);
-        let explanation = explain(&w, &cols);
+        let cols = choose_order_by(&w, &Policy::default());
+        let explanation = explain(&w, &cols, &Policy::default());
         assert!(explanation.contains("Multi-tenant"));
         assert!(explanation.contains("project_id leads"));
         assert!(explanation.contains("Standard volume"));
@@ -136,8 +242,8 @@ mod tests {
     #[test]
     fn explain_single_tenant_high_volume() {
         let w = profile(200_000_000, false);
-        let cols = choose_order_by(&w);
-        let explanation = explain(&w, &cols);
+        let cols = choose_order_by(&w, &Policy::default());
+        let explanation = explain(&w, &cols, &Policy::default());
         assert!(explanation.contains("Single-tenant"));
         assert!(explanation.contains("High volume"));
         assert!(explanation.contains("timestamp promoted"));
@@ -146,10 +252,134 @@ mod tests {
     #[test]
     fn explain_multi_tenant_high_volume() {
         let w = profile(200_000_000, true);
-        let cols = choose_order_by(&w);
-        let explanation = explain(&w, &cols);
+        let cols = choose_order_by(&w, &Policy::default());
+        let explanation = explain(&w, &cols, &Policy::default());
         assert!(explanation.contains("Multi-tenant"));
         assert!(explanation.contains("High volume"));
         assert!(explanation.contains("Final key:"));
     }
+
+    #[test]
+    fn sampling_replaces_distinct_id_with_hash() {
+        let cols = choose_order_by_default(&sampled(1_000_000, true));
+        assert_eq!(
+            cols,
+            ["project_id", "event_name", "timestamp", "cityHash64(distinct_id)"]
+        );
+    }
+
+    #[test]
+    fn sampling_keeps_timestamp_promotion() {
+        let cols = choose_order_by_default(&sampled(200_000_000, false));
+        assert_eq!(cols, ["timestamp", "event_name", "cityHash64(distinct_id)"]);
+    }
+
+    #[test]
+    fn explain_mentions_sampling_key() {
+        let w = sampled(1_000_000, false);
+        let cols = choose_order_by(&w, &Policy::default());
+        let explanation = explain(&w, &cols, &Policy::default());
+        assert!(explanation.contains("SAMPLE BY"));
+        assert!(explanation.contains("UInt64"));
+    }
+
+    #[test]
+    fn explain_omits_sampling_when_disabled() {
+        let w = profile(1_000_000, false);
+        let cols = choose_order_by(&w, &Policy::default());
+        assert!(!explain(&w, &cols, &Policy::default()).contains("Sampling"));
+    }
+
+    #[test]
+    fn policy_lowers_promotion_threshold() {
+        let mut policy = Policy::default();
+        policy.thresholds.high_volume = 10_000_000;
+        let w = profile(20_000_000, false);
+        let cols = choose_order_by(&w, &policy);
+        assert_eq!(cols, ["timestamp", "event_name", "distinct_id"]);
+        assert!(explain(&w, &cols, &policy).contains(">10M events/day"));
+    }
+
+    #[test]
+    fn explain_cites_high_volume_policy() {
+        let w = profile(1_000_000, false);
+        let cols = choose_order_by(&w, &Policy::default());
+        let explanation = explain(&w, &cols, &Policy::default());
+        assert!(explanation.contains("thresholds.high_volume = 100000000 (default)"));
+    }
+
+    #[test]
+    fn explain_reports_query_pattern_coverage() {
+        let w = WorkloadProfile {
+            query_patterns: vec![
+                QueryPattern {
+                    equality: vec!["event_name".to_string()],
+                    share: 0.6,
+                    ..Default::default()
+                },
+                QueryPattern {
+                    equality: vec!["distinct_id".to_string()],
+                    share: 0.4,
+                    ..Default::default()
+                },
+            ],
+            ..profile(1_000_000, false)
+        };
+        let cols = choose_order_by(&w, &Policy::default());
+        assert!(explain(&w, &cols, &Policy::default()).contains("60% of queries"));
+    }
+
+    #[test]
+    fn logged_time_range_queries_promote_timestamp() {
+        let w = WorkloadProfile {
+            query_patterns: vec![
+                QueryPattern {
+                    range: vec!["timestamp".to_string()],
+                    share: 0.7,
+                    ..Default::default()
+                },
+                QueryPattern {
+                    equality: vec!["event_name".to_string()],
+                    share: 0.3,
+                    ..Default::default()
+                },
+            ],
+            ..profile(1_000_000, false)
+        };
+        let cols = choose_order_by(&w, &Policy::default());
+        assert_eq!(cols, ["timestamp", "event_name", "distinct_id"]);
+        assert!(
+            explain(&w, &cols, &Policy::default())
+                .contains("override the volume default: timestamp goes before event_name")
+        );
+    }
+
+    #[test]
+    fn logged_event_lookups_keep_event_name_first_at_high_volume() {
+        let w = WorkloadProfile {
+            query_patterns: vec![QueryPattern {
+                equality: vec!["project_id".to_string(), "event_name".to_string()],
+                range: vec!["timestamp".to_string()],
+                share: 1.0,
+                ..Default::default()
+            }],
+            ..profile(200_000_000, true)
+        };
+        let cols = choose_order_by(&w, &Policy::default());
+        assert_eq!(
+            cols,
+            ["project_id", "event_name", "timestamp", "distinct_id"]
+        );
+    }
+
+    #[test]
+    fn session_key_follows_tenancy() {
+        let mut workload = profile(1_000_000, true);
+        assert_eq!(
+            session_order_by(&workload),
+            ["project_id", "distinct_id", "session_id"]
+        );
+        workload.multi_tenant = false;
+        assert_eq!(session_order_by(&workload), ["distinct_id", "session_id"]);
+    }
 }
@@ -1,4 +1,6 @@
+use crate::config::policy::{Policy, human};
 use crate::config::workload::WorkloadProfile;
+use crate::engine::timestamp::timezone_warning;
 
 #[derive(Debug, PartialEq)]
 pub enum PartitionStrategy {
@@ -12,38 +14,55 @@ impl PartitionStrategy {
         match self {
             Self::Monthly => "toYYYYMM(timestamp)".to_string(),
             Self::Daily => "toYYYYMMDD(timestamp)".to_string(),
-            Self::DailyWithTenant => {
-                "tuple(project_id, toYYYYMMDD(timestamp))".to_string()
-            }
+            Self::DailyWithTenant => "tuple(project_id, toYYYYMMDD(timestamp))".to_string(),
         }
     }
 
-    pub fn explain(&self, workload: &WorkloadProfile) -> String {
-        match self {
+    pub fn explain(&self, workload: &WorkloadProfile, policy: &Policy) -> String {
+        let t = &policy.thresholds;
+        let low = human(t.monthly_partition_below);
+        let high = human(t.tenant_partition_above);
+        let reason = match self {
             Self::Monthly => format!(
-                "Monthly partitioning selected: {} events/day is under the 5M threshold, \
-                 so monthly granularity avoids excessive part count.",
-                workload.events_per_day
+                "Monthly partitioning selected: {} events/day is under the {low} threshold, \
+                 so monthly granularity avoids excessive part count [{}].",
+                workload.events_per_day,
+                policy.cite("monthly_partition_below", t.monthly_partition_below)
+            ),
+            Self::Daily if workload.events_per_day > t.tenant_partition_above => format!(
+                "Daily partitioning selected: {} events/day exceeds {high} but the workload \
+                 is single-tenant, so there is no tenant key to split partitions further [{}].",
+                workload.events_per_day,
+                policy.cite("tenant_partition_above", t.tenant_partition_above)
             ),
             Self::Daily => format!(
-                "Daily partitioning selected: {} events/day falls in the 5M–200M range, \
-                 balancing query pruning against part management overhead.",
-                workload.events_per_day
+                "Daily partitioning selected: {} events/day falls in the {low}–{high} range, \
+                 balancing query pruning against part management overhead [{}; {}].",
+                workload.events_per_day,
+                policy.cite("monthly_partition_below", t.monthly_partition_below),
+                policy.cite("tenant_partition_above", t.tenant_partition_above)
             ),
             Self::DailyWithTenant => format!(
-                "Daily + tenant partitioning selected: {} events/day exceeds 200M \
+                "Daily + tenant partitioning selected: {} events/day exceeds {high} \
                  with multi-tenant enabled, partitioning by (project_id, day) \
-                 isolates tenant data for efficient pruning.",
-                workload.events_per_day
+                 isolates tenant data for efficient pruning [{}].",
+                workload.events_per_day,
+                policy.cite("tenant_partition_above", t.tenant_partition_above)
             ),
+        };
+
+        match timezone_warning(&workload.timestamp) {
+            Some(warning) => format!("{reason} {warning}"),
+            None => reason,
         }
     }
 }
 
-pub fn choose_partition_strategy(workload: &WorkloadProfile) -> PartitionStrategy {
-    if workload.events_per_day < 5_000_000 {
+pub fn choose_partition_strategy(workload: &WorkloadProfile, policy: &Policy) -> PartitionStrategy {
+    let t = &policy.thresholds;
+    if workload.events_per_day < t.monthly_partition_below {
         PartitionStrategy::Monthly
-    } else if workload.events_per_day <= 200_000_000 {
+    } else if workload.events_per_day <= t.tenant_partition_above {
         PartitionStrategy::Daily
     } else if workload.multi_tenant {
         PartitionStrategy::DailyWithTenant
@@ -61,38 +80,51 @@ mod tests {
             events_per_day,
             multi_tenant,
             retention_days: 90,
+            ..Default::default()
         }
     }
 
     #[test]
     fn low_volume_uses_monthly() {
         let w = profile(1_000_000, false);
-        assert_eq!(choose_partition_strategy(&w), PartitionStrategy::Monthly);
+        assert_eq!(
+            choose_partition_strategy(&w, &Policy::default()),
+            PartitionStrategy::Monthly
+        );
     }
 
     #[test]
     fn medium_volume_uses_daily() {
         let w = profile(50_000_000, false);
-        assert_eq!(choose_partition_strategy(&w), PartitionStrategy::Daily);
+        assert_eq!(
+            choose_partition_strategy(&w, &Policy::default()),
+            PartitionStrategy::Daily
+        );
     }
 
     #[test]
     fn boundary_5m_uses_daily() {
         let w = profile(5_000_000, false);
-        assert_eq!(choose_partition_strategy(&w), PartitionStrategy::Daily);
+        assert_eq!(
+            choose_partition_strategy(&w, &Policy::default()),
+            PartitionStrategy::Daily
+        );
     }
 
     #[test]
     fn boundary_200m_uses_daily() {
         let w = profile(200_000_000, false);
-        assert_eq!(choose_partition_strategy(&w), PartitionStrategy::Daily);
+        assert_eq!(
+            choose_partition_strategy(&w, &Policy::default()),
+            PartitionStrategy::Daily
+        );
     }
 
     #[test]
     fn high_volume_multi_tenant_uses_daily_with_tenant() {
         let w = profile(500_000_000, true);
         assert_eq!(
-            choose_partition_strategy(&w),
+            choose_partition_strategy(&w, &Policy::default()),
             PartitionStrategy::DailyWithTenant
         );
     }
@@ -100,7 +132,10 @@ mod tests {
     #[test]
     fn high_volume_single_tenant_falls_back_to_daily() {
         let w = profile(500_000_000, false);
-        assert_eq!(choose_partition_strategy(&w), PartitionStrategy::Daily);
+        assert_eq!(
+            choose_partition_strategy(&w, &Policy::default()),
+            PartitionStrategy::Daily
+        );
     }
 
     #[test]
@@ -124,7 +159,7 @@ mod tests {
     #[test]
     fn explain_monthly() {
         let w = profile(1_000_000, false);
-        let explanation = PartitionStrategy::Monthly.explain(&w);
+        let explanation = PartitionStrategy::Monthly.explain(&w, &Policy::default());
         assert!(explanation.contains("Monthly partitioning"));
         assert!(explanation.contains("1000000"));
         assert!(explanation.contains("5M threshold"));
@@ -133,7 +168,7 @@ mod tests {
     #[test]
     fn explain_daily() {
         let w = profile(50_000_000, false);
-        let explanation = PartitionStrategy::Daily.explain(&w);
+        let explanation = PartitionStrategy::Daily.explain(&w, &Policy::default());
         assert!(explanation.contains("Daily partitioning"));
         assert!(explanation.contains("50000000"));
         assert!(explanation.contains("5M–200M"));
@@ -142,9 +177,67 @@ mod tests {
     #[test]
     fn explain_daily_with_tenant() {
         let w = profile(500_000_000, true);
-        let explanation = PartitionStrategy::DailyWithTenant.explain(&w);
+        let explanation = PartitionStrategy::DailyWithTenant.explain(&w, &Policy::default());
         assert!(explanation.contains("Daily + tenant"));
         assert!(explanation.contains("500000000"));
         assert!(explanation.contains("multi-tenant"));
     }
+
+    #[test]
+    fn explain_warns_about_server_timezone() {
+        let w = profile(50_000_000, false);
+        let explanation = PartitionStrategy::Daily.explain(&w, &Policy::default());
+        assert!(explanation.contains("server timezone"));
+    }
+
+    #[test]
+    fn explain_no_timezone_warning_when_pinned() {
+        let mut w = profile(50_000_000, false);
+        w.timestamp.timezone = Some("UTC".to_string());
+        let explanation = PartitionStrategy::Daily.explain(&w, &Policy::default());
+        assert!(!explanation.contains("Warning"));
+    }
+
+    #[test]
+    fn policy_moves_monthly_cutoff() {
+        let mut policy = Policy::default();
+        policy.thresholds.monthly_partition_below = 10_000_000;
+        let w = profile(8_000_000, false);
+        assert_eq!(
+            choose_partition_strategy(&w, &policy),
+            PartitionStrategy::Monthly
+        );
+        assert!(
+            PartitionStrategy::Monthly
+                .explain(&w, &policy)
+                .contains("10M threshold")
+        );
+    }
+
+    #[test]
+    fn policy_moves_tenant_cutoff() {
+        let mut policy = Policy::default();
+        policy.thresholds.tenant_partition_above = 100_000_000;
+        let w = profile(150_000_000, true);
+        assert_eq!(
+            choose_partition_strategy(&w, &policy),
+            PartitionStrategy::DailyWithTenant
+        );
+    }
+
+    #[test]
+    fn explain_cites_policy_values() {
+        let w = profile(50_000_000, false);
+        let explanation = PartitionStrategy::Daily.explain(&w, &Policy::default());
+        assert!(explanation.contains("thresholds.monthly_partition_below = 5000000 (default)"));
+        assert!(explanation.contains("thresholds.tenant_partition_above = 200000000 (default)"));
+    }
+
+    #[test]
+    fn explain_single_tenant_above_tenant_cutoff() {
+        let w = profile(500_000_000, false);
+        let explanation = PartitionStrategy::Daily.explain(&w, &Policy::default());
+        assert!(explanation.contains("single-tenant"));
+        assert!(!explanation.contains("range"));
+    }
 }
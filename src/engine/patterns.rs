@@ -0,0 +1,343 @@
+use crate::config::schema::QueryPattern;
+use crate::engine::queries::{Filter, key_prefix};
+use crate::engine::query_check::{PredicateKind, TableRead, classify, find_table};
+use crate::errors::SharpError;
+use crate::sql::ast::TableName;
+use crate::sql::query::parse_select;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq)]
+pub struct LoggedQuery {
+    pub query: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug)]
+pub struct PatternReport {
+    pub patterns: Vec<QueryPattern>,
+    pub analysed: usize,
+    // Not a SELECT we can parse, or not against the events table.
+    pub skipped: usize,
+}
+
+fn log_error(message: impl Into<String>) -> SharpError {
+    SharpError::QueryLogError(message.into())
+}
+
+// TabSeparated escapes control characters and backslashes.
+fn unescape_tsv(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('0') => out.push('\0'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+// query_log has a QueryStart row and a QueryFinish row per query; only the
+// finished one carries the duration.
+fn is_finished(kind: Option<&str>) -> bool {
+    kind.is_none_or(|k| k == "QueryFinish" || k == "2")
+}
+
+fn parse_tsv(contents: &str) -> Result<Vec<LoggedQuery>, SharpError> {
+    let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or_else(|| log_error("empty file"))?
+        .split('\t')
+        .collect();
+    let column = |name: &str| header.iter().position(|h| *h == name);
+    let query_at = column("query").ok_or_else(|| log_error("no `query` column in header"))?;
+    let duration_at = column("query_duration_ms")
+        .ok_or_else(|| log_error("no `query_duration_ms` column in header"))?;
+    let type_at = column("type");
+
+    let mut entries = Vec::new();
+    for (lineno, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() != header.len() {
+            return Err(log_error(format!(
+                "row {} has {} fields, header has {}",
+                lineno + 2,
+                fields.len(),
+                header.len()
+            )));
+        }
+        if !is_finished(type_at.map(|i| fields[i])) {
+            continue;
+        }
+        let duration_ms = fields[duration_at].parse().map_err(|_| {
+            log_error(format!(
+                "row {}: query_duration_ms '{}' is not a number",
+                lineno + 2,
+                fields[duration_at]
+            ))
+        })?;
+        entries.push(LoggedQuery {
+            query: unescape_tsv(fields[query_at]),
+            duration_ms,
+        });
+    }
+    Ok(entries)
+}
+
+fn parse_json_each_row(contents: &str) -> Result<Vec<LoggedQuery>, SharpError> {
+    let mut entries = Vec::new();
+    for (lineno, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row: serde_json::Value = serde_json::from_str(line)
+            .map_err(|e| log_error(format!("line {}: {e}", lineno + 1)))?;
+        if !is_finished(row.get("type").and_then(|t| t.as_str())) {
+            continue;
+        }
+        let query = row["query"]
+            .as_str()
+            .ok_or_else(|| log_error(format!("line {}: missing `query`", lineno + 1)))?;
+        // 64-bit integers are quoted in JSON output by default.
+        let duration_ms = match row["query_duration_ms"] {
+            serde_json::Value::Number(ref n) => n.as_u64(),
+            serde_json::Value::String(ref s) => s.parse().ok(),
+            _ => None,
+        }
+        .ok_or_else(|| log_error(format!("line {}: bad `query_duration_ms`", lineno + 1)))?;
+        entries.push(LoggedQuery {
+            query: query.to_string(),
+            duration_ms,
+        });
+    }
+    Ok(entries)
+}
+
+// TSVWithNames or JSONEachRow, told apart by the first character.
+pub fn parse_query_log(contents: &str) -> Result<Vec<LoggedQuery>, SharpError> {
+    if contents.trim_start().starts_with('{') {
+        parse_json_each_row(contents)
+    } else {
+        parse_tsv(contents)
+    }
+}
+
+// The filter shape of a query: sorted, de-duplicated columns per kind.
+fn shape(read: &TableRead, table: &TableName) -> (Vec<String>, Vec<String>) {
+    let mut qualifiers = vec![table.name.as_str()];
+    qualifiers.extend(&read.aliases);
+    let mut equality = Vec::new();
+    let mut range = Vec::new();
+    for predicate in &read.predicates {
+        let p = classify(predicate, &qualifiers);
+        match (p.kind, p.column) {
+            (PredicateKind::Eq, Some(col)) => equality.push(col),
+            (PredicateKind::Range, Some(col)) => range.push(col),
+            _ => {}
+        }
+    }
+    for cols in [&mut equality, &mut range] {
+        cols.sort();
+        cols.dedup();
+    }
+    (equality, range)
+}
+
+// Nearest-rank percentile.
+fn p95(durations: &mut [u64]) -> u64 {
+    durations.sort_unstable();
+    let rank = (durations.len() as f64 * 0.95).ceil() as usize;
+    durations[rank.saturating_sub(1)]
+}
+
+pub fn infer(entries: &[LoggedQuery], table: &TableName) -> PatternReport {
+    let mut clusters: BTreeMap<(Vec<String>, Vec<String>), Vec<u64>> = BTreeMap::new();
+    let mut skipped = 0;
+
+    for entry in entries {
+        let Some(shape) = parse_select(&entry.query)
+            .ok()
+            .and_then(|q| find_table(&q, table).map(|read| shape(&read, table)))
+        else {
+            skipped += 1;
+            continue;
+        };
+        clusters.entry(shape).or_default().push(entry.duration_ms);
+    }
+
+    let analysed = entries.len() - skipped;
+    let mut patterns: Vec<QueryPattern> = clusters
+        .into_iter()
+        .map(|((equality, range), mut durations)| QueryPattern {
+            equality,
+            range,
+            share: (durations.len() as f64 / analysed as f64 * 1000.0).round() / 1000.0,
+            count: durations.len() as u64,
+            p95_ms: p95(&mut durations),
+        })
+        .collect();
+    patterns.sort_by(|a, b| b.count.cmp(&a.count).then(b.p95_ms.cmp(&a.p95_ms)));
+
+    PatternReport {
+        patterns,
+        analysed,
+        skipped,
+    }
+}
+
+#[derive(Serialize)]
+struct PatternsSection<'a> {
+    query_patterns: &'a [QueryPattern],
+}
+
+// A `query_patterns:` block ready to paste into (or append to) a schema file.
+pub fn to_yaml(report: &PatternReport, source: &str) -> String {
+    let section = PatternsSection {
+        query_patterns: &report.patterns,
+    };
+    let yaml = serde_yaml::to_string(&section).expect("patterns are always serializable");
+    format!(
+        "# Inferred by `sharp infer-patterns` from {source}: {} queries analysed, {} skipped\n{yaml}",
+        report.analysed, report.skipped
+    )
+}
+
+pub fn filters(pattern: &QueryPattern) -> Vec<Filter<'_>> {
+    let eq = pattern.equality.iter().map(|c| Filter::Eq(c));
+    let range = pattern.range.iter().map(|c| Filter::Range(c));
+    eq.chain(range).collect()
+}
+
+// Share of logged queries whose filters use at least the first key column.
+pub fn key_coverage(patterns: &[QueryPattern], order_by: &[String]) -> f64 {
+    patterns
+        .iter()
+        .filter(|p| !key_prefix(order_by, &filters(p)).is_empty())
+        .map(|p| p.share)
+        .sum()
+}
+
+// Key columns the logged queries use, weighted by their share: 1.0 means the
+// whole workload uses exactly one column of the key.
+pub fn key_depth(patterns: &[QueryPattern], order_by: &[String]) -> f64 {
+    patterns
+        .iter()
+        .map(|p| p.share * key_prefix(order_by, &filters(p)).len() as f64)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(query: &str, duration_ms: u64) -> LoggedQuery {
+        LoggedQuery {
+            query: query.to_string(),
+            duration_ms,
+        }
+    }
+
+    #[test]
+    fn reads_tsv_with_names() {
+        let log = "type\tquery_duration_ms\tquery\n\
+                   QueryStart\t0\tSELECT 1 FROM events\n\
+                   QueryFinish\t12\tSELECT count()\\nFROM events WHERE name = \\'a\\'\n";
+        let entries = parse_query_log(log).unwrap();
+        assert_eq!(
+            entries,
+            [entry("SELECT count()\nFROM events WHERE name = 'a'", 12)]
+        );
+    }
+
+    #[test]
+    fn reads_json_each_row_with_quoted_integers() {
+        let log = "{\"query\":\"SELECT 1 FROM events\",\"query_duration_ms\":\"40\",\"type\":\"QueryFinish\"}\n\
+                   {\"query\":\"SELECT 2 FROM events\",\"query_duration_ms\":7}\n";
+        let entries = parse_query_log(log).unwrap();
+        assert_eq!(entries[0].duration_ms, 40);
+        assert_eq!(entries[1].duration_ms, 7);
+    }
+
+    #[test]
+    fn missing_columns_are_reported() {
+        let err = parse_query_log("query\nSELECT 1\n").unwrap_err();
+        assert!(err.to_string().contains("query_duration_ms"));
+    }
+
+    #[test]
+    fn clusters_by_filter_shape() {
+        let entries = [
+            entry(
+                "SELECT count() FROM events WHERE project_id = 1 AND timestamp > now() - 60",
+                10,
+            ),
+            entry(
+                "SELECT count() FROM events WHERE timestamp > now() - 3600 AND project_id = 7",
+                30,
+            ),
+            entry("SELECT * FROM events WHERE distinct_id = 'u'", 900),
+            entry("SELECT * FROM sessions WHERE x = 1", 5),
+            entry("INSERT INTO events VALUES", 1),
+        ];
+        let report = infer(&entries, &TableName::new("events"));
+        assert_eq!(report.analysed, 3);
+        assert_eq!(report.skipped, 2);
+        assert_eq!(report.patterns.len(), 2);
+
+        let top = &report.patterns[0];
+        assert_eq!(top.equality, ["project_id"]);
+        assert_eq!(top.range, ["timestamp"]);
+        assert_eq!(top.count, 2);
+        assert_eq!(top.share, 0.667);
+        assert_eq!(top.p95_ms, 30);
+    }
+
+    #[test]
+    fn yaml_round_trips_into_schema() {
+        let entries = [entry("SELECT 1 FROM events WHERE event_name = 'a'", 3)];
+        let yaml = to_yaml(&infer(&entries, &TableName::new("events")), "log.tsv");
+        assert!(yaml.starts_with("# Inferred by `sharp infer-patterns` from log.tsv"));
+
+        let schema = crate::config::parse_schema(&format!(
+            "event_table:\n  name: events\n  expected_events_per_day: 1000\n  retention_days: 7\n{yaml}"
+        ))
+        .unwrap();
+        assert_eq!(schema.query_patterns[0].equality, ["event_name"]);
+        assert_eq!(schema.query_patterns[0].share, 1.0);
+    }
+
+    #[test]
+    fn p95_is_nearest_rank() {
+        let mut durations: Vec<u64> = (1..=100).collect();
+        assert_eq!(p95(&mut durations), 95);
+        assert_eq!(p95(&mut [5]), 5);
+    }
+
+    #[test]
+    fn coverage_counts_patterns_using_the_key() {
+        let patterns = [
+            QueryPattern {
+                equality: vec!["project_id".to_string()],
+                share: 0.75,
+                ..Default::default()
+            },
+            QueryPattern {
+                equality: vec!["distinct_id".to_string()],
+                share: 0.25,
+                ..Default::default()
+            },
+        ];
+        let order_by = vec!["project_id".to_string(), "timestamp".to_string()];
+        assert_eq!(key_coverage(&patterns, &order_by), 0.75);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::config::schema::PersonsConfig;
+use crate::config::workload::WorkloadProfile;
+use crate::engine::queries::CanonicalQuery;
+use crate::engine::rules::PlanContext;
+use crate::engine::{dictionaries, ordering, timestamp};
+use crate::sql::ast::{ColumnExpr, CreateTable, Identifier, TableName};
+use crate::sql::types::ClickHouseType;
+
+// Above this many persons, FINAL's read-time merge costs more than the
+// argMax rewrite.
+const FINAL_AFFORDABLE_BELOW: u64 = 10_000_000;
+
+pub fn persons_table_name(events_table: &TableName) -> TableName {
+    events_table.with_suffix("_persons")
+}
+
+pub fn distinct_id_table_name(events_table: &TableName) -> TableName {
+    events_table.with_suffix("_person_distinct_id")
+}
+
+fn column(name: &str, data_type: ClickHouseType) -> ColumnExpr {
+    ColumnExpr {
+        name: Identifier::new(name),
+        data_type,
+        default: None,
+    }
+}
+
+// Every write (identify, property update, delete) inserts a full row with a
+// higher version; the engine keeps the latest once parts merge and drops it
+// entirely when that latest row has is_deleted = 1.
+fn replacing(name: TableName, mut columns: Vec<ColumnExpr>, order_by: Vec<String>) -> CreateTable {
+    columns.push(column("is_deleted", ClickHouseType::UInt(8)));
+    columns.push(column("version", ClickHouseType::UInt(64)));
+    CreateTable {
+        table_name: name,
+        columns,
+        engine: "ReplacingMergeTree(version, is_deleted)".to_string(),
+        partition_by: None,
+        order_by,
+        sample_by: None,
+        ttl: None,
+        settings: vec![],
+    }
+}
+
+pub fn persons_table(events_table: &TableName, workload: &WorkloadProfile) -> CreateTable {
+    replacing(
+        persons_table_name(events_table),
+        vec![
+            column("project_id", ClickHouseType::UInt(32)),
+            column("id", ClickHouseType::Uuid),
+            column("properties", ClickHouseType::Json),
+            column("created_at", timestamp::column_type(&workload.timestamp)),
+        ],
+        ordering::lookup_order_by(workload, &["id"]),
+    )
+}
+
+// One row per distinct_id; an alias or merge re-points it at another person
+// by inserting a higher version.
+pub fn distinct_id_table(events_table: &TableName, workload: &WorkloadProfile) -> CreateTable {
+    replacing(
+        distinct_id_table_name(events_table),
+        vec![
+            column("project_id", ClickHouseType::UInt(32)),
+            column("distinct_id", ClickHouseType::String),
+            column("person_id", ClickHouseType::Uuid),
+        ],
+        ordering::lookup_order_by(workload, &["distinct_id"]),
+    )
+}
+
+fn reads_with_final(config: &PersonsConfig) -> bool {
+    config
+        .expected_persons
+        .is_some_and(|n| n < FINAL_AFFORDABLE_BELOW)
+}
+
+pub fn explain(workload: &WorkloadProfile, config: &PersonsConfig) -> String {
+    let key = ordering::lookup_order_by(workload, &["id"]).join(", ");
+    let base = format!(
+        "Persons and distinct_id mappings are ReplacingMergeTree tables keyed on ({key}) and \
+         (…, distinct_id): identify and alias calls insert a new row with a higher version \
+         instead of updating in place. Older versions linger until a background merge, so \
+         reads must deduplicate."
+    );
+    let advice = match config.expected_persons {
+        Some(n) if n < FINAL_AFFORDABLE_BELOW => format!(
+            " With ~{n} persons, FINAL is affordable and the simplest correct read; switch to \
+             argMax(col, version) ... GROUP BY past {FINAL_AFFORDABLE_BELOW}."
+        ),
+        Some(n) => format!(
+            " With ~{n} persons, FINAL would merge every matching part at query time; read with \
+             argMax(col, version) ... GROUP BY, which aggregates in parallel after the tenant \
+             filter prunes."
+        ),
+        None => " Read with argMax(col, version) ... GROUP BY: it stays correct and parallel \
+                  as the table grows, where FINAL merges parts at query time. Set \
+                  persons.expected_persons to see whether FINAL is good enough."
+            .to_string(),
+    };
+    format!("{base}{advice}")
+}
+
+fn tenant_condition(ctx: &PlanContext, alias: &str) -> Option<String> {
+    ctx.workload
+        .multi_tenant
+        .then(|| format!("{alias}project_id = {{project_id:UInt32}}"))
+}
+
+// The distinct_id → person_id mapping as of now, written as the argMax
+// subquery the other queries join against.
+fn current_mapping(ctx: &PlanContext, indent: &str) -> String {
+    let table = distinct_id_table_name(&ctx.table());
+    let filter = tenant_condition(ctx, "")
+        .map(|c| format!("\n{indent}WHERE {c}"))
+        .unwrap_or_default();
+    format!(
+        "{indent}SELECT\n{indent}    distinct_id,\n{indent}    argMax(person_id, version) AS person_id\n\
+         {indent}FROM {table}{filter}\n{indent}GROUP BY distinct_id\n\
+         {indent}HAVING argMax(is_deleted, version) = 0"
+    )
+}
+
+fn person_lookup(ctx: &PlanContext, config: &PersonsConfig) -> CanonicalQuery {
+    let table = persons_table_name(&ctx.table());
+    let mut conditions: Vec<String> = tenant_condition(ctx, "").into_iter().collect();
+    conditions.push("id = {person_id:UUID}".to_string());
+    let sql = if reads_with_final(config) {
+        format!(
+            "SELECT id, properties, created_at\nFROM {table} FINAL\nWHERE {};",
+            conditions.join("\n  AND ")
+        )
+    } else {
+        format!(
+            "SELECT\n    id,\n    argMax(properties, version) AS properties,\n    \
+             argMax(created_at, version) AS created_at\nFROM {table}\nWHERE {}\nGROUP BY id\n\
+             HAVING argMax(is_deleted, version) = 0;",
+            conditions.join("\n  AND ")
+        )
+    };
+    CanonicalQuery {
+        name: "Current state of a person",
+        sql,
+        uses: vec![format!(
+            "ORDER BY ({}) of {table}",
+            ordering::lookup_order_by(ctx.workload, &["id"]).join(", ")
+        )],
+    }
+}
+
+fn distinct_ids_of_person(ctx: &PlanContext) -> CanonicalQuery {
+    let sql = format!(
+        "SELECT distinct_id\nFROM\n(\n{}\n)\nWHERE person_id = {{person_id:UUID}};",
+        current_mapping(ctx, "    ")
+    );
+    CanonicalQuery {
+        name: "Distinct ids currently merged into a person",
+        sql,
+        uses: vec!["argMax(person_id, version): the latest mapping wins without FINAL".to_string()],
+    }
+}
+
+fn events_by_person(ctx: &PlanContext) -> CanonicalQuery {
+    let table = ctx.table();
+    // A dictionary over the mapping answers from memory instead of building
+    // the join's hash table on every query.
+    if let Some((dictionary, person_id)) = dictionaries::lookup(ctx, "person_id") {
+        let mut conditions: Vec<String> = tenant_condition(ctx, "").into_iter().collect();
+        conditions.push("timestamp >= {from:DateTime}".to_string());
+        conditions.push("timestamp < {to:DateTime}".to_string());
+        let sql = format!(
+            "SELECT\n    {person_id} AS person_id,\n    count() AS events\nFROM {table}\n\
+             WHERE {}\nGROUP BY person_id\nORDER BY events DESC\nLIMIT 100;",
+            conditions.join("\n  AND ")
+        );
+        return CanonicalQuery {
+            name: "Events per person across merged distinct ids",
+            sql,
+            uses: vec![format!(
+                "dictionary {} instead of a JOIN against {}",
+                dictionaries::dictionary_name(&table, dictionary),
+                distinct_id_table_name(&table)
+            )],
+        };
+    }
+    let mut conditions: Vec<String> = tenant_condition(ctx, "e.").into_iter().collect();
+    conditions.push("e.timestamp >= {from:DateTime}".to_string());
+    conditions.push("e.timestamp < {to:DateTime}".to_string());
+    let sql = format!(
+        "SELECT\n    pdi.person_id,\n    count() AS events\nFROM {table} AS e\nINNER JOIN\n(\n{}\n) AS pdi \
+         ON e.distinct_id = pdi.distinct_id\nWHERE {}\nGROUP BY pdi.person_id\n\
+         ORDER BY events DESC\nLIMIT 100;",
+        current_mapping(ctx, "    "),
+        conditions.join("\n  AND ")
+    );
+    CanonicalQuery {
+        name: "Events per person across merged distinct ids",
+        sql,
+        uses: vec![
+            "the deduplicated mapping as the right-hand (in-memory) side of the join".to_string(),
+        ],
+    }
+}
+
+pub fn queries(ctx: &PlanContext, config: &PersonsConfig) -> Vec<CanonicalQuery> {
+    vec![
+        person_lookup(ctx, config),
+        distinct_ids_of_person(ctx),
+        events_by_person(ctx),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::policy::Policy;
+    use crate::config::schema::{EventSchema, EventTable};
+
+    fn schema(multi_tenant: bool, expected_persons: Option<u64>) -> EventSchema {
+        EventSchema {
+            event_table: EventTable {
+                name: "events".to_string(),
+                multi_tenant,
+                expected_events_per_day: 10_000_000,
+                retention_days: 90,
+                ..Default::default()
+            },
+            persons: Some(PersonsConfig { expected_persons }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tables_replace_by_version() {
+        let schema = schema(true, None);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let persons = persons_table(&TableName::new("events"), &workload).to_sql();
+        assert!(persons.starts_with("CREATE TABLE events_persons ("));
+        assert!(persons.contains(
+            "    version UInt64\n) ENGINE = ReplacingMergeTree(version, is_deleted)\n\
+             ORDER BY (project_id, id);"
+        ));
+        let mapping = distinct_id_table(&TableName::new("events"), &workload).to_sql();
+        assert!(mapping.contains("person_id UUID"));
+        assert!(mapping.ends_with("ORDER BY (project_id, distinct_id);"));
+    }
+
+    #[test]
+    fn large_tables_read_with_argmax() {
+        let schema = schema(true, None);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let config = schema.persons.as_ref().unwrap();
+        let queries = queries(&ctx, config);
+
+        assert!(
+            queries[0]
+                .sql
+                .contains("argMax(properties, version) AS properties")
+        );
+        assert!(!queries[0].sql.contains("FINAL"));
+        assert!(queries[2].sql.contains(
+            "    FROM events_person_distinct_id\n    WHERE project_id = {project_id:UInt32}"
+        ));
+        assert!(
+            queries[2]
+                .sql
+                .contains("WHERE e.project_id = {project_id:UInt32}")
+        );
+        assert!(explain(&workload, config).contains("Set persons.expected_persons"));
+    }
+
+    #[test]
+    fn small_tables_read_with_final() {
+        let schema = schema(false, Some(200_000));
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let config = schema.persons.as_ref().unwrap();
+        let query = &queries(&ctx, config)[0];
+
+        assert_eq!(
+            query.sql,
+            "SELECT id, properties, created_at\nFROM events_persons FINAL\nWHERE id = {person_id:UUID};"
+        );
+        assert!(
+            distinct_id_table(&TableName::new("events"), &workload)
+                .to_sql()
+                .ends_with("ORDER BY (distinct_id);")
+        );
+        assert!(explain(&workload, config).contains("FINAL is affordable"));
+    }
+
+    #[test]
+    fn mapping_dictionary_replaces_the_join() {
+        let mut schema = schema(false, None);
+        schema.dictionaries = crate::config::parse_schema(
+            "\
+event_table:
+  name: events
+  expected_events_per_day: 1000
+  retention_days: 30
+dictionaries:
+  - name: person_ids
+    source_table: events_person_distinct_id
+    key: [{name: distinct_id, type: String}]
+    attributes: [{name: person_id, type: UUID}]
+    rows: 500000
+",
+        )
+        .unwrap()
+        .dictionaries;
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let query = &queries(&ctx, schema.persons.as_ref().unwrap())[2];
+
+        assert!(query.sql.starts_with(
+            "SELECT\n    dictGet('person_ids', 'person_id', (distinct_id)) AS person_id,"
+        ));
+        assert!(!query.sql.contains("JOIN"));
+        assert_eq!(
+            query.uses,
+            ["dictionary person_ids instead of a JOIN against events_person_distinct_id"]
+        );
+    }
+}
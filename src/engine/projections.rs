@@ -1,17 +1,23 @@
-pub fn daily_event_projection(table_name: &str) -> String {
-    format!(
-        "\
-ALTER TABLE {table_name}
-ADD PROJECTION daily_event_counts
-(
-    SELECT
-        project_id,
-        toDate(timestamp) AS day,
-        event_name,
-        count()
-    GROUP BY project_id, day, event_name
-);"
-    )
+use crate::sql::ast::{AlterCommand, Identifier, Statement, TableName};
+
+pub const DAILY_EVENT_PROJECTION: &str = "daily_event_counts";
+
+pub const DAILY_EVENT_SELECT: &str = "\
+SELECT
+    project_id,
+    toDate(timestamp) AS day,
+    event_name,
+    count()
+GROUP BY project_id, day, event_name";
+
+pub fn daily_event_projection(table_name: &TableName) -> Statement {
+    Statement::Alter {
+        table: table_name.clone(),
+        command: AlterCommand::AddProjection {
+            name: Identifier::new(DAILY_EVENT_PROJECTION),
+            select: DAILY_EVENT_SELECT.to_string(),
+        },
+    }
 }
 
 #[cfg(test)]
@@ -20,7 +26,7 @@ mod tests {
 
     #[test]
     fn generates_projection_sql() {
-        let sql = daily_event_projection("user_events");
+        let sql = daily_event_projection(&TableName::new("user_events")).to_sql();
         assert!(sql.starts_with("ALTER TABLE user_events"));
         assert!(sql.contains("ADD PROJECTION daily_event_counts"));
         assert!(sql.contains("toDate(timestamp) AS day"));
@@ -30,7 +36,7 @@ mod tests {
 
     #[test]
     fn uses_table_name() {
-        let sql = daily_event_projection("custom_events");
+        let sql = daily_event_projection(&TableName::new("custom_events")).to_sql();
         assert!(sql.starts_with("ALTER TABLE custom_events"));
     }
 }
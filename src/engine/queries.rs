@@ -0,0 +1,387 @@
+use crate::engine::partitioning::PartitionStrategy;
+use crate::engine::rules::PlanContext;
+use crate::engine::sampling::SAMPLING_EXPR;
+use crate::engine::timestamp;
+use crate::engine::{dictionaries, persons};
+
+// Name of the projection emitted by `projections::daily_event_projection`.
+const DAILY_PROJECTION: &str = "daily_event_counts";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter<'a> {
+    Eq(&'a str),
+    Range(&'a str),
+}
+
+impl<'a> Filter<'a> {
+    pub fn column(&self) -> &'a str {
+        match self {
+            Filter::Eq(c) | Filter::Range(c) => c,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CanonicalQuery {
+    pub name: &'static str,
+    pub sql: String,
+    // Which structures of the generated schema the query is written to hit.
+    pub uses: Vec<String>,
+}
+
+// The leading ORDER BY columns a set of filters can use for index analysis.
+// Equality filters extend the prefix; a range filter is still used but ends
+// it, since the granules after it are no longer contiguous.
+pub fn key_prefix(order_by: &[String], filters: &[Filter]) -> Vec<String> {
+    let mut prefix = Vec::new();
+    for col in order_by {
+        match filters.iter().find(|f| f.column() == col) {
+            Some(Filter::Eq(_)) => prefix.push(col.clone()),
+            Some(Filter::Range(_)) => {
+                prefix.push(col.clone());
+                break;
+            }
+            None => break,
+        }
+    }
+    prefix
+}
+
+pub fn partition_pruning(partition: &PartitionStrategy, filters: &[Filter]) -> Vec<&'static str> {
+    let mut cols = Vec::new();
+    if filters.iter().any(|f| f.column() == "timestamp") {
+        cols.push("timestamp");
+    }
+    if *partition == PartitionStrategy::DailyWithTenant
+        && filters.contains(&Filter::Eq("project_id"))
+    {
+        cols.push("project_id");
+    }
+    cols
+}
+
+fn uses(ctx: &PlanContext, filters: &[Filter]) -> Vec<String> {
+    let mut uses = Vec::new();
+    let pruned = partition_pruning(&ctx.partition, filters);
+    if !pruned.is_empty() {
+        uses.push(format!(
+            "partition pruning on {} (PARTITION BY {})",
+            pruned.join(", "),
+            ctx.partition.to_sql()
+        ));
+    }
+    let prefix = key_prefix(&ctx.order_by, filters);
+    if prefix.is_empty() {
+        uses.push("no ORDER BY prefix: reads every granule of the selected partitions".to_string());
+    } else {
+        uses.push(format!(
+            "ORDER BY prefix ({}) of ({})",
+            prefix.join(", "),
+            ctx.order_by.join(", ")
+        ));
+    }
+    uses
+}
+
+fn uses_for(ctx: &PlanContext, event_filter: bool) -> Vec<String> {
+    uses(ctx, &filters(ctx, event_filter))
+}
+
+// Tenant filter first, mirroring the sort key, so the query reads the way the
+// index is used.
+fn filters(ctx: &PlanContext, event_filter: bool) -> Vec<Filter<'static>> {
+    let mut filters = Vec::new();
+    if ctx.workload.multi_tenant {
+        filters.push(Filter::Eq("project_id"));
+    }
+    if event_filter {
+        filters.push(Filter::Eq("event_name"));
+    }
+    filters.push(Filter::Range("timestamp"));
+    filters
+}
+
+// Conditions in sort-key order; `from`/`to` bound `time` (timestamp or a
+// monotonic function of it).
+fn conditions(
+    ctx: &PlanContext,
+    event_condition: Option<&str>,
+    time: &str,
+    from: &str,
+    to: &str,
+) -> Vec<String> {
+    let mut conditions = Vec::new();
+    if ctx.workload.multi_tenant {
+        conditions.push("project_id = {project_id:UInt32}".to_string());
+    }
+    if let Some(cond) = event_condition {
+        conditions.push(cond.to_string());
+    }
+    conditions.push(format!("{time} >= {from}"));
+    conditions.push(format!("{time} < {to}"));
+    conditions
+}
+
+fn where_clause(ctx: &PlanContext, event_condition: Option<&str>) -> String {
+    conditions(
+        ctx,
+        event_condition,
+        "timestamp",
+        "{from:DateTime}",
+        "{to:DateTime}",
+    )
+    .join("\n  AND ")
+}
+
+fn event_counts(ctx: &PlanContext) -> CanonicalQuery {
+    let table = ctx.table();
+    // Only columns and aggregates present in the projection, so the optimizer
+    // can answer from it instead of raw rows. That includes the time filter:
+    // the projection stores days, not timestamps.
+    let sql = format!(
+        "SELECT\n    toDate(timestamp) AS day,\n    event_name,\n    count() AS events\n\
+         FROM {table}\nWHERE {}\nGROUP BY day, event_name\nORDER BY day, event_name;",
+        conditions(ctx, None, "toDate(timestamp)", "{from:Date}", "{to:Date}").join("\n  AND ")
+    );
+    let mut uses = vec![format!(
+        "projection {DAILY_PROJECTION} (pre-aggregated by project_id, day, event_name)"
+    )];
+    uses.extend(uses_for(ctx, false));
+    CanonicalQuery {
+        name: "Event counts over a time range",
+        sql,
+        uses,
+    }
+}
+
+fn daily_active_users(ctx: &PlanContext) -> CanonicalQuery {
+    let table = ctx.table();
+    let (sample, users) = if ctx.workload.sampling {
+        ("\nSAMPLE 1 / 10", "uniq(distinct_id) * any(_sample_factor)")
+    } else {
+        ("", "uniq(distinct_id)")
+    };
+    let sql = format!(
+        "SELECT\n    toDate(timestamp) AS day,\n    {users} AS dau\n\
+         FROM {table}{sample}\nWHERE {}\nGROUP BY day\nORDER BY day;",
+        where_clause(ctx, None)
+    );
+    let mut uses = uses_for(ctx, false);
+    if ctx.workload.sampling {
+        uses.push(format!(
+            "SAMPLE BY {SAMPLING_EXPR}: reads a tenth of users, scaled back up"
+        ));
+    }
+    let name = if ctx.workload.multi_tenant {
+        "Per-tenant daily active users"
+    } else {
+        "Daily active users"
+    };
+    CanonicalQuery { name, sql, uses }
+}
+
+fn funnel(ctx: &PlanContext) -> CanonicalQuery {
+    let table = ctx.table();
+    // windowFunnel wants a DateTime; DateTime64 is truncated like in TTL.
+    let ts = timestamp::ttl_column(&ctx.workload.timestamp);
+    let sql = format!(
+        "SELECT\n    level,\n    count() AS users\nFROM\n(\n    SELECT\n        distinct_id,\n        \
+         windowFunnel(86400)({ts}, event_name = {{step1:String}}, event_name = {{step2:String}}, \
+         event_name = {{step3:String}}) AS level\n    FROM {table}\n    WHERE {}\n    GROUP BY distinct_id\n)\n\
+         GROUP BY level\nORDER BY level;",
+        conditions(
+            ctx,
+            Some("event_name IN ({step1:String}, {step2:String}, {step3:String})"),
+            "timestamp",
+            "{from:DateTime}",
+            "{to:DateTime}",
+        )
+        .join("\n      AND ")
+    );
+    CanonicalQuery {
+        name: "Three-step funnel within 24 hours",
+        sql,
+        uses: uses_for(ctx, true),
+    }
+}
+
+fn retention(ctx: &PlanContext) -> CanonicalQuery {
+    let table = ctx.table();
+    let sql = format!(
+        "SELECT\n    sum(r[1]) AS cohort,\n    sum(r[2]) AS day_1,\n    sum(r[3]) AS day_7\nFROM\n(\n    SELECT\n        \
+         distinct_id,\n        retention(\n            toDate(timestamp) = {{cohort_day:Date}},\n            \
+         toDate(timestamp) = {{cohort_day:Date}} + 1,\n            toDate(timestamp) = {{cohort_day:Date}} + 7\n        ) AS r\n    \
+         FROM {table}\n    WHERE {}\n    GROUP BY distinct_id\n);",
+        conditions(
+            ctx,
+            None,
+            "timestamp",
+            "{cohort_day:Date}",
+            "{cohort_day:Date} + 8"
+        )
+        .join("\n      AND ")
+    );
+    CanonicalQuery {
+        name: "Day-1 / day-7 retention for a cohort",
+        sql,
+        uses: uses_for(ctx, false),
+    }
+}
+
+fn top_properties(ctx: &PlanContext) -> CanonicalQuery {
+    let table = ctx.table();
+    let sql = format!(
+        "SELECT\n    arrayJoin(JSONAllPaths(properties)) AS property,\n    count() AS events\n\
+         FROM {table}\nWHERE {}\nGROUP BY property\nORDER BY events DESC\nLIMIT 20;",
+        where_clause(ctx, Some("event_name = {event:String}"))
+    );
+    CanonicalQuery {
+        name: "Top properties sent with an event",
+        sql,
+        uses: uses_for(ctx, true),
+    }
+}
+
+pub fn library(ctx: &PlanContext) -> Vec<CanonicalQuery> {
+    let mut library = vec![
+        event_counts(ctx),
+        daily_active_users(ctx),
+        funnel(ctx),
+        retention(ctx),
+        top_properties(ctx),
+    ];
+    if let Some(config) = &ctx.workload.persons {
+        library.extend(persons::queries(ctx, config));
+    }
+    library.extend(dictionaries::queries(ctx));
+    library
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::policy::Policy;
+    use crate::config::schema::{EventSchema, EventTable};
+    use crate::config::workload::WorkloadProfile;
+
+    fn schema(events_per_day: u64, multi_tenant: bool, sampling: bool) -> EventSchema {
+        EventSchema {
+            event_table: EventTable {
+                name: "events".to_string(),
+                multi_tenant,
+                expected_events_per_day: events_per_day,
+                retention_days: 90,
+                sampling,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn cols(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn prefix_stops_at_range_column() {
+        let order_by = cols(&["project_id", "timestamp", "event_name"]);
+        let filters = [
+            Filter::Eq("project_id"),
+            Filter::Range("timestamp"),
+            Filter::Eq("event_name"),
+        ];
+        assert_eq!(key_prefix(&order_by, &filters), ["project_id", "timestamp"]);
+    }
+
+    #[test]
+    fn prefix_stops_at_gap() {
+        let order_by = cols(&["project_id", "event_name", "timestamp"]);
+        let filters = [Filter::Eq("project_id"), Filter::Range("timestamp")];
+        assert_eq!(key_prefix(&order_by, &filters), ["project_id"]);
+    }
+
+    #[test]
+    fn tenant_partitions_prune_on_project_id() {
+        let filters = [Filter::Eq("project_id"), Filter::Range("timestamp")];
+        assert_eq!(
+            partition_pruning(&PartitionStrategy::DailyWithTenant, &filters),
+            ["timestamp", "project_id"]
+        );
+        assert_eq!(
+            partition_pruning(&PartitionStrategy::Daily, &filters),
+            ["timestamp"]
+        );
+    }
+
+    #[test]
+    fn multi_tenant_queries_filter_on_project_id() {
+        let schema = schema(1_000_000, true, false);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let queries = library(&ctx);
+        assert_eq!(queries.len(), 5);
+        for q in &queries {
+            assert!(
+                q.sql.contains("project_id = {project_id:UInt32}"),
+                "{}",
+                q.name
+            );
+            assert!(q.sql.ends_with(';'));
+        }
+    }
+
+    #[test]
+    fn funnel_uses_event_name_prefix() {
+        let schema = schema(1_000_000, true, false);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let funnel = funnel(&ctx);
+        assert!(funnel.sql.contains("windowFunnel(86400)(timestamp,"));
+        assert!(
+            funnel
+                .uses
+                .contains(&"ORDER BY prefix (project_id, event_name, timestamp) of (project_id, event_name, timestamp, distinct_id)".to_string())
+        );
+    }
+
+    #[test]
+    fn event_counts_target_projection() {
+        let schema = schema(1_000_000, false, false);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let counts = event_counts(&ctx);
+        assert!(counts.uses[0].contains(DAILY_PROJECTION));
+        assert!(counts.sql.contains("GROUP BY day, event_name"));
+        assert!(!counts.sql.contains("project_id"));
+    }
+
+    #[test]
+    fn dau_samples_when_enabled() {
+        let schema = schema(1_000_000, false, true);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let dau = daily_active_users(&ctx);
+        assert!(dau.sql.contains("SAMPLE 1 / 10"));
+        assert!(dau.uses.iter().any(|u| u.starts_with("SAMPLE BY")));
+    }
+
+    #[test]
+    fn unkeyed_filters_are_flagged() {
+        // Single-tenant standard volume: event_name leads, retention doesn't filter on it.
+        let schema = schema(1_000_000, false, false);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let retention = retention(&ctx);
+        assert!(
+            retention
+                .uses
+                .iter()
+                .any(|u| u.starts_with("no ORDER BY prefix"))
+        );
+    }
+}
@@ -0,0 +1,537 @@
+use crate::engine::indexes;
+use crate::engine::projections::{self, DAILY_EVENT_PROJECTION};
+use crate::engine::queries::{Filter, key_prefix, partition_pruning};
+use crate::engine::rules::PlanContext;
+use crate::errors::SharpError;
+use crate::sql::ast::TableName;
+use crate::sql::builder;
+use crate::sql::query::{
+    SelectQuery, Source, Token, TokenKind, is_function_name, render, split_top_level, tokenize,
+};
+
+// Functions ClickHouse treats as monotonic for index analysis: a condition on
+// f(col) still narrows the range of col.
+const MONOTONIC: &[&str] = &[
+    "toDate",
+    "toDateTime",
+    "toStartOfMinute",
+    "toStartOfHour",
+    "toStartOfDay",
+    "toMonday",
+    "toStartOfWeek",
+    "toStartOfMonth",
+    "toYYYYMM",
+    "toYYYYMMDD",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum PredicateKind {
+    Eq,
+    Range,
+    Unusable(&'static str),
+}
+
+#[derive(Debug)]
+pub struct Predicate {
+    pub text: String,
+    pub column: Option<String>,
+    pub kind: PredicateKind,
+}
+
+impl Predicate {
+    pub fn filter(&self) -> Option<Filter<'_>> {
+        let column = self.column.as_deref()?;
+        match self.kind {
+            PredicateKind::Eq => Some(Filter::Eq(column)),
+            PredicateKind::Range => Some(Filter::Range(column)),
+            PredicateKind::Unusable(_) => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct QueryReport {
+    pub table: String,
+    pub predicates: Vec<Predicate>,
+    pub partition_pruning: Vec<&'static str>,
+    pub key_prefix: Vec<String>,
+    // The key column a filter would have to cover to extend the prefix.
+    pub next_key_column: Option<String>,
+    pub skip_indexes: Vec<String>,
+    pub projection: Option<&'static str>,
+    pub sample: bool,
+}
+
+impl QueryReport {
+    pub fn verdict(&self) -> &'static str {
+        match (
+            self.partition_pruning.is_empty(),
+            self.key_prefix.is_empty(),
+        ) {
+            (false, false) => "Pruned: partitions and the primary key both narrow the read.",
+            (true, false) => {
+                "Partial: the primary key narrows granules, but every partition is opened."
+            }
+            (false, true) => {
+                "Partition scan: only matching partitions are read, but every granule in them."
+            }
+            (true, true) => {
+                "Likely full scan: nothing in WHERE matches the partition key or the ORDER BY prefix."
+            }
+        }
+    }
+}
+
+fn depth_zero(tokens: &[Token]) -> Vec<(usize, &Token)> {
+    let mut depth = 0usize;
+    let mut out = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if token.is_symbol("(") || token.is_symbol("[") {
+            depth += 1;
+        } else if token.is_symbol(")") || token.is_symbol("]") {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 {
+            out.push((i, token));
+        }
+    }
+    out
+}
+
+// `col`, `alias.col` or a monotonic `f(col)`; returns the column and whether
+// it was wrapped in a function.
+fn column_of(expr: &[Token], qualifiers: &[&str]) -> Option<(String, bool)> {
+    match expr {
+        [col] if col.kind == TokenKind::Word => Some((col.text.clone(), false)),
+        [prefix, dot, col] if dot.is_symbol(".") && col.kind == TokenKind::Word => {
+            if qualifiers.contains(&prefix.text.as_str()) {
+                Some((col.text.clone(), false))
+            } else {
+                // JSON subcolumn, e.g. properties.plan
+                Some((format!("{}.{}", prefix.text, col.text), false))
+            }
+        }
+        [func, open, inner @ .., close]
+            if open.is_symbol("(")
+                && close.is_symbol(")")
+                && MONOTONIC.iter().any(|f| func.is_word(f)) =>
+        {
+            column_of(inner, qualifiers).map(|(col, _)| (col, true))
+        }
+        _ => None,
+    }
+}
+
+pub fn classify(tokens: &[Token], qualifiers: &[&str]) -> Predicate {
+    let text = render(tokens);
+    let unusable = |reason| Predicate {
+        text: text.clone(),
+        column: None,
+        kind: PredicateKind::Unusable(reason),
+    };
+
+    let top = depth_zero(tokens);
+    if top.iter().any(|(_, t)| t.is_word("OR")) {
+        return unusable("OR: the index only helps if every branch filters the same key column");
+    }
+    if tokens.first().is_some_and(|t| t.is_word("NOT")) {
+        return unusable("negated condition");
+    }
+
+    let operators = ["=", "==", "!=", "<>", "<", ">", "<=", ">="];
+    let Some(&(op_at, op)) = top.iter().find(|(_, t)| {
+        (t.kind == TokenKind::Symbol && operators.contains(&t.text.as_str()))
+            || ["IN", "NOT", "LIKE", "ILIKE", "BETWEEN"]
+                .iter()
+                .any(|kw| t.is_word(kw))
+    }) else {
+        return unusable("not a comparison the index can see into");
+    };
+
+    let (lhs, rhs) = (&tokens[..op_at], &tokens[op_at + 1..]);
+    let Some((column, wrapped)) = column_of(lhs, qualifiers).or_else(|| {
+        // `{from:DateTime} <= timestamp` reads the same as the flipped form.
+        if op.kind == TokenKind::Symbol {
+            column_of(rhs, qualifiers)
+        } else {
+            None
+        }
+    }) else {
+        return unusable("left side is not a column or a monotonic function of one");
+    };
+
+    let kind = if op.is_word("NOT") || op.is_symbol("!=") || op.is_symbol("<>") {
+        PredicateKind::Unusable("negation can't narrow the key range")
+    } else if op.is_word("ILIKE") {
+        PredicateKind::Unusable("ILIKE can't use the key")
+    } else if op.is_word("LIKE") {
+        match rhs.first() {
+            Some(t) if t.kind == TokenKind::Str && !t.text[1..].starts_with(['%', '_']) => {
+                PredicateKind::Range
+            }
+            Some(t) if t.kind == TokenKind::Str => {
+                PredicateKind::Unusable("LIKE with a leading wildcard")
+            }
+            _ => PredicateKind::Unusable("LIKE pattern is not a literal"),
+        }
+    } else if (op.is_symbol("=") || op.is_symbol("==") || op.is_word("IN")) && !wrapped {
+        PredicateKind::Eq
+    } else {
+        PredicateKind::Range
+    };
+
+    Predicate {
+        text,
+        column: Some(column),
+        kind,
+    }
+}
+
+// The SELECT that reads the table, with the conditions of every query
+// wrapped around it: ClickHouse pushes an outer WHERE down into the subquery.
+pub struct TableRead<'q> {
+    pub query: &'q SelectQuery,
+    pub predicates: Vec<&'q [Token]>,
+    // Aliases the table's columns can be qualified with, innermost first.
+    pub aliases: Vec<&'q str>,
+}
+
+// Names compare unquoted, so `events` matches `\`events\``; a table given
+// without a database matches it in any database.
+pub fn find_table<'q>(query: &'q SelectQuery, table: &TableName) -> Option<TableRead<'q>> {
+    let mut read = match query.source {
+        Source::Table(ref name)
+            if name == table || (table.database.is_none() && name.name == table.name) =>
+        {
+            TableRead {
+                query,
+                predicates: vec![],
+                aliases: vec![],
+            }
+        }
+        Source::Table(_) => return None,
+        Source::Subquery(ref inner) => find_table(inner, table)?,
+    };
+    read.predicates
+        .extend(query.predicates.iter().map(Vec::as_slice));
+    read.aliases.extend(query.alias.as_deref());
+    Some(read)
+}
+
+// The expressions the projection groups by, read from its definition:
+// `project_id`, `toDate(timestamp)` and `event_name`.
+fn projection_keys() -> Vec<String> {
+    let tokens = tokenize(projections::DAILY_EVENT_SELECT).expect("the projection parses");
+    let list: Vec<Token> = tokens
+        .into_iter()
+        .skip_while(|t| t.is_word("SELECT"))
+        .take_while(|t| !t.is_word("GROUP"))
+        .collect();
+    split_top_level(&list, |t| t.is_symbol(","))
+        .into_iter()
+        .filter(|expr| !expr.first().is_some_and(|t| t.is_word("count")))
+        .map(|expr| match expr.iter().position(|t| t.is_word("AS")) {
+            Some(alias) => render(&expr[..alias]),
+            None => render(&expr),
+        })
+        .collect()
+}
+
+// The projection can answer a count() over its keys; a table column used any
+// other way needs the raw rows.
+fn fits_projection(read: &TableRead, columns: &[String]) -> bool {
+    let keys = projection_keys();
+    let functions: Vec<&str> = keys
+        .iter()
+        .filter_map(|k| Some(k.split_once('(')?.0))
+        .collect();
+    let mut tokens: Vec<&Token> = read.query.select.iter().collect();
+    tokens.extend(read.predicates.iter().copied().flatten());
+    tokens.extend(read.query.group_by.iter().flatten());
+
+    let mut aggregates = false;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind != TokenKind::Word {
+            continue;
+        }
+        let is_call = tokens.get(i + 1).is_some_and(|t| t.is_symbol("("));
+        if is_call && is_function_name(token) {
+            if token.is_word("count") {
+                aggregates = true;
+            } else if !functions.contains(&token.text.as_str()) {
+                return false;
+            }
+            continue;
+        }
+        if !columns.contains(&token.text) || keys.contains(&token.text) {
+            continue;
+        }
+        // A column the projection only keeps wrapped, e.g. toDate(timestamp).
+        let wrapped = i >= 2
+            && tokens[i - 1].is_symbol("(")
+            && tokens.get(i + 1).is_some_and(|t| t.is_symbol(")"))
+            && keys.contains(&format!("{}({})", tokens[i - 2].text, token.text));
+        if !wrapped {
+            return false;
+        }
+    }
+    aggregates
+}
+
+pub fn check(ctx: &PlanContext, query: &SelectQuery) -> Result<QueryReport, SharpError> {
+    let table = ctx.table();
+    let Some(read) = find_table(query, &table) else {
+        return Err(SharpError::WrongTable(table.to_string()));
+    };
+
+    let columns: Vec<String> = builder::columns(ctx.schema)
+        .iter()
+        .map(|c| c.name.as_str().to_string())
+        .collect();
+    let mut qualifiers = vec![table.name.as_str()];
+    qualifiers.extend(&read.aliases);
+    let predicates: Vec<Predicate> = read
+        .predicates
+        .iter()
+        .map(|p| classify(p, &qualifiers))
+        .collect();
+    let filters: Vec<Filter> = predicates.iter().filter_map(Predicate::filter).collect();
+
+    let prefix = key_prefix(&ctx.order_by, &filters);
+    let ended_on_range = prefix
+        .last()
+        .is_some_and(|col| filters.contains(&Filter::Range(col)));
+    let next_key_column = if ended_on_range {
+        None
+    } else {
+        ctx.order_by.get(prefix.len()).cloned()
+    };
+
+    let skip_indexes = ctx
+        .schema
+        .event_table
+        .skip_indexes
+        .iter()
+        .filter(|idx| filters.iter().any(|f| indexes::covers(idx, f)))
+        .map(|idx| format!("{} ({})", idx.name, idx.index_type))
+        .collect();
+
+    Ok(QueryReport {
+        table: table.to_string(),
+        partition_pruning: partition_pruning(&ctx.partition, &filters),
+        key_prefix: prefix,
+        next_key_column,
+        skip_indexes,
+        projection: fits_projection(&read, &columns).then_some(DAILY_EVENT_PROJECTION),
+        sample: read.query.sample,
+        predicates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::policy::Policy;
+    use crate::config::schema::{EventSchema, EventTable, SessionsConfig, SkipIndexConfig};
+    use crate::config::workload::WorkloadProfile;
+    use crate::engine::queries::library;
+    use crate::sql::query::parse_select;
+
+    fn schema(multi_tenant: bool) -> EventSchema {
+        EventSchema {
+            event_table: EventTable {
+                name: "events".to_string(),
+                multi_tenant,
+                expected_events_per_day: 1_000_000,
+                retention_days: 90,
+                skip_indexes: vec![SkipIndexConfig {
+                    name: "idx_distinct_id".to_string(),
+                    expression: "distinct_id".to_string(),
+                    index_type: "bloom_filter(0.01)".to_string(),
+                    granularity: 4,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn report(multi_tenant: bool, sql: &str) -> QueryReport {
+        let schema = schema(multi_tenant);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        check(&ctx, &parse_select(sql).unwrap()).unwrap()
+    }
+
+    fn kind(sql: &str) -> PredicateKind {
+        classify(&tokenize(sql).unwrap(), &["events", "e"]).kind
+    }
+
+    #[test]
+    fn classifies_comparisons() {
+        assert_eq!(kind("project_id = 1"), PredicateKind::Eq);
+        assert_eq!(kind("e.event_name IN ('a', 'b')"), PredicateKind::Eq);
+        assert_eq!(kind("timestamp >= now() - 3600"), PredicateKind::Range);
+        assert_eq!(kind("toDate(timestamp) = today()"), PredicateKind::Range);
+        assert_eq!(kind("event_name LIKE 'sign%'"), PredicateKind::Range);
+        assert!(matches!(
+            kind("event_name LIKE '%up'"),
+            PredicateKind::Unusable(_)
+        ));
+        assert!(matches!(
+            kind("event_name != 'x'"),
+            PredicateKind::Unusable(_)
+        ));
+        assert!(matches!(kind("a = 1 OR b = 2"), PredicateKind::Unusable(_)));
+        assert!(matches!(kind("has(tags, 'x')"), PredicateKind::Unusable(_)));
+    }
+
+    #[test]
+    fn json_subcolumn_keeps_its_path() {
+        let p = classify(&tokenize("properties.plan = 'pro'").unwrap(), &["events"]);
+        assert_eq!(p.column.as_deref(), Some("properties.plan"));
+    }
+
+    #[test]
+    fn tenant_and_time_filters_prune() {
+        let r = report(
+            true,
+            "SELECT count() FROM events WHERE project_id = 1 AND event_name = 'a' \
+             AND timestamp >= '2024-01-01'",
+        );
+        assert_eq!(r.partition_pruning, ["timestamp"]);
+        assert_eq!(r.key_prefix, ["project_id", "event_name", "timestamp"]);
+        assert_eq!(r.next_key_column, None);
+        assert!(r.verdict().starts_with("Pruned"));
+    }
+
+    #[test]
+    fn missing_tenant_filter_is_a_full_scan() {
+        let r = report(true, "SELECT count() FROM events WHERE event_name = 'a'");
+        assert!(r.key_prefix.is_empty());
+        assert_eq!(r.next_key_column.as_deref(), Some("project_id"));
+        assert!(r.verdict().starts_with("Likely full scan"));
+    }
+
+    #[test]
+    fn reports_skip_index() {
+        let r = report(false, "SELECT * FROM events WHERE distinct_id = 'u1'");
+        assert_eq!(r.skip_indexes, ["idx_distinct_id (bloom_filter(0.01))"]);
+    }
+
+    #[test]
+    fn projection_only_for_matching_aggregates() {
+        let fits = report(
+            true,
+            "SELECT toDate(timestamp) AS day, count() FROM events \
+             WHERE project_id = 1 AND toDate(timestamp) >= '2024-01-01' GROUP BY day",
+        );
+        assert_eq!(fits.projection, Some(DAILY_EVENT_PROJECTION));
+
+        let raw_filter = report(
+            true,
+            "SELECT toDate(timestamp) AS day, count() FROM events \
+             WHERE timestamp >= '2024-01-01' GROUP BY day",
+        );
+        assert_eq!(raw_filter.projection, None);
+
+        let uniq = report(true, "SELECT uniq(distinct_id) FROM events");
+        assert_eq!(uniq.projection, None);
+    }
+
+    #[test]
+    fn projection_cannot_filter_on_session_id() {
+        let mut schema = schema(true);
+        schema.sessions = Some(SessionsConfig::default());
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let query =
+            parse_select("SELECT count() FROM events WHERE project_id = 1 AND session_id = 'abc'")
+                .unwrap();
+        assert_eq!(check(&ctx, &query).unwrap().projection, None);
+        let query = parse_select("SELECT count() FROM events WHERE project_id = 1").unwrap();
+        assert_eq!(
+            check(&ctx, &query).unwrap().projection,
+            Some(DAILY_EVENT_PROJECTION)
+        );
+    }
+
+    #[test]
+    fn reads_the_projection_keys_from_its_definition() {
+        assert_eq!(
+            projection_keys(),
+            ["project_id", "toDate(timestamp)", "event_name"]
+        );
+    }
+
+    #[test]
+    fn finds_table_inside_subquery() {
+        let r = report(
+            false,
+            "SELECT level, count() FROM (SELECT distinct_id, windowFunnel(60)(timestamp, \
+             event_name = 'a') AS level FROM db.events WHERE event_name IN ('a') \
+             GROUP BY distinct_id) GROUP BY level",
+        );
+        assert_eq!(r.key_prefix, ["event_name"]);
+    }
+
+    #[test]
+    fn outer_filters_reach_the_subquery() {
+        let r = report(
+            false,
+            "SELECT distinct_id FROM (SELECT * FROM events) AS e \
+             WHERE e.event_name IN ('a', 'b')",
+        );
+        assert_eq!(r.predicates.len(), 1);
+        assert_eq!(r.predicates[0].column.as_deref(), Some("event_name"));
+        assert_eq!(r.key_prefix, ["event_name"]);
+    }
+
+    #[test]
+    fn matches_table_names_unquoted() {
+        let query = parse_select("SELECT 1 FROM `analytics`.`user-events`").unwrap();
+        assert!(find_table(&query, &TableName::new("user-events")).is_some());
+        assert!(find_table(&query, &TableName::new("analytics.user-events")).is_some());
+        assert!(find_table(&query, &TableName::new("staging.user-events")).is_none());
+        // A dot inside a quoted name is part of the name.
+        let query = parse_select("SELECT 1 FROM `events.v2`").unwrap();
+        assert!(find_table(&query, &TableName::new("v2")).is_none());
+    }
+
+    #[test]
+    fn rejects_other_tables() {
+        let schema = schema(false);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let query = parse_select("SELECT 1 FROM sessions").unwrap();
+        let err = check(&ctx, &query).unwrap_err();
+        assert!(matches!(err, SharpError::WrongTable(_)));
+        assert_eq!(err.to_string(), "query does not read from table 'events'");
+    }
+
+    // The query library and the checker should agree with each other.
+    #[test]
+    fn canonical_queries_pass_their_own_check() {
+        for multi_tenant in [false, true] {
+            let schema = schema(multi_tenant);
+            let workload = WorkloadProfile::from_schema(&schema);
+            let policy = Policy::default();
+            let ctx = PlanContext::new(&schema, &workload, &policy);
+            for query in library(&ctx) {
+                let report = check(&ctx, &parse_select(&query.sql).unwrap()).unwrap();
+                assert!(!report.partition_pruning.is_empty(), "{}", query.name);
+                assert_eq!(
+                    report.projection.is_some(),
+                    query
+                        .uses
+                        .iter()
+                        .any(|u| u.contains(DAILY_EVENT_PROJECTION)),
+                    "{}",
+                    query.name
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use crate::config::policy::Policy;
+use crate::config::schema::EventSchema;
+use crate::config::workload::WorkloadProfile;
+use crate::engine::health::TableHealth;
+use crate::engine::ordering::choose_order_by;
+use crate::engine::partitioning::{PartitionStrategy, choose_partition_strategy};
+use crate::sql::ast::TableName;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    Retention,
+    Storage,
+    Scaling,
+    MultiTenancy,
+    Ingestion,
+    Queries,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl Category {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Category::Retention => "retention",
+            Category::Storage => "storage",
+            Category::Scaling => "scaling",
+            Category::MultiTenancy => "multi-tenancy",
+            Category::Ingestion => "ingestion",
+            Category::Queries => "queries",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub rule_id: &'static str,
+    pub severity: Severity,
+    pub category: Category,
+    pub message: String,
+    pub rationale: String,
+    pub suggested_sql: Option<String>,
+}
+
+impl Finding {
+    pub fn with_sql(mut self, sql: String) -> Self {
+        self.suggested_sql = Some(sql);
+        self
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+// Everything a rule may look at, computed once so rules agree on the plan they
+// are judging.
+pub struct PlanContext<'a> {
+    pub schema: &'a EventSchema,
+    pub workload: &'a WorkloadProfile,
+    pub policy: &'a Policy,
+    pub partition: PartitionStrategy,
+    pub order_by: Vec<String>,
+}
+
+impl<'a> PlanContext<'a> {
+    pub fn new(schema: &'a EventSchema, workload: &'a WorkloadProfile, policy: &'a Policy) -> Self {
+        Self {
+            schema,
+            workload,
+            policy,
+            partition: choose_partition_strategy(workload, policy),
+            order_by: choose_order_by(workload, policy),
+        }
+    }
+
+    pub fn table_name(&self) -> &str {
+        &self.schema.event_table.name
+    }
+
+    // The event table as it appears in SQL, quoted where needed.
+    pub fn table(&self) -> TableName {
+        TableName::new(self.table_name())
+    }
+}
+
+pub trait Rule {
+    fn id(&self) -> &'static str;
+    fn severity(&self) -> Severity;
+    fn category(&self) -> Category;
+    fn evaluate(&self, ctx: &PlanContext) -> Option<Finding>;
+
+    // Schema keys the rule judges; a `sharp:allow` comment on one of them
+    // silences it.
+    fn keys(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    fn finding(&self, message: String, rationale: &str) -> Finding {
+        Finding {
+            rule_id: self.id(),
+            severity: self.severity(),
+            category: self.category(),
+            message,
+            rationale: rationale.to_string(),
+            suggested_sql: None,
+        }
+    }
+}
+
+// Judges a running table from the metrics `sharp health` collects, with no
+// schema or plan to go on.
+pub trait LiveRule {
+    fn id(&self) -> &'static str;
+    fn severity(&self) -> Severity;
+    fn category(&self) -> Category;
+    fn evaluate(&self, table: &TableName, health: &TableHealth) -> Option<Finding>;
+
+    fn finding(&self, message: String, rationale: &str) -> Finding {
+        Finding {
+            rule_id: self.id(),
+            severity: self.severity(),
+            category: self.category(),
+            message,
+            rationale: rationale.to_string(),
+            suggested_sql: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::EventTable;
+
+    struct AlwaysFires;
+
+    impl Rule for AlwaysFires {
+        fn id(&self) -> &'static str {
+            "always-fires"
+        }
+        fn severity(&self) -> Severity {
+            Severity::Warning
+        }
+        fn category(&self) -> Category {
+            Category::Storage
+        }
+        fn evaluate(&self, ctx: &PlanContext) -> Option<Finding> {
+            Some(self.finding(format!("table {}", ctx.table_name()), "because"))
+        }
+    }
+
+    fn schema() -> EventSchema {
+        EventSchema {
+            event_table: EventTable {
+                name: "events".to_string(),
+                multi_tenant: true,
+                expected_events_per_day: 500_000_000,
+                retention_days: 30,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn context_computes_plan() {
+        let schema = schema();
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        assert_eq!(ctx.partition, PartitionStrategy::DailyWithTenant);
+        assert_eq!(ctx.order_by[0], "project_id");
+        assert_eq!(ctx.table_name(), "events");
+    }
+
+    #[test]
+    fn finding_carries_rule_metadata() {
+        let schema = schema();
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let finding = AlwaysFires.evaluate(&ctx).unwrap();
+        assert_eq!(finding.rule_id, "always-fires");
+        assert_eq!(finding.severity, Severity::Warning);
+        assert_eq!(finding.category, Category::Storage);
+        assert_eq!(finding.rationale, "because");
+        assert_eq!(finding.suggested_sql, None);
+        assert_eq!(finding.to_string(), "table events");
+    }
+
+    #[test]
+    fn with_sql_attaches_statement() {
+        let schema = schema();
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let ctx = PlanContext::new(&schema, &workload, &policy);
+        let finding = AlwaysFires
+            .evaluate(&ctx)
+            .unwrap()
+            .with_sql("SELECT 1;".to_string());
+        assert_eq!(finding.suggested_sql.as_deref(), Some("SELECT 1;"));
+    }
+
+    #[test]
+    fn severities_order_by_impact() {
+        assert!(Severity::Error > Severity::Warning);
+        assert!(Severity::Warning > Severity::Info);
+    }
+}
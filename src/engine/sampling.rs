@@ -0,0 +1,43 @@
+use crate::config::workload::WorkloadProfile;
+
+// SAMPLE BY needs an unsigned integer expression that is part of the primary
+// key. Hashing distinct_id gives a UInt64 spread uniformly across users, so
+// `SAMPLE 0.1` reads a tenth of users (with all their events) instead of a
+// skewed slice of the key space.
+pub const SAMPLING_EXPR: &str = "cityHash64(distinct_id)";
+
+pub fn suggest(workload: &WorkloadProfile) -> Option<String> {
+    if workload.sampling {
+        Some(SAMPLING_EXPR.to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(sampling: bool) -> WorkloadProfile {
+        WorkloadProfile {
+            events_per_day: 1_000_000,
+            multi_tenant: false,
+            retention_days: 90,
+            sampling,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn suggests_hash_of_distinct_id_when_enabled() {
+        assert_eq!(
+            suggest(&profile(true)),
+            Some("cityHash64(distinct_id)".to_string())
+        );
+    }
+
+    #[test]
+    fn returns_none_when_disabled() {
+        assert_eq!(suggest(&profile(false)), None);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::config::policy::Policy;
+use crate::config::schema::SessionsConfig;
+use crate::config::workload::WorkloadProfile;
+use crate::engine::partitioning::{PartitionStrategy, choose_partition_strategy};
+use crate::engine::{ordering, timestamp, ttl};
+use crate::sql::ast::{ColumnExpr, CreateTable, Identifier, Statement, TableName};
+use crate::sql::types::ClickHouseType;
+
+pub fn table_name(events_table: &TableName) -> TableName {
+    events_table.with_suffix("_sessions")
+}
+
+// The sessions table as a workload of its own: one row per session per day,
+// kept as long as the events it summarises.
+fn session_workload(workload: &WorkloadProfile, config: &SessionsConfig) -> WorkloadProfile {
+    WorkloadProfile {
+        events_per_day: workload.events_per_day / u64::from(config.events_per_session.max(1)),
+        multi_tenant: workload.multi_tenant,
+        retention_days: workload.retention_days,
+        timestamp: workload.timestamp.clone(),
+        ..Default::default()
+    }
+}
+
+pub fn partition_strategy(
+    workload: &WorkloadProfile,
+    config: &SessionsConfig,
+    policy: &Policy,
+) -> PartitionStrategy {
+    choose_partition_strategy(&session_workload(workload, config), policy)
+}
+
+fn column(name: &str, data_type: ClickHouseType) -> ColumnExpr {
+    ColumnExpr {
+        name: Identifier::new(name),
+        data_type,
+        default: None,
+    }
+}
+
+fn simple(function: &str, data_type: ClickHouseType) -> ClickHouseType {
+    ClickHouseType::SimpleAggregateFunction(function.to_string(), Box::new(data_type))
+}
+
+// Aggregate functions read LowCardinality arguments as the plain type, so the
+// state is declared over String even though event_name is LowCardinality.
+fn event_name_by(function: &str, ts: ClickHouseType) -> ClickHouseType {
+    ClickHouseType::AggregateFunction(function.to_string(), vec![ClickHouseType::String, ts])
+}
+
+// `timestamp` is the first event each insert block saw for the session. It
+// is a plain column so the event table's partition and TTL expressions apply
+// unchanged; a session crossing a partition boundary leaves one row on each
+// side, which the -Merge combinators fold back together at query time.
+pub fn sessions_table(
+    events: &CreateTable,
+    workload: &WorkloadProfile,
+    config: &SessionsConfig,
+    policy: &Policy,
+) -> CreateTable {
+    let ts = timestamp::column_type(&workload.timestamp);
+    let sessions = session_workload(workload, config);
+    CreateTable {
+        table_name: table_name(&events.table_name),
+        columns: vec![
+            column("project_id", ClickHouseType::UInt(32)),
+            column("distinct_id", ClickHouseType::String),
+            column("session_id", ClickHouseType::String),
+            column("timestamp", ts.clone()),
+            column("session_start", simple("min", ts.clone())),
+            column("session_end", simple("max", ts.clone())),
+            column("event_count", simple("sum", ClickHouseType::UInt(64))),
+            column("entry_event", event_name_by("argMin", ts.clone())),
+            column("exit_event", event_name_by("argMax", ts)),
+        ],
+        engine: "AggregatingMergeTree".to_string(),
+        partition_by: Some(partition_strategy(workload, config, policy).to_sql()),
+        order_by: ordering::session_order_by(workload),
+        sample_by: None,
+        ttl: ttl::suggest(&sessions),
+        settings: vec![],
+    }
+}
+
+// Events without a session id (server-side events, say) are left out rather
+// than collapsed into one giant empty session per user. The aggregates name
+// the event table's timestamp explicitly: bare `timestamp` would resolve to
+// the `min(timestamp) AS timestamp` alias and nest one aggregate in another.
+pub fn materialized_view(events: &CreateTable, sessions: &CreateTable) -> Statement {
+    let select = format!(
+        "\
+SELECT
+    project_id,
+    distinct_id,
+    session_id,
+    min({events}.timestamp) AS timestamp,
+    min({events}.timestamp) AS session_start,
+    max({events}.timestamp) AS session_end,
+    count() AS event_count,
+    argMinState(event_name, {events}.timestamp) AS entry_event,
+    argMaxState(event_name, {events}.timestamp) AS exit_event
+FROM {events}
+WHERE session_id != ''
+GROUP BY project_id, distinct_id, session_id",
+        events = events.table_name
+    );
+    Statement::CreateMaterializedView {
+        name: sessions.table_name.with_suffix("_mv"),
+        to: sessions.table_name.clone(),
+        columns: vec![],
+        select,
+    }
+}
+
+pub fn explain(workload: &WorkloadProfile, config: &SessionsConfig, policy: &Policy) -> String {
+    let sessions = session_workload(workload, config);
+    let partition = choose_partition_strategy(&sessions, policy);
+    format!(
+        "~{} sessions/day at {} events per session. Rows are pre-aggregated per session by an \
+         AggregatingMergeTree keyed on ({}), so session lookups and funnels read one row per \
+         session instead of every event. Sized as a {} rows/day workload: {}",
+        sessions.events_per_day,
+        config.events_per_session,
+        ordering::session_order_by(workload).join(", "),
+        sessions.events_per_day,
+        partition.explain(&sessions, policy)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::{EventSchema, EventTable};
+    use crate::sql::builder::build_create_table_sql;
+
+    fn schema(events_per_day: u64) -> EventSchema {
+        EventSchema {
+            event_table: EventTable {
+                name: "events".to_string(),
+                multi_tenant: true,
+                expected_events_per_day: events_per_day,
+                retention_days: 90,
+                ..Default::default()
+            },
+            sessions: Some(SessionsConfig::default()),
+            ..Default::default()
+        }
+    }
+
+    fn events(schema: &EventSchema) -> CreateTable {
+        build_create_table_sql(
+            schema,
+            "toYYYYMMDD(timestamp)",
+            &["project_id".to_string()],
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn sessions_table_is_partitioned_for_its_own_volume() {
+        // 50M events/day is daily territory, but 2.5M sessions/day is monthly.
+        let schema = schema(50_000_000);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let config = SessionsConfig::default();
+        let table = sessions_table(&events(&schema), &workload, &config, &Policy::default());
+        let sql = table.to_sql();
+
+        assert!(sql.starts_with("CREATE TABLE events_sessions ("));
+        assert!(sql.contains("ENGINE = AggregatingMergeTree\nPARTITION BY toYYYYMM(timestamp)"));
+        assert!(sql.contains("ORDER BY (project_id, distinct_id, session_id)"));
+        assert!(sql.contains("TTL timestamp + INTERVAL 90 DAY"));
+        assert!(sql.contains("entry_event AggregateFunction(argMin, String, DateTime)"));
+        assert!(
+            explain(&workload, &config, &Policy::default()).starts_with("~2500000 sessions/day")
+        );
+    }
+
+    #[test]
+    fn view_reads_from_the_event_table() {
+        let schema = schema(1_000_000);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let events = events(&schema);
+        let sessions = sessions_table(
+            &events,
+            &workload,
+            &SessionsConfig::default(),
+            &Policy::default(),
+        );
+        let view = materialized_view(&events, &sessions).to_sql();
+
+        assert!(
+            view.starts_with("CREATE MATERIALIZED VIEW events_sessions_mv TO events_sessions AS")
+        );
+        assert!(view.ends_with(
+            "AS
+SELECT
+    project_id,
+    distinct_id,
+    session_id,
+    min(events.timestamp) AS timestamp,
+    min(events.timestamp) AS session_start,
+    max(events.timestamp) AS session_end,
+    count() AS event_count,
+    argMinState(event_name, events.timestamp) AS entry_event,
+    argMaxState(event_name, events.timestamp) AS exit_event
+FROM events
+WHERE session_id != ''
+GROUP BY project_id, distinct_id, session_id;"
+        ));
+        assert!(
+            events
+                .columns
+                .iter()
+                .any(|c| c.name.as_str() == "session_id")
+        );
+    }
+}
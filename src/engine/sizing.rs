@@ -0,0 +1,136 @@
+use crate::config::schema::EventSchema;
+use crate::config::workload::WorkloadProfile;
+use crate::engine::partitioning::PartitionStrategy;
+
+// Typical uncompressed event with a dozen properties, used when the schema
+// has no data_profile measured from a sample.
+const DEFAULT_ROW_BYTES: u64 = 300;
+// Event data normally compresses 5-10x with the default codecs.
+const COMPRESSION_RATIO: u64 = 6;
+// Unbounded retention is sized for the first year.
+const UNBOUNDED_DAYS: u64 = 365;
+
+#[derive(Debug, PartialEq)]
+pub struct Estimate {
+    pub rows_per_day: u64,
+    pub retained_days: u64,
+    pub retained_rows: u64,
+    pub row_bytes: u64,
+    // Whether row_bytes comes from data_profile or the default.
+    pub measured: bool,
+    pub raw_bytes_per_day: u64,
+    pub disk_bytes: u64,
+    pub partitions: u64,
+    // Partitions are counted per tenant when the tenant count is unknown.
+    pub per_tenant: bool,
+}
+
+pub fn estimate(
+    schema: &EventSchema,
+    workload: &WorkloadProfile,
+    partition: &PartitionStrategy,
+) -> Estimate {
+    let measured = schema.data_profile.as_ref().and_then(|p| p.avg_row_bytes);
+    let row_bytes = measured.unwrap_or(DEFAULT_ROW_BYTES);
+    let retained_days = match workload.retention_days {
+        0 => UNBOUNDED_DAYS,
+        days => u64::from(days),
+    };
+    let retained_rows = workload.events_per_day.saturating_mul(retained_days);
+    let tenants = schema.data_profile.as_ref().and_then(|p| p.tenants);
+
+    let (partitions, per_tenant) = match partition {
+        PartitionStrategy::Monthly => (retained_days.div_ceil(30), false),
+        PartitionStrategy::Daily => (retained_days, false),
+        PartitionStrategy::DailyWithTenant => match tenants {
+            Some(n) => (retained_days * u64::from(n), false),
+            None => (retained_days, true),
+        },
+    };
+
+    Estimate {
+        rows_per_day: workload.events_per_day,
+        retained_days,
+        retained_rows,
+        row_bytes,
+        measured: measured.is_some(),
+        raw_bytes_per_day: workload.events_per_day.saturating_mul(row_bytes),
+        disk_bytes: retained_rows.saturating_mul(row_bytes) / COMPRESSION_RATIO,
+        partitions,
+        per_tenant,
+    }
+}
+
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schema::{DataProfile, EventTable};
+
+    fn schema(retention_days: u32, profile: Option<DataProfile>) -> EventSchema {
+        EventSchema {
+            event_table: EventTable {
+                name: "events".to_string(),
+                multi_tenant: true,
+                expected_events_per_day: 10_000_000,
+                retention_days,
+                ..Default::default()
+            },
+            data_profile: profile,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sizes_retained_data() {
+        let schema = schema(30, None);
+        let workload = WorkloadProfile::from_schema(&schema);
+        let estimate = estimate(&schema, &workload, &PartitionStrategy::Daily);
+        assert_eq!(estimate.retained_rows, 300_000_000);
+        assert_eq!(estimate.raw_bytes_per_day, 3_000_000_000);
+        assert_eq!(estimate.disk_bytes, 15_000_000_000);
+        assert_eq!(estimate.partitions, 30);
+        assert!(!estimate.measured);
+    }
+
+    #[test]
+    fn uses_measured_profile_and_tenants() {
+        let schema = schema(
+            0,
+            Some(DataProfile {
+                avg_row_bytes: Some(120),
+                tenants: Some(50),
+                ..Default::default()
+            }),
+        );
+        let workload = WorkloadProfile::from_schema(&schema);
+        let estimate = estimate(&schema, &workload, &PartitionStrategy::DailyWithTenant);
+        assert_eq!(estimate.retained_days, 365);
+        assert_eq!(estimate.row_bytes, 120);
+        assert_eq!(estimate.partitions, 365 * 50);
+        assert!(estimate.measured && !estimate.per_tenant);
+
+        let monthly = super::estimate(&schema, &workload, &PartitionStrategy::Monthly);
+        assert_eq!(monthly.partitions, 13);
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(human_bytes(512), "512 B");
+        assert_eq!(human_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
@@ -0,0 +1,187 @@
+use crate::config::schema::TimestampConfig;
+use crate::sql::types::ClickHouseType;
+
+pub fn column_type(ts: &TimestampConfig) -> ClickHouseType {
+    match ts.precision {
+        0 => ClickHouseType::DateTime(ts.timezone.clone()),
+        precision => ClickHouseType::DateTime64 {
+            precision,
+            timezone: ts.timezone.clone(),
+        },
+    }
+}
+
+pub fn now_expr(ts: &TimestampConfig) -> String {
+    match ts.precision {
+        0 => "now()".to_string(),
+        p => format!("now64({p})"),
+    }
+}
+
+// TTL expressions must evaluate to Date or DateTime; ClickHouse rejects a bare
+// DateTime64 column there, so sub-second timestamps are truncated first.
+pub fn ttl_column(ts: &TimestampConfig) -> &'static str {
+    if ts.precision == 0 {
+        "timestamp"
+    } else {
+        "toDateTime(timestamp)"
+    }
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date.
+pub fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (i64::from(m) + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + i64::from(d) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Inverse of days_from_civil: (year, month, day).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+pub fn explain(ts: &TimestampConfig) -> String {
+    let precision = match ts.precision {
+        0 => "Second precision: plain DateTime (4 bytes/row).".to_string(),
+        3 => "Millisecond precision: DateTime64(3) (8 bytes/row).".to_string(),
+        6 => "Microsecond precision: DateTime64(6) (8 bytes/row).".to_string(),
+        p => format!("{p} fractional digits: DateTime64({p}) (8 bytes/row)."),
+    };
+
+    let timezone = match ts.timezone.as_deref() {
+        Some(tz) => format!(
+            " Timezone pinned to '{tz}': day boundaries for partitions, TTL and projections \
+             are the same on every server."
+        ),
+        None => " No timezone configured: values are stored as UTC instants but \
+                 rendered and bucketed in the server timezone."
+            .to_string(),
+    };
+
+    let ingested_at = if ts.ingested_at {
+        format!(
+            " ingested_at defaults to {} so late-arriving events can be told apart from event time.",
+            now_expr(ts)
+        )
+    } else {
+        String::new()
+    };
+
+    format!("{precision}{timezone}{ingested_at}")
+}
+
+pub fn timezone_warning(ts: &TimestampConfig) -> Option<String> {
+    if ts.timezone.is_some() {
+        return None;
+    }
+    Some(
+        "Warning: no timezone set on the timestamp column, so toYYYYMMDD/toYYYYMM \
+         use the server timezone. Servers in different timezones will cut partitions \
+         at different instants; set `timestamp.timezone` (e.g. 'UTC')."
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(precision: u8, timezone: Option<&str>) -> TimestampConfig {
+        TimestampConfig {
+            precision,
+            timezone: timezone.map(String::from),
+            ingested_at: false,
+        }
+    }
+
+    #[test]
+    fn default_is_plain_datetime() {
+        assert_eq!(
+            column_type(&TimestampConfig::default()).to_string(),
+            "DateTime"
+        );
+    }
+
+    #[test]
+    fn datetime_with_timezone() {
+        assert_eq!(
+            column_type(&ts(0, Some("UTC"))).to_string(),
+            "DateTime('UTC')"
+        );
+    }
+
+    #[test]
+    fn millisecond_precision() {
+        assert_eq!(column_type(&ts(3, None)).to_string(), "DateTime64(3)");
+    }
+
+    #[test]
+    fn microsecond_precision_with_timezone() {
+        assert_eq!(
+            column_type(&ts(6, Some("Europe/Berlin"))).to_string(),
+            "DateTime64(6, 'Europe/Berlin')"
+        );
+    }
+
+    #[test]
+    fn now_expr_matches_precision() {
+        assert_eq!(now_expr(&ts(0, None)), "now()");
+        assert_eq!(now_expr(&ts(3, None)), "now64(3)");
+    }
+
+    #[test]
+    fn ttl_column_truncates_datetime64() {
+        assert_eq!(ttl_column(&ts(0, None)), "timestamp");
+        assert_eq!(ttl_column(&ts(3, Some("UTC"))), "toDateTime(timestamp)");
+    }
+
+    #[test]
+    fn explain_mentions_precision_and_timezone() {
+        let explanation = explain(&ts(3, Some("UTC")));
+        assert!(explanation.contains("Millisecond"));
+        assert!(explanation.contains("'UTC'"));
+    }
+
+    #[test]
+    fn explain_mentions_ingested_at_default() {
+        let cfg = TimestampConfig {
+            ingested_at: true,
+            ..ts(6, None)
+        };
+        assert!(explain(&cfg).contains("now64(6)"));
+    }
+
+    #[test]
+    fn warns_only_without_timezone() {
+        assert!(
+            timezone_warning(&ts(0, None))
+                .unwrap()
+                .contains("server timezone")
+        );
+        assert_eq!(timezone_warning(&ts(0, Some("UTC"))), None);
+    }
+
+    #[test]
+    fn civil_days_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2024, 3, 1), 19_783);
+        for days in [-1, 0, 59, 19_783, 20_000] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    }
+}
@@ -1,9 +1,11 @@
 use crate::config::workload::WorkloadProfile;
+use crate::engine::timestamp::ttl_column;
 
 pub fn suggest(workload: &WorkloadProfile) -> Option<String> {
     if workload.retention_days > 0 {
         Some(format!(
-            "timestamp + INTERVAL {} DAY",
+            "{} + INTERVAL {} DAY",
+            ttl_column(&workload.timestamp),
             workload.retention_days
         ))
     } else {
@@ -20,6 +22,7 @@ mod tests {
             events_per_day: 1_000_000,
             multi_tenant: false,
             retention_days,
+            ..Default::default()
         }
     }
 
@@ -35,6 +38,16 @@ mod tests {
         assert_eq!(result, Some("timestamp + INTERVAL 1 DAY".to_string()));
     }
 
+    #[test]
+    fn wraps_datetime64_for_ttl() {
+        let mut w = profile(30);
+        w.timestamp.precision = 3;
+        assert_eq!(
+            suggest(&w),
+            Some("toDateTime(timestamp) + INTERVAL 30 DAY".to_string())
+        );
+    }
+
     #[test]
     fn returns_none_for_zero_retention() {
         let result = suggest(&profile(0));
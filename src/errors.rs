@@ -6,8 +6,62 @@ pub enum SharpError {
     #[error("failed to read file '{}': {}", .0.display(), .1)]
     IoError(PathBuf, #[source] std::io::Error),
 
+    #[error("failed to write '{}': {}", .0.display(), .1)]
+    WriteError(PathBuf, #[source] std::io::Error),
+
+    #[error("'{}' already uses that migration version; write to an empty directory", .0.display())]
+    MigrationExists(PathBuf),
+
     #[error("invalid YAML schema: {0}")]
     YamlError(#[from] serde_yaml::Error),
+
+    #[error("invalid schema: {0}")]
+    InvalidSchema(String),
+
+    #[error("could not parse query: {0}")]
+    InvalidQuery(String),
+
+    #[error("query does not read from table '{0}'")]
+    WrongTable(String),
+
+    #[error("invalid identifier {0}")]
+    InvalidIdentifier(String),
+
+    #[error("invalid type {0}")]
+    InvalidType(String),
+
+    #[error("invalid query log: {0}")]
+    QueryLogError(String),
+
+    #[error("invalid sample: {0}")]
+    SampleError(String),
+
+    #[error("invalid ClickHouse URL {0}")]
+    InvalidUrl(String),
+
+    #[error("could not reach ClickHouse at {0}: {1}")]
+    ConnectionError(String, #[source] std::io::Error),
+
+    #[error("invalid ClickHouse credentials: {0}")]
+    InvalidCredentials(String),
+
+    #[error("ClickHouse rejected the credentials: {0}")]
+    AuthError(String),
+
+    #[error("ClickHouse returned an error: {message}")]
+    ServerError { code: Option<u32>, message: String },
+
+    #[error("table '{0}' does not exist")]
+    TableNotFound(String),
+
+    #[error("invalid health snapshot: {0}")]
+    SnapshotError(String),
+
+    #[error("invalid policy file '{}': {}", .0.display(), .1)]
+    PolicyError(PathBuf, #[source] serde_yaml::Error),
+
+    #[error("invalid policy file '{}': {}", .0.display(), .1)]
+    InvalidPolicy(PathBuf, String),
 }
 
 #[cfg(test)]
@@ -40,4 +94,19 @@ mod tests {
         let sharp_err: SharpError = yaml_err.unwrap_err().into();
         assert!(matches!(sharp_err, SharpError::YamlError(_)));
     }
+
+    #[test]
+    fn invalid_schema_display() {
+        let err = SharpError::InvalidSchema("bad precision".to_string());
+        assert_eq!(err.to_string(), "invalid schema: bad precision");
+    }
+
+    #[test]
+    fn policy_error_display_includes_path() {
+        let yaml_err: Result<serde_yaml::Value, _> = serde_yaml::from_str("{{bad");
+        let err =
+            SharpError::PolicyError(PathBuf::from("sharp-policy.yaml"), yaml_err.unwrap_err());
+        let msg = err.to_string();
+        assert!(msg.starts_with("invalid policy file 'sharp-policy.yaml'"));
+    }
 }
@@ -1,4 +1,5 @@
 mod cli;
+mod client;
 mod config;
 mod engine;
 mod errors;
@@ -6,11 +7,15 @@ mod output;
 mod sql;
 
 use clap::Parser;
-use cli::{Cli, Command};
+use cli::{Cli, Command, DenyLevel, ExplainFormat, FakeFormat, LintFormat, MigrationLayout};
+use config::policy::FormatOptions;
 use config::workload::WorkloadProfile;
-use engine::ordering;
-use engine::partitioning::choose_partition_strategy;
-use sql::builder::build_create_table_sql;
+use engine::query_check::PredicateKind;
+use engine::rules::{PlanContext, Severity};
+use errors::SharpError;
+use sql::ast::{Statement, TableName};
+use std::io::Write;
+use std::path::PathBuf;
 
 fn main() {
     let cli = Cli::parse();
@@ -19,61 +24,431 @@ fn main() {
         Command::Init => {
             println!("Initialized default schema config.");
         }
-        Command::Generate { schema } => {
-            let event_schema = match config::load_schema(&schema) {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Error: {e}");
-                    std::process::exit(1);
+        Command::Generate {
+            schema,
+            out_dir,
+            layout,
+            format,
+        } => {
+            let event_schema = or_exit(config::load_schema(&schema));
+            let policy = or_exit(config::policy::resolve(cli.policy.as_deref(), &schema));
+            let workload = WorkloadProfile::from_schema(&event_schema);
+            or_exit(engine::ddl::validate(&event_schema, &workload, &policy));
+            let format = format.resolve(&policy.format);
+            let table = &TableName::new(&event_schema.event_table.name);
+
+            // Files hold the objects apply would create; the erasure runbook
+            // is for operators and always goes to stdout.
+            if let Some(dir) = &out_dir {
+                let statements: Vec<Statement> =
+                    engine::ddl::statements(&event_schema, &workload, &policy)
+                        .into_iter()
+                        .chain(engine::ddl::erasure_objects(table, &workload, &policy))
+                        .collect();
+                let layout = match layout {
+                    MigrationLayout::Plain => output::migrations::Layout::Plain,
+                    MigrationLayout::Goose => output::migrations::Layout::Goose,
+                    MigrationLayout::GolangMigrate => output::migrations::Layout::GolangMigrate,
+                    MigrationLayout::Flyway => output::migrations::Layout::Flyway,
+                };
+                for path in or_exit(output::migrations::write(dir, &statements, layout, &format)) {
+                    eprintln!("Wrote {}", path.display());
+                }
+            } else {
+                output::formatter::print_statements(
+                    &engine::ddl::statements(&event_schema, &workload, &policy),
+                    &format,
+                );
+                for stmt in engine::ddl::erasure_objects(table, &workload, &policy) {
+                    println!();
+                    println!("{}", output::formatter::format_statement(&stmt, &format));
                 }
+            }
+
+            // Only the erasure objects are DDL; the deletes themselves are
+            // commented out so piping this into a client never erases data.
+            if let Some(strategy) = engine::compliance::deletion_strategy(&workload, &policy) {
+                if out_dir.is_none() {
+                    println!();
+                }
+                println!("-- Erasure runbook");
+                for (step, sql) in engine::compliance::runbook(table, &workload, strategy) {
+                    println!("-- {step}");
+                    for line in sql.lines() {
+                        println!("-- {line}");
+                    }
+                }
+            }
+        }
+        Command::Apply {
+            schema,
+            url,
+            dry_run,
+            format,
+        } => {
+            let event_schema = or_exit(config::load_schema(&schema));
+            let policy = or_exit(config::policy::resolve(cli.policy.as_deref(), &schema));
+            let workload = WorkloadProfile::from_schema(&event_schema);
+            or_exit(engine::ddl::validate(&event_schema, &workload, &policy));
+            let table = &TableName::new(&event_schema.event_table.name);
+            // Re-running apply against a server that has some of the objects
+            // must not fail, whatever the policy says.
+            let format = FormatOptions {
+                if_not_exists: true,
+                ..format.resolve(&policy.format)
             };
+            let statements: Vec<String> =
+                engine::ddl::statements(&event_schema, &workload, &policy)
+                    .iter()
+                    .chain(&engine::ddl::erasure_objects(table, &workload, &policy))
+                    .map(|stmt| output::formatter::format_statement(stmt, &format))
+                    .collect();
+
+            if dry_run {
+                println!("{}", statements.join("\n\n"));
+                return;
+            }
+
+            let client = or_exit(client::Client::new(&url).and_then(|c| {
+                c.with_credentials(
+                    std::env::var("CLICKHOUSE_USER").ok(),
+                    std::env::var("CLICKHOUSE_PASSWORD").ok(),
+                )
+            }));
+            let total = statements.len();
+            for (i, sql) in statements.iter().enumerate() {
+                let label = engine::ddl::label(sql);
+                match client.execute(sql) {
+                    Ok(_) => println!("[{}/{total}] {label} ... ok", i + 1),
+                    Err(e) => {
+                        println!("[{}/{total}] {label} ... failed", i + 1);
+                        or_exit(Err::<(), _>(e));
+                    }
+                }
+            }
+            println!("Applied {total} statements to {}.", client.address());
+        }
+        Command::Drift {
+            schema,
+            url,
+            migration,
+        } => {
+            let event_schema = or_exit(config::load_schema(&schema));
+            let policy = or_exit(config::policy::resolve(cli.policy.as_deref(), &schema));
             let workload = WorkloadProfile::from_schema(&event_schema);
-            let partition = choose_partition_strategy(&workload);
-            let order_cols = ordering::choose_order_by(&workload);
-            let ttl = engine::ttl::suggest(&workload);
-
-            let ast = build_create_table_sql(
-                &event_schema,
-                &partition.to_sql(),
-                &order_cols,
-                ttl,
-            );
-            output::formatter::print_sql(&ast);
-            println!();
-            let projection_sql =
-                engine::projections::daily_event_projection(&event_schema.event_table.name);
-            println!("{projection_sql}");
+            let table = &event_schema.event_table.name;
+            let client = or_exit(client::Client::new(&url).and_then(|c| {
+                c.with_credentials(
+                    std::env::var("CLICKHOUSE_USER").ok(),
+                    std::env::var("CLICKHOUSE_PASSWORD").ok(),
+                )
+            }));
+
+            let Some(live) = or_exit(engine::drift::introspect(
+                |sql| client.execute(sql),
+                &TableName::new(table),
+            )) else {
+                if migration {
+                    output::formatter::print_statements(
+                        &engine::ddl::statements(&event_schema, &workload, &policy),
+                        &policy.format,
+                    );
+                } else {
+                    println!("{table}: does not exist on {}", client.address());
+                }
+                std::process::exit(1);
+            };
+            let expected = engine::ddl::create_table(&event_schema, &workload, &policy);
+            let diffs =
+                engine::drift::diff(&expected, &event_schema.event_table.skip_indexes, &live);
+            if migration {
+                print!("{}", output::drift::render_migration(table, &diffs));
+            } else {
+                print!("{}", output::drift::render_text(table, &diffs));
+            }
+            if !diffs.is_empty() {
+                std::process::exit(1);
+            }
         }
-        Command::Explain { schema } => {
-            let event_schema = match config::load_schema(&schema) {
-                Ok(s) => s,
-                Err(e) => {
-                    eprintln!("Error: {e}");
-                    std::process::exit(1);
+        Command::Health {
+            table,
+            url,
+            snapshot,
+            save_snapshot,
+        } => {
+            let snapshot = match snapshot {
+                Some(path) => {
+                    let contents = or_exit(
+                        std::fs::read_to_string(&path)
+                            .map_err(|e| SharpError::IoError(path.clone(), e)),
+                    );
+                    or_exit(engine::health::parse_snapshot(&contents))
                 }
+                None => {
+                    let client = or_exit(client::Client::new(&url).and_then(|c| {
+                        c.with_credentials(
+                            std::env::var("CLICKHOUSE_USER").ok(),
+                            std::env::var("CLICKHOUSE_PASSWORD").ok(),
+                        )
+                    }));
+                    let table = table.expect("clap requires --table without --snapshot");
+                    let table = or_exit(TableName::parse(&table));
+                    or_exit(engine::health::capture(|sql| client.execute(sql), &table))
+                }
+            };
+            if let Some(path) = save_snapshot {
+                or_exit(
+                    std::fs::write(&path, engine::health::to_json(&snapshot))
+                        .map_err(|e| SharpError::WriteError(path.clone(), e)),
+                );
+            }
+
+            let health = engine::health::summarize(&snapshot);
+            let findings =
+                engine::heuristics::analyze_health(&TableName::new(&snapshot.table), &health);
+            print!("{}", output::health::render_text(&health, &findings));
+            if findings.iter().any(|f| f.severity == Severity::Error) {
+                std::process::exit(1);
+            }
+        }
+        Command::Explain {
+            schema,
+            format,
+            out,
+        } => {
+            let event_schema = or_exit(config::load_schema(&schema));
+            let policy = or_exit(config::policy::resolve(cli.policy.as_deref(), &schema));
+            let workload = WorkloadProfile::from_schema(&event_schema);
+            let ctx = PlanContext::new(&event_schema, &workload, &policy);
+
+            let doc = match format {
+                ExplainFormat::Text => output::explain::render_text(&ctx),
+                ExplainFormat::Markdown => output::explain::render_markdown(&ctx),
+                ExplainFormat::Html => output::explain::render_html(&ctx),
             };
+            match out {
+                Some(path) => {
+                    or_exit(
+                        std::fs::write(&path, doc)
+                            .map_err(|e| SharpError::WriteError(path.clone(), e)),
+                    );
+                    eprintln!("Wrote {}", path.display());
+                }
+                None => print!("{doc}"),
+            }
+        }
+        Command::Queries { schema } => {
+            let event_schema = or_exit(config::load_schema(&schema));
+            let policy = or_exit(config::policy::resolve(cli.policy.as_deref(), &schema));
             let workload = WorkloadProfile::from_schema(&event_schema);
-            let partition = choose_partition_strategy(&workload);
-            let order_cols = ordering::choose_order_by(&workload);
+            let ctx = PlanContext::new(&event_schema, &workload, &policy);
 
-            println!("Partitioning:");
-            println!("  Strategy: {partition:?}");
-            println!("  SQL:      PARTITION BY {}", partition.to_sql());
-            println!("  Reason:   {}", partition.explain(&workload));
-            println!();
-            println!("Ordering:");
-            println!("  SQL:      {}", ordering::order_by_sql(&order_cols));
-            println!(
-                "  Reason:   {}",
-                ordering::explain(&workload, &order_cols)
+            for (i, query) in engine::queries::library(&ctx).iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                println!("-- {}", query.name);
+                for used in &query.uses {
+                    println!("-- Uses: {used}");
+                }
+                println!("{}", query.sql);
+            }
+        }
+        Command::Fake {
+            schema,
+            days,
+            out,
+            format,
+            seed,
+            start,
+        } => {
+            let event_schema = or_exit(config::load_schema(&schema));
+            let extension = out.as_ref().and_then(|p| p.extension()?.to_str());
+            let format = match (format, extension) {
+                (Some(FakeFormat::Csv), _) | (None, Some("csv")) => {
+                    engine::fake::Format::CsvWithNames
+                }
+                (Some(FakeFormat::Tsv), _) | (None, Some("tsv")) => {
+                    engine::fake::Format::TsvWithNames
+                }
+                _ => engine::fake::Format::JsonEachRow,
+            };
+            let start = start.unwrap_or_else(|| {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs() as i64);
+                (now / 86_400 - i64::from(days)) * 86_400
+            });
+            // Checked before the output file is created, so a bad volume
+            // doesn't leave an empty file behind.
+            or_exit(engine::fake::row_count(&event_schema, days));
+            let options = engine::fake::Options {
+                days,
+                start,
+                seed,
+                format,
+            };
+
+            let (path, writer): (PathBuf, Box<dyn Write>) = match out {
+                Some(path) => {
+                    let file = or_exit(
+                        std::fs::File::create(&path)
+                            .map_err(|e| SharpError::WriteError(path.clone(), e)),
+                    );
+                    (path, Box::new(file))
+                }
+                None => (
+                    PathBuf::from("<stdout>"),
+                    Box::new(std::io::stdout().lock()),
+                ),
+            };
+            let mut writer = std::io::BufWriter::new(writer);
+            let rows = or_exit(
+                engine::fake::generate(&event_schema, &options, &mut writer)
+                    .and_then(|rows| writer.flush().map(|_| rows))
+                    .map_err(|e| SharpError::WriteError(path.clone(), e)),
             );
-            println!();
+            eprintln!(
+                "Wrote {rows} rows to {}. Load with: INSERT INTO {} FORMAT {}",
+                path.display(),
+                TableName::new(&event_schema.event_table.name),
+                format.clickhouse_name()
+            );
+        }
+        Command::CheckQuery { schema, query } => {
+            let event_schema = or_exit(config::load_schema(&schema));
+            let policy = or_exit(config::policy::resolve(cli.policy.as_deref(), &schema));
+            let workload = WorkloadProfile::from_schema(&event_schema);
+            let ctx = PlanContext::new(&event_schema, &workload, &policy);
+            let sql = or_exit(
+                std::fs::read_to_string(&query).map_err(|e| SharpError::IoError(query.clone(), e)),
+            );
+            let parsed = or_exit(sql::query::parse_select(&sql));
+            let report = or_exit(engine::query_check::check(&ctx, &parsed));
 
-            let recommendations = engine::heuristics::analyze(&event_schema, &workload);
-            println!("Recommendations:");
-            for rec in &recommendations {
-                println!("  - {rec}");
+            println!("Table:      {}", report.table);
+            if report.partition_pruning.is_empty() {
+                println!(
+                    "Partitions: no pruning (no usable filter on PARTITION BY {})",
+                    ctx.partition.to_sql()
+                );
+            } else {
+                println!(
+                    "Partitions: pruned on {} (PARTITION BY {})",
+                    report.partition_pruning.join(", "),
+                    ctx.partition.to_sql()
+                );
+            }
+            if report.key_prefix.is_empty() {
+                println!(
+                    "Sort key:   no prefix of ({}) used",
+                    ctx.order_by.join(", ")
+                );
+            } else {
+                println!(
+                    "Sort key:   prefix ({}) of ({})",
+                    report.key_prefix.join(", "),
+                    ctx.order_by.join(", ")
+                );
+            }
+            println!("Projection: {}", report.projection.unwrap_or("none"));
+            if !report.skip_indexes.is_empty() {
+                println!("Skip index: {}", report.skip_indexes.join(", "));
+            }
+            if report.sample {
+                println!("Sample:     SAMPLE clause reads a fraction of the sampling key");
             }
+            println!("Verdict:    {}", report.verdict());
+            if let Some(ref next) = report.next_key_column {
+                println!("Hint:       a filter on `{next}` would extend the key prefix");
+            }
+            println!();
+            println!("Predicates:");
+            for p in &report.predicates {
+                let usage = match (&p.kind, p.column.as_deref()) {
+                    (PredicateKind::Unusable(reason), _) => format!("not indexable: {reason}"),
+                    (kind, Some(col)) => {
+                        let op = if *kind == PredicateKind::Eq {
+                            "equality"
+                        } else {
+                            "range"
+                        };
+                        if ctx.order_by.iter().any(|c| c == col) {
+                            format!("{op} on key column {col}")
+                        } else {
+                            format!("{op} on {col} (not in ORDER BY)")
+                        }
+                    }
+                    (_, None) => "not indexable".to_string(),
+                };
+                println!("  - {}", p.text);
+                println!("    {usage}");
+            }
+        }
+        Command::Infer { sample, name } => {
+            let file = or_exit(
+                std::fs::File::open(&sample).map_err(|e| SharpError::IoError(sample.clone(), e)),
+            );
+            let inference = or_exit(engine::infer::infer(std::io::BufReader::new(file), &name));
+            print!(
+                "{}",
+                engine::infer::to_yaml(&inference, &sample.display().to_string())
+            );
+        }
+        Command::InferPatterns { query_log, table } => {
+            let contents = or_exit(
+                std::fs::read_to_string(&query_log)
+                    .map_err(|e| SharpError::IoError(query_log.clone(), e)),
+            );
+            let entries = or_exit(engine::patterns::parse_query_log(&contents));
+            let table = or_exit(TableName::parse(&table));
+            let report = engine::patterns::infer(&entries, &table);
+            print!(
+                "{}",
+                engine::patterns::to_yaml(&report, &query_log.display().to_string())
+            );
+        }
+        Command::Lint {
+            schema,
+            format,
+            deny,
+        } => {
+            // Bad input gets its own exit code so CI can tell a broken YAML
+            // apart from a schema that merely fails checks.
+            let source = lint_or_exit(config::read_schema_source(&schema));
+            let event_schema = lint_or_exit(config::parse_schema(&source));
+            let allowed = lint_or_exit(engine::lint::suppressions(&source));
+            let policy = lint_or_exit(config::policy::resolve(cli.policy.as_deref(), &schema));
+            let workload = WorkloadProfile::from_schema(&event_schema);
+            let ctx = PlanContext::new(&event_schema, &workload, &policy);
+
+            let report = engine::lint::lint(&ctx, &allowed);
+            let path = schema.display().to_string();
+            let rendered = match format {
+                LintFormat::Text => output::lint::render_text(&report, &path),
+                LintFormat::Sarif => output::lint::render_sarif(&report, &path),
+                LintFormat::Junit => output::lint::render_junit(&report, &path),
+            };
+            print!("{rendered}");
+            std::process::exit(
+                report
+                    .outcome(deny == Some(DenyLevel::Warnings))
+                    .exit_code(),
+            );
         }
     }
 }
+
+fn lint_or_exit<T>(result: Result<T, SharpError>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        std::process::exit(engine::lint::EXIT_INVALID_INPUT);
+    })
+}
+
+fn or_exit<T>(result: Result<T, SharpError>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    })
+}
@@ -0,0 +1,136 @@
+use crate::engine::drift::{Difference, Fix};
+use crate::sql::ast::TableName;
+
+pub fn render_text(table: &str, diffs: &[Difference]) -> String {
+    if diffs.is_empty() {
+        return format!("{table}: in sync with the plan\n");
+    }
+    let mut out = format!("{table}: {} difference(s)\n", diffs.len());
+    for diff in diffs {
+        out.push_str(&format!(
+            "  {}\n    expected: {}\n    found:    {}\n",
+            diff.object,
+            diff.expected.as_deref().unwrap_or("(absent)"),
+            diff.actual.as_deref().unwrap_or("(absent)")
+        ));
+        match diff.fix {
+            Fix::Sql(_) => {}
+            Fix::Review(_) => out.push_str("    fix:      drop, after review\n"),
+            Fix::Rebuild(_) => out.push_str("    fix:      rebuild the table\n"),
+        }
+    }
+    out
+}
+
+// Statements that converge the server on the plan. Drops are left commented
+// out, and clauses that can't be altered get the rebuild recipe instead: once,
+// where the first of them appears, since one rebuild fixes them all.
+pub fn render_migration(table: &str, diffs: &[Difference]) -> String {
+    let rebuilt: Vec<&str> = diffs
+        .iter()
+        .filter(|d| matches!(d.fix, Fix::Rebuild(_)))
+        .map(|d| d.object.as_str())
+        .collect();
+    let mut recipe_written = false;
+    let mut out = format!("-- Converge {table} on the sharp plan\n");
+    for diff in diffs {
+        match &diff.fix {
+            Fix::Sql(sql) => out.push_str(&format!("\n{sql}\n")),
+            Fix::Review(sql) => out.push_str(&format!(
+                "\n-- {} is not in the plan; uncomment to drop it:\n-- {sql}\n",
+                diff.object
+            )),
+            Fix::Rebuild(_) if recipe_written => {}
+            Fix::Rebuild(insert) => {
+                let table = TableName::new(table);
+                let new = table.with_suffix("_new");
+                out.push_str(&format!(
+                    "\n-- {} can't be altered in place: create the planned table as {new},\n\
+                     -- {insert}\n-- then EXCHANGE TABLES {new} AND {table}.\n",
+                    rebuilt.join(", ")
+                ));
+                recipe_written = true;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn difference(object: &str, fix: Fix) -> Difference {
+        Difference {
+            object: object.to_string(),
+            expected: Some("x".to_string()),
+            actual: None,
+            fix,
+        }
+    }
+
+    #[test]
+    fn text_lists_each_difference() {
+        let text = render_text(
+            "events",
+            &[difference("ORDER BY", Fix::Rebuild(String::new()))],
+        );
+        assert!(text.starts_with("events: 1 difference(s)\n  ORDER BY\n"));
+        assert!(text.contains("found:    (absent)"));
+        assert!(text.contains("rebuild the table"));
+        assert_eq!(
+            render_text("events", &[]),
+            "events: in sync with the plan\n"
+        );
+    }
+
+    #[test]
+    fn migration_comments_out_drops() {
+        let sql = render_migration(
+            "events",
+            &[
+                difference(
+                    "TTL",
+                    Fix::Sql("ALTER TABLE events MODIFY TTL t;".to_string()),
+                ),
+                difference(
+                    "column legacy",
+                    Fix::Review("ALTER TABLE events DROP COLUMN legacy;".to_string()),
+                ),
+            ],
+        );
+        assert!(sql.contains("\nALTER TABLE events MODIFY TTL t;\n"));
+        assert!(sql.contains("\n-- ALTER TABLE events DROP COLUMN legacy;\n"));
+    }
+
+    #[test]
+    fn migration_comments_out_the_rebuild_recipe() {
+        let insert = "INSERT INTO events_new (a, b) SELECT a, b FROM events;";
+        let sql = render_migration(
+            "events",
+            &[difference("ORDER BY", Fix::Rebuild(insert.to_string()))],
+        );
+        assert!(sql.ends_with(
+            "-- ORDER BY can't be altered in place: create the planned table as events_new,\n\
+             -- INSERT INTO events_new (a, b) SELECT a, b FROM events;\n\
+             -- then EXCHANGE TABLES events_new AND events.\n"
+        ));
+    }
+
+    #[test]
+    fn migration_writes_one_rebuild_recipe() {
+        let insert = "INSERT INTO events_new (a) SELECT a FROM events;";
+        let sql = render_migration(
+            "events",
+            &[
+                difference("PARTITION BY", Fix::Rebuild(insert.to_string())),
+                difference("ORDER BY", Fix::Rebuild(insert.to_string())),
+                difference("column timestamp", Fix::Rebuild(insert.to_string())),
+            ],
+        );
+        assert_eq!(sql.matches("EXCHANGE TABLES").count(), 1);
+        assert!(
+            sql.contains("-- PARTITION BY, ORDER BY, column timestamp can't be altered in place")
+        );
+    }
+}
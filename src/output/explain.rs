@@ -0,0 +1,677 @@
+use crate::config::policy::human;
+use crate::engine::rules::{Finding, PlanContext};
+use crate::engine::sizing::{Estimate, human_bytes};
+use crate::engine::{
+    compliance, ddl, dictionaries, heuristics, kafka, ordering, persons, projections, sampling,
+    sessions, sizing, timestamp, ttl,
+};
+use crate::output::lint::xml_escape;
+
+pub fn render_text(ctx: &PlanContext) -> String {
+    let workload = ctx.workload;
+    let policy = ctx.policy;
+    let partition = &ctx.partition;
+    let order_cols = &ctx.order_by;
+    let mut out = String::new();
+
+    match policy.source {
+        Some(ref path) => out.push_str(&format!("Policy: {}\n", path.display())),
+        None => out.push_str("Policy: built-in defaults\n"),
+    }
+    out.push_str("\nPartitioning:\n");
+    out.push_str(&format!("  Strategy: {partition:?}\n"));
+    out.push_str(&format!(
+        "  SQL:      PARTITION BY {}\n",
+        partition.to_sql()
+    ));
+    out.push_str(&format!(
+        "  Reason:   {}\n",
+        partition.explain(workload, policy)
+    ));
+    out.push_str("\nTimestamp:\n");
+    out.push_str(&format!(
+        "  Type:     {}\n",
+        timestamp::column_type(&workload.timestamp)
+    ));
+    out.push_str(&format!(
+        "  Reason:   {}\n",
+        timestamp::explain(&workload.timestamp)
+    ));
+    out.push_str("\nOrdering:\n");
+    out.push_str(&format!(
+        "  SQL:      {}\n",
+        ordering::order_by_sql(order_cols)
+    ));
+    out.push_str(&format!(
+        "  Reason:   {}\n",
+        ordering::explain(workload, order_cols, policy)
+    ));
+    if let Some(sample_by) = sampling::suggest(workload) {
+        out.push_str(&format!("  Sample:   SAMPLE BY {sample_by}\n"));
+    }
+    out.push('\n');
+
+    if let Some(kafka) = workload.ingestion.as_ref().and_then(|i| i.kafka.as_ref()) {
+        let consumers = kafka::num_consumers(workload, kafka);
+        out.push_str("Ingestion:\n");
+        out.push_str(&format!("  Source:   Kafka topic '{}'\n", kafka.topic));
+        out.push_str(&format!(
+            "  Settings: kafka_num_consumers = {consumers}, kafka_max_block_size = {}\n",
+            kafka::recommend_max_block_size(workload, consumers)
+        ));
+        out.push_str(&format!(
+            "  Reason:   {}\n\n",
+            kafka::explain(workload, kafka, partition)
+        ));
+    }
+
+    if let Some(config) = &workload.sessions {
+        out.push_str("Sessions:\n");
+        out.push_str(&format!(
+            "  Table:    {} (AggregatingMergeTree)\n",
+            sessions::table_name(&ctx.table())
+        ));
+        out.push_str(&format!(
+            "  SQL:      PARTITION BY {} {}\n",
+            sessions::partition_strategy(workload, config, policy).to_sql(),
+            ordering::order_by_sql(&ordering::session_order_by(workload))
+        ));
+        out.push_str(&format!(
+            "  Reason:   {}\n\n",
+            sessions::explain(workload, config, policy)
+        ));
+    }
+
+    if let Some(config) = &workload.persons {
+        out.push_str("Persons:\n");
+        out.push_str(&format!(
+            "  Tables:   {}, {} (ReplacingMergeTree)\n",
+            persons::persons_table_name(&ctx.table()),
+            persons::distinct_id_table_name(&ctx.table())
+        ));
+        out.push_str(&format!(
+            "  Reason:   {}\n\n",
+            persons::explain(workload, config)
+        ));
+    }
+
+    if !ctx.schema.dictionaries.is_empty() {
+        out.push_str("Dictionaries:\n");
+        for dictionary in &ctx.schema.dictionaries {
+            out.push_str(&format!(
+                "  {}: LAYOUT({})\n    {}\n",
+                dictionaries::dictionary_name(&ctx.table(), dictionary),
+                dictionaries::choose_layout(dictionary).to_sql(),
+                dictionaries::explain(dictionary)
+            ));
+        }
+        out.push('\n');
+    }
+
+    if let Some(strategy) = compliance::deletion_strategy(workload, policy) {
+        out.push_str("Compliance:\n");
+        out.push_str(&format!("  Strategy: {strategy:?}\n"));
+        out.push_str(&format!(
+            "  Reason:   {}\n",
+            compliance::explain(strategy, workload, policy)
+        ));
+        if let Some(warning) = compliance::order_by_warning(order_cols) {
+            out.push_str(&format!("  {warning}\n"));
+        }
+        out.push('\n');
+    }
+
+    out.push_str("Recommendations:\n");
+    for finding in heuristics::analyze(ctx) {
+        out.push_str(&format!("  - {finding}\n"));
+        out.push_str(&format!(
+            "    Why ({}): {}\n",
+            finding.rule_id, finding.rationale
+        ));
+        if let Some(ref sql) = finding.suggested_sql {
+            for line in sql.lines() {
+                out.push_str(&format!("    | {line}\n"));
+            }
+        }
+    }
+    out
+}
+
+// One row of the design doc's decision table.
+struct Decision {
+    topic: &'static str,
+    choice: String,
+    reason: String,
+}
+
+fn decisions(ctx: &PlanContext) -> Vec<Decision> {
+    let workload = ctx.workload;
+    let policy = ctx.policy;
+    let mut rows = vec![
+        Decision {
+            topic: "Partitioning",
+            choice: format!("PARTITION BY {}", ctx.partition.to_sql()),
+            reason: ctx.partition.explain(workload, policy),
+        },
+        Decision {
+            topic: "Ordering",
+            choice: ordering::order_by_sql(&ctx.order_by),
+            reason: ordering::explain(workload, &ctx.order_by, policy),
+        },
+        Decision {
+            topic: "Timestamp",
+            choice: timestamp::column_type(&workload.timestamp).to_string(),
+            reason: timestamp::explain(&workload.timestamp),
+        },
+    ];
+    if let Some(sample_by) = sampling::suggest(workload) {
+        rows.push(Decision {
+            topic: "Sampling",
+            choice: format!("SAMPLE BY {sample_by}"),
+            reason: "Hashing distinct_id spreads users uniformly over the key space, so a \
+                     sampled query reads a fraction of users with all their events."
+                .to_string(),
+        });
+    }
+    rows.push(match ttl::suggest(workload) {
+        Some(ttl) => Decision {
+            topic: "TTL",
+            choice: format!("TTL {ttl}"),
+            reason: format!(
+                "Rows expire after the configured retention of {} days; ClickHouse drops \
+                 whole parts once every row in them has expired.",
+                workload.retention_days
+            ),
+        },
+        None => Decision {
+            topic: "TTL",
+            choice: "none".to_string(),
+            reason: "retention_days is 0, so data is kept forever.".to_string(),
+        },
+    });
+    for index in &ctx.schema.event_table.skip_indexes {
+        rows.push(Decision {
+            topic: "Skip index",
+            choice: format!(
+                "INDEX {} {} TYPE {} GRANULARITY {}",
+                index.name, index.expression, index.index_type, index.granularity
+            ),
+            reason: "Declared in the schema to skip granules the sort key can't.".to_string(),
+        });
+    }
+    rows.push(Decision {
+        topic: "Projection",
+        choice: projections::DAILY_EVENT_PROJECTION.to_string(),
+        reason: "Pre-aggregates event counts per tenant, day and event name so dashboard \
+                 trends don't scan raw events."
+            .to_string(),
+    });
+    rows.push(Decision {
+        topic: "Codecs",
+        choice: "server default (LZ4)".to_string(),
+        reason: "Per-column codecs are tuned on real data: `sharp health` flags columns that \
+                 compress poorly once the table is live."
+            .to_string(),
+    });
+    if let Some(kafka) = workload.ingestion.as_ref().and_then(|i| i.kafka.as_ref()) {
+        let consumers = kafka::num_consumers(workload, kafka);
+        rows.push(Decision {
+            topic: "Ingestion",
+            choice: format!(
+                "Kafka topic '{}', kafka_num_consumers = {consumers}, kafka_max_block_size = {}",
+                kafka.topic,
+                kafka::recommend_max_block_size(workload, consumers)
+            ),
+            reason: kafka::explain(workload, kafka, &ctx.partition),
+        });
+    }
+    if let Some(config) = &workload.sessions {
+        rows.push(Decision {
+            topic: "Sessions",
+            choice: format!(
+                "{} PARTITION BY {} {}",
+                sessions::table_name(&ctx.table()),
+                sessions::partition_strategy(workload, config, policy).to_sql(),
+                ordering::order_by_sql(&ordering::session_order_by(workload))
+            ),
+            reason: sessions::explain(workload, config, policy),
+        });
+    }
+    if let Some(config) = &workload.persons {
+        rows.push(Decision {
+            topic: "Persons",
+            choice: format!(
+                "{}, {} ENGINE = ReplacingMergeTree(version, is_deleted)",
+                persons::persons_table_name(&ctx.table()),
+                persons::distinct_id_table_name(&ctx.table())
+            ),
+            reason: persons::explain(workload, config),
+        });
+    }
+    for dictionary in &ctx.schema.dictionaries {
+        rows.push(Decision {
+            topic: "Dictionary",
+            choice: format!(
+                "{} LAYOUT({})",
+                dictionaries::dictionary_name(&ctx.table(), dictionary),
+                dictionaries::choose_layout(dictionary).to_sql()
+            ),
+            reason: dictionaries::explain(dictionary),
+        });
+    }
+    if let Some(strategy) = compliance::deletion_strategy(workload, policy) {
+        let mut reason = compliance::explain(strategy, workload, policy);
+        if let Some(warning) = compliance::order_by_warning(&ctx.order_by) {
+            reason = format!("{reason} {warning}");
+        }
+        rows.push(Decision {
+            topic: "Erasure",
+            choice: format!("{strategy:?}"),
+            reason,
+        });
+    }
+    rows
+}
+
+fn workload_summary(ctx: &PlanContext) -> Vec<(&'static str, String)> {
+    let workload = ctx.workload;
+    let mut rows = vec![
+        ("Table", ctx.table_name().to_string()),
+        ("Events per day", human(workload.events_per_day)),
+        (
+            "Tenancy",
+            if workload.multi_tenant {
+                "multi-tenant (project_id)".to_string()
+            } else {
+                "single-tenant".to_string()
+            },
+        ),
+        (
+            "Retention",
+            match workload.retention_days {
+                0 => "unbounded".to_string(),
+                days => format!("{days} days"),
+            },
+        ),
+        (
+            "Ingestion",
+            match workload.ingestion.as_ref().and_then(|i| i.kafka.as_ref()) {
+                Some(kafka) => format!("Kafka topic '{}'", kafka.topic),
+                None => "direct inserts".to_string(),
+            },
+        ),
+        (
+            "Policy",
+            match ctx.policy.source {
+                Some(ref path) => path.display().to_string(),
+                None => "built-in defaults".to_string(),
+            },
+        ),
+    ];
+    if !workload.query_patterns.is_empty() {
+        rows.push((
+            "Query patterns",
+            format!("{} from the query log", workload.query_patterns.len()),
+        ));
+    }
+    rows
+}
+
+fn sizing_summary(estimate: &Estimate) -> Vec<(&'static str, String)> {
+    let row_source = if estimate.measured {
+        "measured"
+    } else {
+        "assumed"
+    };
+    let partitions = if estimate.per_tenant {
+        format!("{} per tenant", estimate.partitions)
+    } else {
+        estimate.partitions.to_string()
+    };
+    vec![
+        (
+            "Row size",
+            format!("{} ({row_source})", human_bytes(estimate.row_bytes)),
+        ),
+        (
+            "Ingested per day",
+            format!("{} uncompressed", human_bytes(estimate.raw_bytes_per_day)),
+        ),
+        (
+            "Retained rows",
+            format!(
+                "{} over {} days",
+                human(estimate.retained_rows),
+                estimate.retained_days
+            ),
+        ),
+        (
+            "On disk",
+            format!("~{} compressed", human_bytes(estimate.disk_bytes)),
+        ),
+        ("Partitions", partitions),
+    ]
+}
+
+fn statements(ctx: &PlanContext) -> Vec<String> {
+    ddl::statements(ctx.schema, ctx.workload, ctx.policy)
+        .into_iter()
+        .chain(ddl::erasure_objects(&ctx.table(), ctx.workload, ctx.policy))
+        .map(|stmt| stmt.to_sql())
+        .collect()
+}
+
+// Markdown tables can't hold newlines or bare pipes.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+pub fn render_markdown(ctx: &PlanContext) -> String {
+    let estimate = sizing::estimate(ctx.schema, ctx.workload, &ctx.partition);
+    let mut out = format!("# Schema design: `{}`\n\n", ctx.table_name());
+
+    out.push_str("## Workload\n\n| | |\n|---|---|\n");
+    for (name, value) in workload_summary(ctx) {
+        out.push_str(&format!("| {name} | {} |\n", cell(&value)));
+    }
+
+    out.push_str("\n## Schema\n\n```sql\n");
+    out.push_str(&statements(ctx).join("\n\n"));
+    out.push_str("\n```\n");
+
+    out.push_str("\n## Decisions\n\n| Decision | Choice | Reason |\n|---|---|---|\n");
+    for decision in decisions(ctx) {
+        out.push_str(&format!(
+            "| {} | `{}` | {} |\n",
+            decision.topic,
+            cell(&decision.choice),
+            cell(&decision.reason)
+        ));
+    }
+
+    out.push_str("\n## Sizing\n\n| | |\n|---|---|\n");
+    for (name, value) in sizing_summary(&estimate) {
+        out.push_str(&format!("| {name} | {value} |\n"));
+    }
+    out.push_str(
+        "\nEstimates assume the default codecs compress event data about 6x; \
+         measure with `sharp health` once data is flowing.\n",
+    );
+
+    out.push_str("\n## Recommendations\n\n");
+    let findings = heuristics::analyze(ctx);
+    if findings.is_empty() {
+        out.push_str("None.\n");
+    }
+    for finding in &findings {
+        out.push_str(&format!(
+            "- **{}** `{}`: {}\n\n  {}\n",
+            finding.severity.as_str(),
+            finding.rule_id,
+            finding.message,
+            finding.rationale
+        ));
+        if let Some(ref sql) = finding.suggested_sql {
+            out.push_str(&format!(
+                "\n  ```sql\n  {}\n  ```\n",
+                sql.replace('\n', "\n  ")
+            ));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+const SQL_KEYWORDS: &[&str] = &[
+    "ADD",
+    "ALTER",
+    "AND",
+    "AS",
+    "BY",
+    "CODEC",
+    "COLUMN",
+    "CREATE",
+    "DEFAULT",
+    "DELETE",
+    "ENGINE",
+    "EXISTS",
+    "FROM",
+    "GRANULARITY",
+    "GROUP",
+    "IF",
+    "IN",
+    "INDEX",
+    "INTERVAL",
+    "MATERIALIZED",
+    "MODIFY",
+    "NOT",
+    "OR",
+    "ORDER",
+    "PARTITION",
+    "PROJECTION",
+    "SAMPLE",
+    "SELECT",
+    "SETTINGS",
+    "TABLE",
+    "TO",
+    "TTL",
+    "TYPE",
+    "VIEW",
+    "WHERE",
+];
+
+// Just enough highlighting to make the DDL scannable: keywords, string
+// literals, numbers and comments.
+fn highlight_sql(sql: &str) -> String {
+    let mut out = String::new();
+    let mut chars = sql.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let class = if c == '\'' {
+            while let Some((i, c)) = chars.next() {
+                end = i + c.len_utf8();
+                if c == '\\' {
+                    if let Some((i, c)) = chars.next() {
+                        end = i + c.len_utf8();
+                    }
+                } else if c == '\'' {
+                    break;
+                }
+            }
+            Some("str")
+        } else if c == '-' && chars.peek().is_some_and(|&(_, n)| n == '-') {
+            while let Some(&(i, c)) = chars.peek() {
+                if c == '\n' {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            Some("comment")
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let word = &sql[start..end];
+            if c.is_ascii_digit() {
+                Some("num")
+            } else if SQL_KEYWORDS.contains(&word) {
+                Some("kw")
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+        let text = xml_escape(&sql[start..end]);
+        match class {
+            Some(class) => out.push_str(&format!("<span class=\"{class}\">{text}</span>")),
+            None => out.push_str(&text),
+        }
+    }
+    out
+}
+
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; max-width: 60rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1rem; }
+th, td { border: 1px solid #d0d7de; padding: 0.4rem 0.6rem; text-align: left; vertical-align: top; }
+pre { background: #f6f8fa; padding: 1rem; overflow-x: auto; }
+.kw { color: #cf222e; font-weight: 600; }
+.str { color: #0a3069; }
+.num { color: #0550ae; }
+.comment { color: #6e7781; font-style: italic; }
+.error { color: #cf222e; }
+.warning { color: #9a6700; }
+.info { color: #0969da; }";
+
+fn html_finding(finding: &Finding) -> String {
+    let mut out = format!(
+        "<li><strong class=\"{severity}\">{severity}</strong> <code>{}</code>: {}<p>{}</p>",
+        finding.rule_id,
+        xml_escape(&finding.message),
+        xml_escape(&finding.rationale),
+        severity = finding.severity.as_str()
+    );
+    if let Some(ref sql) = finding.suggested_sql {
+        out.push_str(&format!("<pre><code>{}</code></pre>", highlight_sql(sql)));
+    }
+    out.push_str("</li>\n");
+    out
+}
+
+// A standalone page with inline CSS so it can be attached to a review as is.
+pub fn render_html(ctx: &PlanContext) -> String {
+    let estimate = sizing::estimate(ctx.schema, ctx.workload, &ctx.partition);
+    let table = xml_escape(ctx.table_name());
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Schema design: {table}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>\n\
+         <h1>Schema design: <code>{table}</code></h1>\n"
+    );
+
+    out.push_str("<h2>Workload</h2>\n<table>\n");
+    for (name, value) in workload_summary(ctx) {
+        out.push_str(&format!(
+            "<tr><th>{name}</th><td>{}</td></tr>\n",
+            xml_escape(&value)
+        ));
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Schema</h2>\n<pre><code>");
+    out.push_str(&highlight_sql(&statements(ctx).join("\n\n")));
+    out.push_str("</code></pre>\n");
+
+    out.push_str(
+        "<h2>Decisions</h2>\n<table>\n<tr><th>Decision</th><th>Choice</th><th>Reason</th></tr>\n",
+    );
+    for decision in decisions(ctx) {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td><code>{}</code></td><td>{}</td></tr>\n",
+            decision.topic,
+            xml_escape(&decision.choice),
+            xml_escape(&decision.reason)
+        ));
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Sizing</h2>\n<table>\n");
+    for (name, value) in sizing_summary(&estimate) {
+        out.push_str(&format!("<tr><th>{name}</th><td>{value}</td></tr>\n"));
+    }
+    out.push_str(
+        "</table>\n<p>Estimates assume the default codecs compress event data about 6x; \
+         measure with <code>sharp health</code> once data is flowing.</p>\n",
+    );
+
+    out.push_str("<h2>Recommendations</h2>\n");
+    let findings = heuristics::analyze(ctx);
+    if findings.is_empty() {
+        out.push_str("<p>None.</p>\n");
+    } else {
+        out.push_str("<ul>\n");
+        for finding in &findings {
+            out.push_str(&html_finding(finding));
+        }
+        out.push_str("</ul>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::policy::Policy;
+    use crate::config::schema::{EventSchema, EventTable, SkipIndexConfig};
+    use crate::config::workload::WorkloadProfile;
+
+    fn schema() -> EventSchema {
+        EventSchema {
+            event_table: EventTable {
+                name: "user_events".to_string(),
+                multi_tenant: true,
+                expected_events_per_day: 50_000_000,
+                retention_days: 90,
+                skip_indexes: vec![SkipIndexConfig {
+                    name: "idx_distinct_id".to_string(),
+                    expression: "distinct_id".to_string(),
+                    index_type: "bloom_filter(0.01)".to_string(),
+                    granularity: 4,
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn markdown_covers_every_section() {
+        let schema = schema();
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let doc = render_markdown(&PlanContext::new(&schema, &workload, &policy));
+
+        for heading in [
+            "# Schema design: `user_events`",
+            "## Workload",
+            "## Schema",
+            "## Decisions",
+            "## Sizing",
+            "## Recommendations",
+        ] {
+            assert!(doc.contains(heading), "missing {heading}");
+        }
+        assert!(doc.contains("```sql\nCREATE TABLE user_events ("));
+        assert!(doc.contains("| Partitioning | `PARTITION BY toYYYYMMDD(timestamp)` | Daily"));
+        assert!(doc.contains("| Skip index | `INDEX idx_distinct_id distinct_id"));
+        assert!(doc.contains("| Retained rows | 4500M over 90 days |"));
+    }
+
+    #[test]
+    fn html_escapes_and_highlights() {
+        let schema = schema();
+        let workload = WorkloadProfile::from_schema(&schema);
+        let policy = Policy::default();
+        let doc = render_html(&PlanContext::new(&schema, &workload, &policy));
+
+        assert!(doc.starts_with("<!DOCTYPE html>"));
+        assert!(doc.ends_with("</html>\n"));
+        assert!(doc.contains("<span class=\"kw\">CREATE</span> <span class=\"kw\">TABLE</span>"));
+        assert!(doc.contains("<td><code>PARTITION BY toYYYYMMDD(timestamp)</code></td>"));
+    }
+
+    #[test]
+    fn highlights_literals_and_comments() {
+        assert_eq!(
+            highlight_sql("-- a < b\nWHERE x = 'it\\'s' AND y > 10"),
+            "<span class=\"comment\">-- a &lt; b</span>\n<span class=\"kw\">WHERE</span> x = \
+             <span class=\"str\">'it\\'s'</span> <span class=\"kw\">AND</span> y &gt; <span class=\"num\">10</span>"
+        );
+    }
+}
@@ -1,29 +1,169 @@
-use crate::sql::ast::CreateTable;
+use crate::config::policy::{FormatOptions, KeywordCase};
+use crate::sql::ast::{RenderOptions, Statement, is_keyword};
 
-pub fn print_sql(stmt: &CreateTable) {
-    println!("{}", stmt.to_sql());
+// The AST renders with four-space indentation and upper-case keywords; the
+// remaining options are applied to that text.
+pub fn format_statement(statement: &Statement, options: &FormatOptions) -> String {
+    let sql = statement.render(&RenderOptions {
+        if_not_exists: options.if_not_exists,
+        align_types: options.align_types,
+    });
+    let sql = reindent(&sql, options.indent);
+    let mut sql = match options.keyword_case {
+        KeywordCase::Upper => sql,
+        KeywordCase::Lower => lowercase_keywords(&sql),
+    };
+    if options.semicolons {
+        sql.push(';');
+    }
+    sql
+}
+
+// Statements are separated by a blank line, as in a migration file.
+pub fn format_statements(statements: &[Statement], options: &FormatOptions) -> String {
+    statements
+        .iter()
+        .map(|s| format_statement(s, options))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+pub fn print_statements(statements: &[Statement], options: &FormatOptions) {
+    println!("{}", format_statements(statements, options));
+}
+
+fn reindent(sql: &str, width: usize) -> String {
+    sql.lines()
+        .map(|line| {
+            let body = line.trim_start_matches(' ');
+            let spaces = line.len() - body.len();
+            format!(
+                "{}{}{body}",
+                " ".repeat(spaces / 4 * width),
+                " ".repeat(spaces % 4)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Walks the text token by token so that string literals, quoted names and
+// query parameters ({from:DateTime}) are copied untouched.
+fn lowercase_keywords(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '`' | '"' | '{' => {
+                let close = if c == '{' { '}' } else { c };
+                out.push(c);
+                while let Some(next) = chars.next() {
+                    out.push(next);
+                    if next == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            out.push(escaped);
+                        }
+                    } else if next == close {
+                        break;
+                    }
+                }
+            }
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                if is_keyword(&word) {
+                    out.push_str(&word.to_ascii_lowercase());
+                } else {
+                    out.push_str(&word);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::sql::ast::ColumnExpr;
+    use crate::config::parse_schema;
+    use crate::config::policy::Policy;
+    use crate::config::workload::WorkloadProfile;
+    use crate::engine::ddl;
+
+    const SCHEMA: &str = "\
+event_table:
+  name: events
+  multi_tenant: true
+  expected_events_per_day: 10000000
+  retention_days: 90
+  skip_indexes:
+    - name: idx_event_name
+      expression: event_name
+      type: set(100)
+      granularity: 4
+";
+
+    fn statements() -> Vec<Statement> {
+        let schema = parse_schema(SCHEMA).unwrap();
+        let workload = WorkloadProfile::from_schema(&schema);
+        ddl::statements(&schema, &workload, &Policy::default())
+    }
+
+    #[test]
+    fn default_options_match_to_sql() {
+        let statements = statements();
+        let sql: Vec<String> = statements.iter().map(Statement::to_sql).collect();
+        assert_eq!(
+            format_statements(&statements, &FormatOptions::default()),
+            sql.join("\n\n")
+        );
+        assert_eq!(
+            format_statements(&statements, &FormatOptions::default()),
+            include_str!("golden/default.sql").trim_end()
+        );
+    }
 
     #[test]
-    fn print_sql_does_not_panic() {
-        let stmt = CreateTable {
-            table_name: "test".to_string(),
-            columns: vec![ColumnExpr {
-                name: "id".to_string(),
-                data_type: "UInt64".to_string(),
-                nullable: false,
-            }],
-            engine: "MergeTree".to_string(),
-            partition_by: None,
-            order_by: vec![],
-            ttl: None,
+    fn compact_lower_case() {
+        let options = FormatOptions {
+            indent: 2,
+            keyword_case: KeywordCase::Lower,
+            semicolons: false,
+            ..FormatOptions::default()
         };
-        // Verify print_sql completes without panicking
-        print_sql(&stmt);
+        assert_eq!(
+            format_statements(&statements(), &options),
+            include_str!("golden/compact_lower.sql").trim_end()
+        );
+    }
+
+    #[test]
+    fn aligned_if_not_exists() {
+        let options = FormatOptions {
+            align_types: true,
+            if_not_exists: true,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_statements(&statements(), &options),
+            include_str!("golden/aligned_if_not_exists.sql").trim_end()
+        );
+    }
+
+    #[test]
+    fn lowercasing_leaves_literals_and_names_alone() {
+        assert_eq!(
+            lowercase_keywords(
+                "SELECT `ORDER`, 'AND \\' OR' AS x FROM t WHERE ts >= {from:DateTime} AND TYPE"
+            ),
+            "select `ORDER`, 'AND \\' OR' as x from t where ts >= {from:DateTime} and type"
+        );
     }
 }
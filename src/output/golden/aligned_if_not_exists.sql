@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS events (
+    project_id  UInt32,
+    timestamp   DateTime,
+    event_name  LowCardinality(String),
+    distinct_id String,
+    properties  JSON
+) ENGINE = MergeTree
+PARTITION BY toYYYYMMDD(timestamp)
+ORDER BY (project_id, event_name, timestamp, distinct_id)
+TTL timestamp + INTERVAL 90 DAY;
+
+ALTER TABLE events
+ADD PROJECTION IF NOT EXISTS daily_event_counts
+(
+    SELECT
+        project_id,
+        toDate(timestamp) AS day,
+        event_name,
+        count()
+    GROUP BY project_id, day, event_name
+);
+
+ALTER TABLE events
+ADD INDEX IF NOT EXISTS idx_event_name event_name TYPE set(100) GRANULARITY 4;
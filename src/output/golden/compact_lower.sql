@@ -0,0 +1,24 @@
+create table events (
+  project_id UInt32,
+  timestamp DateTime,
+  event_name LowCardinality(String),
+  distinct_id String,
+  properties JSON
+) engine = MergeTree
+partition by toYYYYMMDD(timestamp)
+order by (project_id, event_name, timestamp, distinct_id)
+ttl timestamp + interval 90 day
+
+alter table events
+add projection daily_event_counts
+(
+  select
+    project_id,
+    toDate(timestamp) as day,
+    event_name,
+    count()
+  group by project_id, day, event_name
+)
+
+alter table events
+add index idx_event_name event_name type set(100) granularity 4
@@ -0,0 +1,108 @@
+use crate::engine::health::TableHealth;
+use crate::engine::rules::Finding;
+
+// Columns listed in the summary, largest first.
+const TOP_COLUMNS: usize = 5;
+
+fn mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+pub fn render_text(health: &TableHealth, findings: &[Finding]) -> String {
+    let mut out = format!("Table {}\n", health.table);
+    let busiest = match &health.busiest_partition {
+        Some(p) => format!("; busiest {} with {}", p.partition_id, p.parts),
+        None => String::new(),
+    };
+    out.push_str(&format!(
+        "  Partitions:  {} ({} active parts, {:.1} per partition{busiest})\n",
+        health.partitions,
+        health.active_parts,
+        health.parts_per_partition()
+    ));
+    out.push_str(&format!("  Rows:        {}\n", health.rows));
+    out.push_str(&format!(
+        "  Compression: {:.1}x ({} on disk)\n",
+        health.compression_ratio(),
+        mib(health.compressed_bytes)
+    ));
+    for column in health.columns.iter().take(TOP_COLUMNS) {
+        out.push_str(&format!(
+            "    {:<12} {:>5.1}x  {}\n",
+            column.name,
+            column.ratio(),
+            mib(column.compressed)
+        ));
+    }
+    out.push_str(&format!(
+        "  Merges:      {} running\n",
+        health.merges_running
+    ));
+    out.push_str(&format!(
+        "  Mutations:   {} pending\n",
+        health.pending_mutations.len()
+    ));
+    if health.replicas.is_empty() {
+        out.push_str("  Replication: not replicated\n");
+    } else {
+        out.push_str(&format!(
+            "  Replication: {} replica(s), max delay {}s\n",
+            health.replicas.len(),
+            health.max_replica_delay()
+        ));
+    }
+
+    out.push('\n');
+    if findings.is_empty() {
+        out.push_str("No issues found.\n");
+    }
+    for finding in findings {
+        out.push_str(&format!(
+            "{}[{}]: {}\n  why: {}\n",
+            finding.severity.as_str(),
+            finding.rule_id,
+            finding.message,
+            finding.rationale
+        ));
+        if let Some(sql) = &finding.suggested_sql {
+            out.push_str(&format!("  fix: {sql}\n"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::health::PartitionRow;
+    use crate::engine::rules::{Category, Severity};
+
+    #[test]
+    fn renders_summary_and_findings() {
+        let health = TableHealth {
+            table: "events".to_string(),
+            partitions: 2,
+            active_parts: 10,
+            busiest_partition: Some(PartitionRow {
+                partition_id: "202403".to_string(),
+                parts: 8,
+                rows: 100,
+            }),
+            ..Default::default()
+        };
+        let finding = Finding {
+            rule_id: "live-compression",
+            severity: Severity::Warning,
+            category: Category::Storage,
+            message: "Column `x` compresses only 1.1x".to_string(),
+            rationale: "because".to_string(),
+            suggested_sql: Some("ALTER TABLE events MODIFY COLUMN x CODEC(ZSTD(3));".to_string()),
+        };
+        let text = render_text(&health, &[finding]);
+        assert!(text.contains("2 (10 active parts, 5.0 per partition; busiest 202403 with 8)"));
+        assert!(text.contains("Replication: not replicated"));
+        assert!(text.contains("warning[live-compression]: Column `x`"));
+        assert!(text.contains("  fix: ALTER TABLE events MODIFY COLUMN x CODEC(ZSTD(3));"));
+        assert!(render_text(&health, &[]).ends_with("No issues found.\n"));
+    }
+}
@@ -0,0 +1,231 @@
+use crate::engine::lint::{LintReport, Status};
+use crate::engine::rules::Severity;
+use serde_json::json;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const INFORMATION_URI: &str = "https://github.com/lucasarieta/sharp-cli";
+
+pub fn render_text(report: &LintReport, path: &str) -> String {
+    let mut out = String::new();
+    for finding in report.findings() {
+        out.push_str(&format!(
+            "{}[{}]: {}\n  --> {path}\n  why: {}\n",
+            finding.severity.as_str(),
+            finding.rule_id,
+            finding.message,
+            finding.rationale
+        ));
+    }
+    out.push_str(&format!(
+        "{path}: {} error(s), {} warning(s), {} info, {} suppressed\n",
+        report.count(Severity::Error),
+        report.count(Severity::Warning),
+        report.count(Severity::Info),
+        report.suppressed()
+    ));
+    out
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
+
+// SARIF 2.1.0, the format GitHub code scanning and most CI dashboards ingest.
+// Suppressed findings are kept with an inSource suppression so reviewers can
+// still see them.
+pub fn render_sarif(report: &LintReport, path: &str) -> String {
+    let rules: Vec<_> = report
+        .results
+        .iter()
+        .map(|r| {
+            json!({
+                "id": r.rule_id,
+                "defaultConfiguration": { "level": sarif_level(r.severity) },
+                "properties": { "category": r.category.as_str() },
+            })
+        })
+        .collect();
+
+    let results: Vec<_> = report
+        .results
+        .iter()
+        .filter_map(|r| {
+            let (finding, suppressed) = match r.status {
+                Status::Fired(ref f) => (f, false),
+                Status::Suppressed(ref f) => (f, true),
+                _ => return None,
+            };
+            let mut result = json!({
+                "ruleId": finding.rule_id,
+                "level": sarif_level(finding.severity),
+                "message": { "text": format!("{} {}", finding.message, finding.rationale) },
+                "locations": [{
+                    "physicalLocation": { "artifactLocation": { "uri": path } }
+                }],
+            });
+            if suppressed {
+                result["suppressions"] = json!([{ "kind": "inSource" }]);
+            }
+            Some(result)
+        })
+        .collect();
+
+    let sarif = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "sharp",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": INFORMATION_URI,
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&sarif).expect("SARIF is always serializable")
+}
+
+pub(super) fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// One test case per registered rule, so CI test reports show the full rule set
+// and not only what fired. Info findings pass; they are advice, not failures.
+pub fn render_junit(report: &LintReport, path: &str) -> String {
+    let failures = report
+        .findings()
+        .filter(|f| f.severity > Severity::Info)
+        .count();
+    let skipped = report
+        .results
+        .iter()
+        .filter(|r| matches!(r.status, Status::Suppressed(_) | Status::Disabled))
+        .count();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuite name=\"sharp lint {}\" tests=\"{}\" failures=\"{failures}\" skipped=\"{skipped}\">\n",
+        xml_escape(path),
+        report.results.len()
+    ));
+    for r in &report.results {
+        out.push_str(&format!(
+            "  <testcase classname=\"sharp.{}\" name=\"{}\"",
+            r.category.as_str(),
+            r.rule_id
+        ));
+        match r.status {
+            Status::Fired(ref f) if f.severity > Severity::Info => {
+                out.push_str(&format!(
+                    ">\n    <failure type=\"{}\" message=\"{}\">{}</failure>\n  </testcase>\n",
+                    f.severity.as_str(),
+                    xml_escape(&f.message),
+                    xml_escape(&f.rationale)
+                ));
+            }
+            Status::Suppressed(_) => {
+                out.push_str(
+                    ">\n    <skipped message=\"suppressed by sharp:allow\"/>\n  </testcase>\n",
+                );
+            }
+            Status::Disabled => {
+                out.push_str(">\n    <skipped message=\"disabled in rules\"/>\n  </testcase>\n");
+            }
+            _ => out.push_str("/>\n"),
+        }
+    }
+    out.push_str("</testsuite>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::lint::RuleResult;
+    use crate::engine::rules::{Category, Finding};
+
+    fn finding(rule_id: &'static str, severity: Severity) -> Finding {
+        Finding {
+            rule_id,
+            severity,
+            category: Category::Scaling,
+            message: "Consider <sharding>".to_string(),
+            rationale: "because & so".to_string(),
+            suggested_sql: None,
+        }
+    }
+
+    fn result(rule_id: &'static str, severity: Severity, status: Status) -> RuleResult {
+        RuleResult {
+            rule_id,
+            severity,
+            category: Category::Scaling,
+            status,
+        }
+    }
+
+    fn report() -> LintReport {
+        LintReport {
+            results: vec![
+                result(
+                    "sharding",
+                    Severity::Warning,
+                    Status::Fired(finding("sharding", Severity::Warning)),
+                ),
+                result(
+                    "too-many-parts",
+                    Severity::Error,
+                    Status::Suppressed(finding("too-many-parts", Severity::Error)),
+                ),
+                result("wide-parts", Severity::Info, Status::Passed),
+                result("compression", Severity::Info, Status::Disabled),
+            ],
+        }
+    }
+
+    #[test]
+    fn text_lists_findings_and_summary() {
+        let text = render_text(&report(), "schema.yaml");
+        assert!(text.starts_with("warning[sharding]: Consider <sharding>\n"));
+        assert!(text.ends_with("schema.yaml: 0 error(s), 1 warning(s), 0 info, 1 suppressed\n"));
+    }
+
+    #[test]
+    fn sarif_has_rules_and_results() {
+        let sarif: serde_json::Value =
+            serde_json::from_str(&render_sarif(&report(), "schema.yaml")).unwrap();
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "sharp");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 4);
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "sharding");
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "schema.yaml"
+        );
+        assert_eq!(results[1]["suppressions"][0]["kind"], "inSource");
+    }
+
+    #[test]
+    fn junit_has_a_case_per_rule() {
+        let xml = render_junit(&report(), "schema.yaml");
+        assert!(xml.contains("tests=\"4\" failures=\"1\" skipped=\"2\""));
+        assert!(xml.contains("message=\"Consider &lt;sharding&gt;\">because &amp; so</failure>"));
+        assert!(xml.contains("<testcase classname=\"sharp.scaling\" name=\"wide-parts\"/>"));
+        assert!(xml.contains("<skipped message=\"disabled in rules\"/>"));
+    }
+}
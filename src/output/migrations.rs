@@ -0,0 +1,330 @@
+use crate::config::policy::FormatOptions;
+use crate::engine::ddl;
+use crate::errors::SharpError;
+use crate::output::formatter::format_statement;
+use crate::sql::ast::{AlterCommand, Statement};
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    // 001_name.sql with 001_name.down.sql beside it.
+    Plain,
+    // 00001_name.sql holding both directions between +goose annotations.
+    Goose,
+    // 000001_name.up.sql and 000001_name.down.sql.
+    GolangMigrate,
+    // V1__name.sql and the undo migration U1__name.sql.
+    Flyway,
+}
+
+impl Layout {
+    // (file name, contents) pairs for one migration.
+    fn files(self, version: u32, stem: &str, up: &str, down: &str) -> Vec<(String, String)> {
+        match self {
+            Layout::Plain => vec![
+                (format!("{version:03}_{stem}.sql"), up.to_string()),
+                (format!("{version:03}_{stem}.down.sql"), down.to_string()),
+            ],
+            Layout::Goose => vec![(
+                format!("{version:05}_{stem}.sql"),
+                format!("-- +goose Up\n{up}\n\n-- +goose Down\n{down}"),
+            )],
+            Layout::GolangMigrate => vec![
+                (format!("{version:06}_{stem}.up.sql"), up.to_string()),
+                (format!("{version:06}_{stem}.down.sql"), down.to_string()),
+            ],
+            Layout::Flyway => vec![
+                (format!("V{version}__{stem}.sql"), up.to_string()),
+                (format!("U{version}__{stem}.sql"), down.to_string()),
+            ],
+        }
+    }
+
+    // The version a file in the migrations directory claims, if it is one of
+    // this layout's.
+    fn version(self, file_name: &str) -> Option<u32> {
+        let digits = match self {
+            Layout::Flyway => {
+                file_name
+                    .strip_prefix('V')
+                    .or_else(|| file_name.strip_prefix('U'))?
+                    .split_once("__")?
+                    .0
+            }
+            _ => file_name.split_once('_')?.0,
+        };
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+}
+
+struct Migration<'a> {
+    stem: String,
+    statements: Vec<&'a Statement>,
+}
+
+// Undoes the migration's statements in reverse order.
+fn down(migration: &Migration, options: &FormatOptions) -> String {
+    migration
+        .statements
+        .iter()
+        .rev()
+        .map(|s| match s.inverse() {
+            Some(inverse) => format_statement(&inverse, options),
+            None => format!("-- {} has no automatic inverse", ddl::label(&s.to_sql())),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// Numbered from 1 in dependency order, so the tool runs the table before the
+// objects that hang off it. Nothing is written if any of the versions is
+// already taken in `dir`: an applied migration must never change under the
+// tool's feet.
+pub fn write(
+    dir: &Path,
+    statements: &[Statement],
+    layout: Layout,
+    options: &FormatOptions,
+) -> Result<Vec<PathBuf>, SharpError> {
+    let migrations: Vec<Migration> = if options.one_statement_per_file {
+        statements
+            .iter()
+            .map(|s| Migration {
+                stem: file_stem(s),
+                statements: vec![s],
+            })
+            .collect()
+    } else {
+        vec![Migration {
+            stem: "schema".to_string(),
+            statements: statements.iter().collect(),
+        }]
+    };
+    // goose splits a file into statements on semicolons.
+    let options = &FormatOptions {
+        semicolons: options.semicolons || layout == Layout::Goose,
+        ..options.clone()
+    };
+
+    let mut files = Vec::new();
+    for (version, migration) in (1..).zip(&migrations) {
+        let up = migration
+            .statements
+            .iter()
+            .map(|s| format_statement(s, options))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let down = down(migration, options);
+        for (name, sql) in layout.files(version, &migration.stem, &up, &down) {
+            files.push((version, dir.join(name), sql));
+        }
+    }
+
+    let versions: BTreeSet<u32> = files.iter().map(|(v, _, _)| *v).collect();
+    std::fs::create_dir_all(dir).map_err(|e| SharpError::WriteError(dir.to_path_buf(), e))?;
+    let mut taken = Vec::new();
+    let entries = std::fs::read_dir(dir).map_err(|e| SharpError::IoError(dir.to_path_buf(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| SharpError::IoError(dir.to_path_buf(), e))?;
+        if layout
+            .version(&entry.file_name().to_string_lossy())
+            .is_some_and(|v| versions.contains(&v))
+        {
+            taken.push(entry.path());
+        }
+    }
+    if let Some(path) = taken.into_iter().min() {
+        return Err(SharpError::MigrationExists(path));
+    }
+
+    for (_, path, sql) in &files {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{sql}"))
+            .map_err(|e| SharpError::WriteError(path.clone(), e))?;
+    }
+    Ok(files.into_iter().map(|(_, path, _)| path).collect())
+}
+
+// `create_events`, `add_projection_daily_event_counts`, ...
+fn file_stem(statement: &Statement) -> String {
+    let (action, name) = match statement {
+        Statement::CreateTable(table) => ("create", &table.table_name.name),
+        Statement::CreateView { name, .. } | Statement::CreateMaterializedView { name, .. } => {
+            ("create", &name.name)
+        }
+        Statement::CreateDictionary(dictionary) => ("create", &dictionary.name.name),
+        Statement::Alter {
+            command: AlterCommand::AddIndex { name, .. },
+            ..
+        } => ("add_index", name),
+        Statement::Alter {
+            command: AlterCommand::AddProjection { name, .. },
+            ..
+        } => ("add_projection", name),
+        Statement::Alter { table, .. } => ("alter", &table.name),
+        Statement::Delete { table, .. } => ("delete", &table.name),
+        Statement::Drop { name, .. } => ("drop", &name.name),
+    };
+    let name: String = name
+        .as_str()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{action}_{name}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::parse_schema;
+    use crate::config::policy::Policy;
+    use crate::config::workload::WorkloadProfile;
+
+    const SCHEMA: &str = "\
+event_table:
+  name: events
+  expected_events_per_day: 1000000
+  retention_days: 30
+  skip_indexes:
+    - name: idx_event_name
+      expression: event_name
+      type: set(100)
+      granularity: 4
+";
+
+    fn statements() -> Vec<Statement> {
+        let schema = parse_schema(SCHEMA).unwrap();
+        let workload = WorkloadProfile::from_schema(&schema);
+        ddl::statements(&schema, &workload, &Policy::default())
+    }
+
+    fn names(files: &[PathBuf]) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn golang_migrate_pairs_up_and_down_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = write(
+            dir.path(),
+            &statements(),
+            Layout::GolangMigrate,
+            &FormatOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            names(&files),
+            [
+                "000001_create_events.up.sql",
+                "000001_create_events.down.sql",
+                "000002_add_projection_daily_event_counts.up.sql",
+                "000002_add_projection_daily_event_counts.down.sql",
+                "000003_add_index_idx_event_name.up.sql",
+                "000003_add_index_idx_event_name.down.sql",
+            ]
+        );
+        let read = |i: usize| std::fs::read_to_string(&files[i]).unwrap();
+        assert!(read(0).starts_with("CREATE TABLE events ("));
+        assert_eq!(read(1), "DROP TABLE events;\n");
+        assert_eq!(
+            read(3),
+            "ALTER TABLE events DROP PROJECTION daily_event_counts;\n"
+        );
+        assert_eq!(read(5), "ALTER TABLE events DROP INDEX idx_event_name;\n");
+    }
+
+    #[test]
+    fn goose_keeps_both_directions_in_one_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = FormatOptions {
+            one_statement_per_file: false,
+            if_not_exists: true,
+            semicolons: false,
+            ..FormatOptions::default()
+        };
+        let files = write(dir.path(), &statements(), Layout::Goose, &options).unwrap();
+        assert_eq!(names(&files), ["00001_schema.sql"]);
+        let sql = std::fs::read_to_string(&files[0]).unwrap();
+        assert!(sql.starts_with("-- +goose Up\nCREATE TABLE IF NOT EXISTS events ("));
+        assert!(sql.ends_with(
+            "-- +goose Down\nALTER TABLE events DROP INDEX IF EXISTS idx_event_name;\n\n\
+             ALTER TABLE events DROP PROJECTION IF EXISTS daily_event_counts;\n\n\
+             DROP TABLE IF EXISTS events;\n"
+        ));
+    }
+
+    #[test]
+    fn flyway_names_versioned_and_undo_migrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = write(
+            dir.path(),
+            &statements(),
+            Layout::Flyway,
+            &FormatOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            names(&files)[..2],
+            ["V1__create_events.sql", "U1__create_events.sql"]
+        );
+        let files = write(
+            &dir.path().join("plain"),
+            &statements(),
+            Layout::Plain,
+            &FormatOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            names(&files)[..2],
+            ["001_create_events.sql", "001_create_events.down.sql"]
+        );
+    }
+
+    #[test]
+    fn refuses_to_reuse_a_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("000002_add_column_plan.up.sql");
+        std::fs::write(&existing, "ALTER TABLE events ADD COLUMN plan String;\n").unwrap();
+
+        let err = write(
+            dir.path(),
+            &statements(),
+            Layout::GolangMigrate,
+            &FormatOptions::default(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, SharpError::MigrationExists(ref path) if *path == existing));
+        // Nothing was written, and the existing migration is untouched.
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert!(
+            std::fs::read_to_string(&existing)
+                .unwrap()
+                .contains("ADD COLUMN plan")
+        );
+    }
+
+    #[test]
+    fn parses_versions_per_layout() {
+        assert_eq!(Layout::Flyway.version("V12__add_index.sql"), Some(12));
+        assert_eq!(Layout::Flyway.version("README.md"), None);
+        assert_eq!(Layout::Goose.version("00003_create_events.sql"), Some(3));
+        assert_eq!(Layout::Plain.version("notes_on_migrations.txt"), None);
+    }
+}
@@ -1 +1,6 @@
+pub mod drift;
+pub mod explain;
 pub mod formatter;
+pub mod health;
+pub mod lint;
+pub mod migrations;
@@ -1,73 +1,703 @@
-#[derive(Debug)]
+use crate::errors::SharpError;
+use crate::sql::types::ClickHouseType;
+use std::fmt;
+
+// Keywords that would be parsed as syntax if left bare in a name position.
+// ClickHouse accepts many keywords as identifiers, but quoting them is never
+// wrong and saves guessing which ones the parser tolerates where.
+const RESERVED: &[&str] = &[
+    "ALL",
+    "ALTER",
+    "AND",
+    "ANY",
+    "ARRAY",
+    "AS",
+    "ASC",
+    "BETWEEN",
+    "BY",
+    "CASE",
+    "CAST",
+    "CREATE",
+    "CROSS",
+    "DATABASE",
+    "DEFAULT",
+    "DELETE",
+    "DESC",
+    "DISTINCT",
+    "DROP",
+    "ELSE",
+    "END",
+    "ENGINE",
+    "EXISTS",
+    "FINAL",
+    "FORMAT",
+    "FROM",
+    "FULL",
+    "GLOBAL",
+    "GROUP",
+    "HAVING",
+    "IF",
+    "IN",
+    "INDEX",
+    "INNER",
+    "INSERT",
+    "INTERVAL",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "NOT",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "PARTITION",
+    "PREWHERE",
+    "PRIMARY",
+    "RIGHT",
+    "SAMPLE",
+    "SELECT",
+    "SETTINGS",
+    "TABLE",
+    "THEN",
+    "TO",
+    "TTL",
+    "UNION",
+    "USING",
+    "VIEW",
+    "WHEN",
+    "WHERE",
+    "WITH",
+];
+
+// Words sharp writes in upper case that ClickHouse doesn't reserve. A bare
+// name spelled exactly like one is quoted, so a formatter that changes the
+// case of keywords can't rename it.
+const KEYWORDS: &[&str] = &[
+    "ADD",
+    "APPLY",
+    "CODEC",
+    "COLUMN",
+    "DAY",
+    "DELETED",
+    "DICTIONARY",
+    "GRANULARITY",
+    "HOUR",
+    "LAYOUT",
+    "LIFETIME",
+    "MASK",
+    "MATERIALIZE",
+    "MATERIALIZED",
+    "MAX",
+    "MIN",
+    "MINUTE",
+    "MODIFY",
+    "MONTH",
+    "PROJECTION",
+    "RANGE",
+    "REMOVE",
+    "SECOND",
+    "SETTING",
+    "SOURCE",
+    "TYPE",
+    "VALUES",
+    "WEEK",
+    "YEAR",
+];
+
+// Table names become directory names on disk.
+const MAX_IDENTIFIER_BYTES: usize = 255;
+
+// The longest suffix sharp appends to the event table's name, for the
+// `<table>_person_distinct_id` mapping table. Event table names are checked
+// to leave room for it.
+pub const MAX_SUFFIX_BYTES: usize = "_person_distinct_id".len();
+
+// Only the upper-case spelling counts: that is how sharp writes keywords, and
+// a bare word spelled that way can't be a name (see `needs_quoting`).
+pub fn is_keyword(word: &str) -> bool {
+    RESERVED.contains(&word) || KEYWORDS.contains(&word)
+}
+
+pub fn string_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// A single name (table, database or column), rendered with backticks when it
+// isn't a plain word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identifier(String);
+
+impl Identifier {
+    // For names sharp makes up itself; user input goes through `parse`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+
+    pub fn parse(name: &str) -> Result<Self, SharpError> {
+        let invalid = |reason: &str| SharpError::InvalidIdentifier(format!("{name:?}: {reason}"));
+        if name.is_empty() {
+            return Err(invalid("must not be empty"));
+        }
+        if name.len() > MAX_IDENTIFIER_BYTES {
+            return Err(invalid("longer than 255 bytes"));
+        }
+        if name.chars().any(char::is_control) {
+            return Err(invalid("contains control characters"));
+        }
+        Ok(Self::new(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn needs_quoting(&self) -> bool {
+        let mut chars = self.0.chars();
+        let plain = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        !plain
+            || RESERVED.contains(&self.0.to_ascii_uppercase().as_str())
+            || KEYWORDS.contains(&self.0.as_str())
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.needs_quoting() {
+            write!(f, "`{}`", self.0.replace('\\', "\\\\").replace('`', "\\`"))
+        } else {
+            f.write_str(&self.0)
+        }
+    }
+}
+
+// `table` or `database.table`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableName {
+    pub database: Option<Identifier>,
+    pub name: Identifier,
+}
+
+impl TableName {
+    // Splits on the first dot without validating, for names that already
+    // passed `parse` (or that sharp derived from one).
+    pub fn new(name: &str) -> Self {
+        match name.split_once('.') {
+            Some((database, table)) => Self {
+                database: Some(Identifier::new(database)),
+                name: Identifier::new(table),
+            },
+            None => Self {
+                database: None,
+                name: Identifier::new(name),
+            },
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, SharpError> {
+        match name.split_once('.') {
+            Some((_, table)) if table.contains('.') => Err(SharpError::InvalidIdentifier(format!(
+                "{name:?}: expected table or database.table"
+            ))),
+            Some((database, table)) => Ok(Self {
+                database: Some(Identifier::parse(database)?),
+                name: Identifier::parse(table)?,
+            }),
+            None => Ok(Self {
+                database: None,
+                name: Identifier::parse(name)?,
+            }),
+        }
+    }
+
+    // Matches this table's rows in the system tables, where the table name
+    // sits in `name_column` (`name` in system.tables, `table` elsewhere).
+    pub fn system_filter(&self, name_column: &str) -> String {
+        let database = match &self.database {
+            Some(database) => string_literal(database.as_str()),
+            None => "currentDatabase()".to_string(),
+        };
+        format!(
+            "database = {database} AND {name_column} = {}",
+            string_literal(self.name.as_str())
+        )
+    }
+
+    // A companion object in the same database, e.g. `events` -> `events_kafka`.
+    // Only called on names `parse` accepted with MAX_SUFFIX_BYTES to spare, so
+    // a name that no longer fits is a bug rather than bad input.
+    pub fn with_suffix(&self, suffix: &str) -> Self {
+        let name = format!("{}{suffix}", self.name.as_str());
+        assert!(
+            name.len() <= MAX_IDENTIFIER_BYTES,
+            "{name:?} is longer than {MAX_IDENTIFIER_BYTES} bytes"
+        );
+        Self {
+            database: self.database.clone(),
+            name: Identifier::new(name),
+        }
+    }
+
+    // `parse`, leaving room for every suffix sharp appends.
+    pub fn parse_event_table(name: &str) -> Result<Self, SharpError> {
+        let table = Self::parse(name)?;
+        if table.name.as_str().len() + MAX_SUFFIX_BYTES > MAX_IDENTIFIER_BYTES {
+            return Err(SharpError::InvalidIdentifier(format!(
+                "{name:?}: longer than {} bytes, which leaves no room for the \
+                 companion tables' suffixes",
+                MAX_IDENTIFIER_BYTES - MAX_SUFFIX_BYTES
+            )));
+        }
+        Ok(table)
+    }
+}
+
+impl fmt::Display for TableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.database {
+            Some(database) => write!(f, "{database}.{}", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CreateTable {
-    pub table_name: String,
+    pub table_name: TableName,
     pub columns: Vec<ColumnExpr>,
     pub engine: String,
     pub partition_by: Option<String>,
     pub order_by: Vec<String>,
+    pub sample_by: Option<String>,
     pub ttl: Option<String>,
+    pub settings: Vec<(String, String)>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ColumnExpr {
-    pub name: String,
-    pub data_type: String,
-    pub nullable: bool,
+    pub name: Identifier,
+    pub data_type: ClickHouseType,
+    pub default: Option<String>,
 }
 
-impl CreateTable {
-    pub fn to_sql(&self) -> String {
-        let mut sql = format!("CREATE TABLE {} (\n", self.table_name);
+impl ColumnExpr {
+    // As written in CREATE TABLE and ALTER TABLE ... ADD/MODIFY COLUMN.
+    pub fn definition(&self) -> String {
+        self.padded(0)
+    }
+
+    // The name padded to `width` characters so types line up in a list.
+    fn padded(&self, width: usize) -> String {
+        let name = format!("{:<width$}", self.name.to_string());
+        match &self.default {
+            Some(default) => format!("{name} {} DEFAULT {default}", self.data_type),
+            None => format!("{name} {}", self.data_type),
+        }
+    }
+}
 
-        let col_defs: Vec<String> = self
-            .columns
+// Choices that change the statement's text but not what it does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderOptions {
+    // Re-running it against a server that already has the object (or, for a
+    // drop, no longer has it) is a no-op.
+    pub if_not_exists: bool,
+    pub align_types: bool,
+}
+
+impl CreateTable {
+    // Column types must be valid on their own, and the sort key can't hold a
+    // Nullable column unless the table opts in with allow_nullable_key.
+    pub fn validate(&self) -> Result<(), SharpError> {
+        for column in &self.columns {
+            column
+                .data_type
+                .validate()
+                .map_err(|e| SharpError::InvalidType(format!("of column {}: {e}", column.name)))?;
+        }
+        let nullable_keys = self
+            .settings
             .iter()
-            .map(|c| {
-                let typ = if c.nullable {
-                    format!("Nullable({})", c.data_type)
-                } else {
-                    c.data_type.clone()
-                };
-                format!("    {} {}", c.name, typ)
-            })
-            .collect();
+            .any(|(k, v)| k == "allow_nullable_key" && v == "1");
+        if nullable_keys {
+            return Ok(());
+        }
+        match self.columns.iter().find(|c| {
+            c.data_type.is_nullable() && self.order_by.iter().any(|k| k == c.name.as_str())
+        }) {
+            Some(column) => Err(SharpError::InvalidType(format!(
+                "of sort key column {}: {} needs allow_nullable_key = 1",
+                column.name, column.data_type
+            ))),
+            None => Ok(()),
+        }
+    }
 
-        sql.push_str(&col_defs.join(",\n"));
-        sql.push_str(&format!("\n) ENGINE = {}", self.engine));
+    // Callers outside tests go through Statement::CreateTable.
+    #[cfg(test)]
+    pub fn to_sql(&self) -> String {
+        format!("{};", self.render(&RenderOptions::default()))
+    }
 
-        if let Some(ref p) = self.partition_by {
+    fn render(&self, options: &RenderOptions) -> String {
+        let mut sql = format!(
+            "CREATE TABLE {}{} (\n{}\n) ENGINE = {}",
+            if_not_exists(options),
+            self.table_name,
+            column_list(&self.columns, options),
+            self.engine
+        );
+        if let Some(p) = &self.partition_by {
             sql.push_str(&format!("\nPARTITION BY {p}"));
         }
-
         if !self.order_by.is_empty() {
             sql.push_str(&format!("\nORDER BY ({})", self.order_by.join(", ")));
         }
-
-        if let Some(ref t) = self.ttl {
+        if let Some(s) = &self.sample_by {
+            sql.push_str(&format!("\nSAMPLE BY {s}"));
+        }
+        if let Some(t) = &self.ttl {
             sql.push_str(&format!("\nTTL {t}"));
         }
-
-        sql.push(';');
+        if !self.settings.is_empty() {
+            let settings: Vec<String> = self
+                .settings
+                .iter()
+                .map(|(k, v)| format!("    {k} = {v}"))
+                .collect();
+            sql.push_str(&format!("\nSETTINGS\n{}", settings.join(",\n")));
+        }
         sql
     }
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateDictionary {
+    pub name: TableName,
+    pub columns: Vec<ColumnExpr>,
+    pub primary_key: Vec<Identifier>,
+    // The SOURCE(...) argument, e.g. CLICKHOUSE(TABLE 'tenants').
+    pub source: String,
+    // Reload interval in seconds; None never reloads.
+    pub lifetime: Option<(u64, u64)>,
+    // The LAYOUT(...) argument, e.g. HASHED().
+    pub layout: String,
+    // Validity interval columns of RANGE_HASHED layouts.
+    pub range: Option<(Identifier, Identifier)>,
+}
+
+// What follows ALTER TABLE <table>.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlterCommand {
+    AddColumn(ColumnExpr),
+    ModifyColumn(ColumnExpr),
+    ModifyColumnCodec {
+        column: Identifier,
+        codec: String,
+    },
+    DropColumn(Identifier),
+    ModifySampleBy(String),
+    RemoveSampleBy,
+    ModifyTtl(String),
+    RemoveTtl,
+    ModifySetting(String, String),
+    AddIndex {
+        name: Identifier,
+        expression: String,
+        index_type: String,
+        granularity: u32,
+    },
+    DropIndex(Identifier),
+    MaterializeIndex(Identifier),
+    // `select` is the projection body without the surrounding parentheses.
+    AddProjection {
+        name: Identifier,
+        select: String,
+    },
+    DropProjection(Identifier),
+    MaterializeProjection(Identifier),
+    Delete(String),
+    ApplyDeletedMask,
+}
+
+// The DDL (and lightweight DELETE) sharp emits. Bodies of views and
+// projections are kept as SQL text; everything around them is structured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    CreateTable(CreateTable),
+    CreateView {
+        name: TableName,
+        select: String,
+    },
+    CreateMaterializedView {
+        name: TableName,
+        to: TableName,
+        // Empty when the SELECT already lines up with the target's columns.
+        columns: Vec<Identifier>,
+        select: String,
+    },
+    CreateDictionary(CreateDictionary),
+    Alter {
+        table: TableName,
+        command: AlterCommand,
+    },
+    Delete {
+        table: TableName,
+        condition: String,
+    },
+    Drop {
+        kind: ObjectKind,
+        name: TableName,
+    },
+}
+
+// What DROP removes; materialized views are dropped as views.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjectKind {
+    Table,
+    View,
+    Dictionary,
+}
+
+impl fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ObjectKind::Table => "TABLE",
+            ObjectKind::View => "VIEW",
+            ObjectKind::Dictionary => "DICTIONARY",
+        })
+    }
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn column_list(columns: &[ColumnExpr], options: &RenderOptions) -> String {
+    let width = if options.align_types {
+        columns
+            .iter()
+            .map(|c| c.name.to_string().chars().count())
+            .max()
+            .unwrap_or(0)
+    } else {
+        0
+    };
+    columns
+        .iter()
+        .map(|c| format!("    {}", c.padded(width)))
+        .collect::<Vec<_>>()
+        .join(",\n")
+}
+
+fn if_not_exists(options: &RenderOptions) -> &'static str {
+    if options.if_not_exists {
+        "IF NOT EXISTS "
+    } else {
+        ""
+    }
+}
+
+fn if_exists(options: &RenderOptions) -> &'static str {
+    if options.if_not_exists {
+        "IF EXISTS "
+    } else {
+        ""
+    }
+}
+
+fn joined(items: &[Identifier]) -> String {
+    items
+        .iter()
+        .map(Identifier::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl AlterCommand {
+    // The command that undoes this one, where there is one: a modified TTL or
+    // column doesn't remember what it replaced.
+    pub fn inverse(&self) -> Option<AlterCommand> {
+        match self {
+            Self::AddColumn(column) => Some(Self::DropColumn(column.name.clone())),
+            Self::AddIndex { name, .. } => Some(Self::DropIndex(name.clone())),
+            Self::AddProjection { name, .. } => Some(Self::DropProjection(name.clone())),
+            _ => None,
+        }
+    }
+
+    // Commands that define an object start on their own line.
+    fn render(&self, options: &RenderOptions) -> String {
+        let (if_not_exists, if_exists) = (if_not_exists(options), if_exists(options));
+        match self {
+            Self::AddColumn(column) => format!(" ADD COLUMN {}", column.definition()),
+            Self::ModifyColumn(column) => format!(" MODIFY COLUMN {}", column.definition()),
+            Self::ModifyColumnCodec { column, codec } => {
+                format!(" MODIFY COLUMN {column} CODEC({codec})")
+            }
+            Self::DropColumn(column) => format!(" DROP COLUMN {if_exists}{column}"),
+            Self::ModifySampleBy(expr) => format!(" MODIFY SAMPLE BY {expr}"),
+            Self::RemoveSampleBy => " REMOVE SAMPLE BY".to_string(),
+            Self::ModifyTtl(expr) => format!(" MODIFY TTL {expr}"),
+            Self::RemoveTtl => " REMOVE TTL".to_string(),
+            Self::ModifySetting(name, value) => format!(" MODIFY SETTING {name} = {value}"),
+            Self::AddIndex {
+                name,
+                expression,
+                index_type,
+                granularity,
+            } => format!(
+                "\nADD INDEX {if_not_exists}{name} {expression} TYPE {index_type} GRANULARITY \
+                 {granularity}"
+            ),
+            Self::DropIndex(name) => format!(" DROP INDEX {if_exists}{name}"),
+            Self::MaterializeIndex(name) => format!(" MATERIALIZE INDEX {name}"),
+            Self::AddProjection { name, select } => format!(
+                "\nADD PROJECTION {if_not_exists}{name}\n(\n{}\n)",
+                indent(select)
+            ),
+            Self::DropProjection(name) => format!(" DROP PROJECTION {if_exists}{name}"),
+            Self::MaterializeProjection(name) => format!(" MATERIALIZE PROJECTION {name}"),
+            Self::Delete(condition) => format!(" DELETE WHERE {condition}"),
+            Self::ApplyDeletedMask => " APPLY DELETED MASK".to_string(),
+        }
+    }
+}
+
+impl Statement {
+    pub fn alter(table: &TableName, command: AlterCommand) -> Self {
+        Statement::Alter {
+            table: table.clone(),
+            command,
+        }
+    }
+
+    pub fn to_sql(&self) -> String {
+        format!("{};", self.render(&RenderOptions::default()))
+    }
+
+    // Whether this defines a schema object rather than changing data or
+    // dropping one.
+    pub fn creates(&self) -> bool {
+        !matches!(
+            self,
+            Statement::Delete { .. }
+                | Statement::Drop { .. }
+                | Statement::Alter {
+                    command: AlterCommand::Delete(_)
+                        | AlterCommand::ApplyDeletedMask
+                        | AlterCommand::DropColumn(_)
+                        | AlterCommand::DropIndex(_)
+                        | AlterCommand::DropProjection(_),
+                    ..
+                }
+        )
+    }
+
+    // What a down migration runs to undo this statement. Data changes and
+    // in-place modifications have no inverse.
+    pub fn inverse(&self) -> Option<Statement> {
+        let drop = |kind, name: &TableName| Statement::Drop {
+            kind,
+            name: name.clone(),
+        };
+        match self {
+            Statement::CreateTable(table) => Some(drop(ObjectKind::Table, &table.table_name)),
+            Statement::CreateView { name, .. } | Statement::CreateMaterializedView { name, .. } => {
+                Some(drop(ObjectKind::View, name))
+            }
+            Statement::CreateDictionary(dictionary) => {
+                Some(drop(ObjectKind::Dictionary, &dictionary.name))
+            }
+            Statement::Alter { table, command } => command
+                .inverse()
+                .map(|command| Statement::alter(table, command)),
+            Statement::Drop { .. } | Statement::Delete { .. } => None,
+        }
+    }
+
+    // Without the trailing semicolon.
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let ine = if_not_exists(options);
+        match self {
+            Statement::CreateTable(table) => table.render(options),
+            Statement::CreateView { name, select } => {
+                format!("CREATE VIEW {ine}{name} AS\n{select}")
+            }
+            Statement::CreateMaterializedView {
+                name,
+                to,
+                columns,
+                select,
+            } => {
+                let columns = if columns.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", joined(columns))
+                };
+                format!("CREATE MATERIALIZED VIEW {ine}{name} TO {to}{columns} AS\n{select}")
+            }
+            Statement::CreateDictionary(dictionary) => {
+                let lifetime = match dictionary.lifetime {
+                    Some((min, max)) => format!("LIFETIME(MIN {min} MAX {max})"),
+                    None => "LIFETIME(0)".to_string(),
+                };
+                let mut sql = format!(
+                    "CREATE DICTIONARY {ine}{} (\n{}\n)\nPRIMARY KEY {}\nSOURCE({})\n{lifetime}\n\
+                     LAYOUT({})",
+                    dictionary.name,
+                    column_list(&dictionary.columns, options),
+                    joined(&dictionary.primary_key),
+                    dictionary.source,
+                    dictionary.layout
+                );
+                if let Some((min, max)) = &dictionary.range {
+                    sql.push_str(&format!("\nRANGE(MIN {min} MAX {max})"));
+                }
+                sql
+            }
+            Statement::Alter { table, command } => {
+                format!("ALTER TABLE {table}{}", command.render(options))
+            }
+            Statement::Delete { table, condition } => {
+                format!("DELETE FROM {table} WHERE {condition}")
+            }
+            Statement::Drop { kind, name } => {
+                format!("DROP {kind} {}{name}", if_exists(options))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     fn minimal_table() -> CreateTable {
         CreateTable {
-            table_name: "events".to_string(),
+            table_name: TableName::new("events"),
             columns: vec![ColumnExpr {
-                name: "id".to_string(),
-                data_type: "UInt64".to_string(),
-                nullable: false,
+                name: Identifier::new("id"),
+                data_type: ClickHouseType::UInt(64),
+                default: None,
             }],
             engine: "MergeTree".to_string(),
             partition_by: None,
             order_by: vec![],
+            sample_by: None,
             ttl: None,
+            settings: vec![],
         }
     }
 
@@ -84,9 +714,9 @@ mod tests {
     fn nullable_column_wraps_type() {
         let table = CreateTable {
             columns: vec![ColumnExpr {
-                name: "email".to_string(),
-                data_type: "String".to_string(),
-                nullable: true,
+                name: Identifier::new("email"),
+                data_type: ClickHouseType::String.nullable(),
+                default: None,
             }],
             ..minimal_table()
         };
@@ -94,19 +724,33 @@ mod tests {
         assert!(sql.contains("email Nullable(String)"));
     }
 
+    #[test]
+    fn default_expression_rendered() {
+        let table = CreateTable {
+            columns: vec![ColumnExpr {
+                name: Identifier::new("ingested_at"),
+                data_type: ClickHouseType::DateTime(None),
+                default: Some("now()".to_string()),
+            }],
+            ..minimal_table()
+        };
+        let sql = table.to_sql();
+        assert!(sql.contains("ingested_at DateTime DEFAULT now()"));
+    }
+
     #[test]
     fn multiple_columns_comma_separated() {
         let table = CreateTable {
             columns: vec![
                 ColumnExpr {
-                    name: "a".to_string(),
-                    data_type: "UInt32".to_string(),
-                    nullable: false,
+                    name: Identifier::new("a"),
+                    data_type: ClickHouseType::UInt(32),
+                    default: None,
                 },
                 ColumnExpr {
-                    name: "b".to_string(),
-                    data_type: "String".to_string(),
-                    nullable: false,
+                    name: Identifier::new("b"),
+                    data_type: ClickHouseType::String,
+                    default: None,
                 },
             ],
             ..minimal_table()
@@ -147,6 +791,22 @@ mod tests {
         assert!(!sql.contains("ORDER BY"));
     }
 
+    #[test]
+    fn sample_by_rendered_when_present() {
+        let table = CreateTable {
+            sample_by: Some("cityHash64(user)".to_string()),
+            ..minimal_table()
+        };
+        let sql = table.to_sql();
+        assert!(sql.contains("SAMPLE BY cityHash64(user)"));
+    }
+
+    #[test]
+    fn sample_by_omitted_when_none() {
+        let sql = minimal_table().to_sql();
+        assert!(!sql.contains("SAMPLE BY"));
+    }
+
     #[test]
     fn ttl_rendered_when_present() {
         let table = CreateTable {
@@ -163,30 +823,301 @@ mod tests {
         assert!(!sql.contains("TTL"));
     }
 
+    #[test]
+    fn settings_rendered_one_per_line() {
+        let table = CreateTable {
+            settings: vec![
+                ("kafka_topic_list".to_string(), "'events'".to_string()),
+                ("kafka_num_consumers".to_string(), "2".to_string()),
+            ],
+            ..minimal_table()
+        };
+        let sql = table.to_sql();
+        assert!(
+            sql.ends_with(
+                "SETTINGS\n    kafka_topic_list = 'events',\n    kafka_num_consumers = 2;"
+            )
+        );
+    }
+
+    #[test]
+    fn settings_omitted_when_empty() {
+        let sql = minimal_table().to_sql();
+        assert!(!sql.contains("SETTINGS"));
+    }
+
     #[test]
     fn full_statement_clauses_in_order() {
         let table = CreateTable {
-            table_name: "t".to_string(),
+            table_name: TableName::new("t"),
             columns: vec![ColumnExpr {
-                name: "id".to_string(),
-                data_type: "UInt64".to_string(),
-                nullable: false,
+                name: Identifier::new("id"),
+                data_type: ClickHouseType::UInt(64),
+                default: None,
             }],
             engine: "MergeTree".to_string(),
             partition_by: Some("toYYYYMMDD(ts)".to_string()),
             order_by: vec!["id".to_string()],
+            sample_by: Some("intHash32(id)".to_string()),
             ttl: Some("ts + INTERVAL 7 DAY".to_string()),
+            settings: vec![("index_granularity".to_string(), "8192".to_string())],
         };
         let sql = table.to_sql();
 
         let engine_pos = sql.find("ENGINE").unwrap();
         let partition_pos = sql.find("PARTITION BY").unwrap();
         let order_pos = sql.find("ORDER BY").unwrap();
+        let sample_pos = sql.find("SAMPLE BY").unwrap();
         let ttl_pos = sql.find("TTL").unwrap();
 
         assert!(engine_pos < partition_pos);
         assert!(partition_pos < order_pos);
-        assert!(order_pos < ttl_pos);
+        assert!(order_pos < sample_pos);
+        assert!(sample_pos < ttl_pos);
+        assert!(ttl_pos < sql.find("SETTINGS").unwrap());
         assert!(sql.ends_with(';'));
     }
+
+    #[test]
+    fn plain_identifiers_stay_bare() {
+        assert_eq!(Identifier::new("user_events").to_string(), "user_events");
+        assert_eq!(Identifier::new("_tmp2").to_string(), "_tmp2");
+    }
+
+    #[test]
+    fn quotes_reserved_words_and_special_characters() {
+        assert_eq!(Identifier::new("order").to_string(), "`order`");
+        assert_eq!(Identifier::new("Select").to_string(), "`Select`");
+        assert_eq!(Identifier::new("user-events").to_string(), "`user-events`");
+        assert_eq!(Identifier::new("2024_events").to_string(), "`2024_events`");
+        assert_eq!(Identifier::new("a`b\\c").to_string(), "`a\\`b\\\\c`");
+    }
+
+    #[test]
+    fn quotes_names_spelled_like_upper_case_keywords() {
+        assert_eq!(Identifier::new("TYPE").to_string(), "`TYPE`");
+        assert_eq!(Identifier::new("type").to_string(), "type");
+        assert!(is_keyword("PROJECTION"));
+        assert!(!is_keyword("projection"));
+    }
+
+    #[test]
+    fn quotes_unicode_names() {
+        let name = Identifier::parse("événements").unwrap();
+        assert_eq!(name.to_string(), "`événements`");
+        assert_eq!(name.as_str(), "événements");
+    }
+
+    #[test]
+    fn rejects_invalid_identifiers() {
+        assert!(Identifier::parse("").is_err());
+        assert!(Identifier::parse("bad\nname").is_err());
+        assert!(Identifier::parse(&"x".repeat(256)).is_err());
+        let err = TableName::parse("a.b.c").unwrap_err();
+        assert!(err.to_string().contains("database.table"));
+        assert!(TableName::parse("analytics.").is_err());
+    }
+
+    #[test]
+    fn table_names_carry_a_database() {
+        let name = TableName::parse("analytics.user-events").unwrap();
+        assert_eq!(name.to_string(), "analytics.`user-events`");
+        assert_eq!(
+            name.with_suffix("_kafka").to_string(),
+            "analytics.`user-events_kafka`"
+        );
+        assert_eq!(TableName::new("events").to_string(), "events");
+    }
+
+    #[test]
+    fn event_tables_leave_room_for_suffixes() {
+        let longest = "e".repeat(MAX_IDENTIFIER_BYTES - MAX_SUFFIX_BYTES);
+        let table = TableName::parse_event_table(&longest).unwrap();
+        assert_eq!(
+            table.with_suffix("_person_distinct_id").name.as_str().len(),
+            MAX_IDENTIFIER_BYTES
+        );
+        assert!(TableName::parse(&format!("{longest}e")).is_ok());
+        assert!(TableName::parse_event_table(&format!("{longest}e")).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "longer than 255 bytes")]
+    fn suffixes_never_exceed_the_limit() {
+        TableName::new(&"e".repeat(250)).with_suffix("_sessions_mv");
+    }
+
+    #[test]
+    fn filters_system_tables_by_database() {
+        assert_eq!(
+            TableName::new("events").system_filter("table"),
+            "database = currentDatabase() AND table = 'events'"
+        );
+        assert_eq!(
+            TableName::new("analytics.user's").system_filter("name"),
+            "database = 'analytics' AND name = 'user\\'s'"
+        );
+    }
+
+    #[test]
+    fn create_table_quotes_names() {
+        let table = CreateTable {
+            table_name: TableName::new("analytics.order"),
+            columns: vec![ColumnExpr {
+                name: Identifier::new("group"),
+                data_type: ClickHouseType::String,
+                default: None,
+            }],
+            ..minimal_table()
+        };
+        assert!(
+            table
+                .to_sql()
+                .starts_with("CREATE TABLE analytics.`order` (\n    `group` String")
+        );
+    }
+
+    #[test]
+    fn nullable_sort_keys_need_opt_in() {
+        let mut table = CreateTable {
+            columns: vec![ColumnExpr {
+                name: Identifier::new("email"),
+                data_type: ClickHouseType::low_cardinality(ClickHouseType::String).nullable(),
+                default: None,
+            }],
+            order_by: vec!["email".to_string()],
+            ..minimal_table()
+        };
+        let err = table.validate().unwrap_err().to_string();
+        assert!(err.contains("sort key column email"));
+        assert!(err.contains("LowCardinality(Nullable(String)) needs allow_nullable_key = 1"));
+
+        table
+            .settings
+            .push(("allow_nullable_key".to_string(), "1".to_string()));
+        assert!(table.validate().is_ok());
+    }
+
+    #[test]
+    fn validates_column_types() {
+        let table = CreateTable {
+            columns: vec![ColumnExpr {
+                name: Identifier::new("tags"),
+                data_type: ClickHouseType::Nullable(Box::new(ClickHouseType::low_cardinality(
+                    ClickHouseType::String,
+                ))),
+                default: None,
+            }],
+            ..minimal_table()
+        };
+        let err = table.validate().unwrap_err().to_string();
+        assert!(err.contains("of column tags"));
+        assert!(err.contains("write LowCardinality(Nullable(String)) instead"));
+    }
+
+    fn if_not_exists(statement: &Statement) -> String {
+        let options = RenderOptions {
+            if_not_exists: true,
+            ..RenderOptions::default()
+        };
+        format!("{};", statement.render(&options))
+    }
+
+    #[test]
+    fn if_not_exists_goes_after_the_object_keyword() {
+        let table = Statement::CreateTable(minimal_table());
+        assert!(if_not_exists(&table).starts_with("CREATE TABLE IF NOT EXISTS events ("));
+        let projection = Statement::alter(
+            &TableName::new("events"),
+            AlterCommand::AddProjection {
+                name: Identifier::new("daily"),
+                select: "SELECT count()\nGROUP BY day".to_string(),
+            },
+        );
+        assert_eq!(
+            if_not_exists(&projection),
+            "ALTER TABLE events\nADD PROJECTION IF NOT EXISTS daily\n(\n    SELECT count()\n    \
+             GROUP BY day\n);"
+        );
+        let view = Statement::CreateMaterializedView {
+            name: TableName::new("events_mv"),
+            to: TableName::new("events"),
+            columns: vec![],
+            select: "SELECT * FROM events_queue".to_string(),
+        };
+        assert_eq!(
+            if_not_exists(&view),
+            "CREATE MATERIALIZED VIEW IF NOT EXISTS events_mv TO events AS\nSELECT * FROM events_queue;"
+        );
+        // Commands that only change data have no IF NOT EXISTS form.
+        let ttl = Statement::alter(
+            &TableName::new("events"),
+            AlterCommand::ModifyTtl("ts + INTERVAL 7 DAY".to_string()),
+        );
+        assert_eq!(if_not_exists(&ttl), ttl.to_sql());
+    }
+
+    #[test]
+    fn data_changes_and_drops_are_not_object_definitions() {
+        let table = TableName::new("events");
+        assert!(Statement::CreateTable(minimal_table()).creates());
+        assert!(
+            !Statement::alter(&table, AlterCommand::DropIndex(Identifier::new("idx"))).creates()
+        );
+        assert!(
+            !Statement::Delete {
+                table: table.clone(),
+                condition: "1".to_string()
+            }
+            .creates()
+        );
+        assert!(!Statement::alter(&table, AlterCommand::ApplyDeletedMask).creates());
+    }
+
+    #[test]
+    fn inverses_drop_what_was_created() {
+        let view = Statement::CreateMaterializedView {
+            name: TableName::new("analytics.events_mv"),
+            to: TableName::new("analytics.events"),
+            columns: vec![],
+            select: "SELECT 1".to_string(),
+        };
+        let drop = view.inverse().unwrap();
+        assert_eq!(drop.to_sql(), "DROP VIEW analytics.events_mv;");
+        assert_eq!(
+            if_not_exists(&drop),
+            "DROP VIEW IF EXISTS analytics.events_mv;"
+        );
+        assert!(!drop.creates());
+        assert_eq!(drop.inverse(), None);
+
+        let ttl = AlterCommand::ModifyTtl("ts + INTERVAL 7 DAY".to_string());
+        assert_eq!(
+            Statement::alter(&TableName::new("events"), ttl).inverse(),
+            None
+        );
+    }
+
+    #[test]
+    fn statements_compare_structurally() {
+        let column = |name: &str| ColumnExpr {
+            name: Identifier::new(name),
+            data_type: ClickHouseType::String,
+            default: Some("''".to_string()),
+        };
+        let table = TableName::new("analytics.order");
+        let add = Statement::alter(&table, AlterCommand::AddColumn(column("group")));
+        assert_eq!(
+            add,
+            Statement::alter(&table, AlterCommand::AddColumn(column("group")))
+        );
+        assert_ne!(
+            add,
+            Statement::alter(&table, AlterCommand::ModifyColumn(column("group")))
+        );
+        assert_eq!(
+            add.to_sql(),
+            "ALTER TABLE analytics.`order` ADD COLUMN `group` String DEFAULT '';"
+        );
+    }
 }
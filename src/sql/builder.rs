@@ -1,56 +1,84 @@
 use crate::config::schema::EventSchema;
-use crate::sql::ast::{ColumnExpr, CreateTable};
+use crate::engine::timestamp;
+use crate::sql::ast::{ColumnExpr, CreateTable, Identifier, TableName};
+use crate::sql::types::ClickHouseType;
 
-pub fn build_create_table_sql(
-    schema: &EventSchema,
-    partition_sql: &str,
-    order_by_cols: &[String],
-    ttl: Option<String>,
-) -> CreateTable {
+pub fn columns(schema: &EventSchema) -> Vec<ColumnExpr> {
     let table = &schema.event_table;
+    let timestamp_type = timestamp::column_type(&table.timestamp);
 
-    let columns = vec![
+    let mut columns = vec![
         ColumnExpr {
-            name: "project_id".to_string(),
-            data_type: "UInt32".to_string(),
-            nullable: false,
+            name: Identifier::new("project_id"),
+            data_type: ClickHouseType::UInt(32),
+            default: None,
         },
         ColumnExpr {
-            name: "timestamp".to_string(),
-            data_type: "DateTime".to_string(),
-            nullable: false,
+            name: Identifier::new("timestamp"),
+            data_type: timestamp_type.clone(),
+            default: None,
         },
         ColumnExpr {
-            name: "event_name".to_string(),
-            data_type: "LowCardinality(String)".to_string(),
-            nullable: false,
+            name: Identifier::new("event_name"),
+            data_type: ClickHouseType::low_cardinality(ClickHouseType::String),
+            default: None,
         },
         ColumnExpr {
-            name: "distinct_id".to_string(),
-            data_type: "String".to_string(),
-            nullable: false,
+            name: Identifier::new("distinct_id"),
+            data_type: ClickHouseType::String,
+            default: None,
         },
         ColumnExpr {
-            name: "properties".to_string(),
-            data_type: "JSON".to_string(),
-            nullable: false,
+            name: Identifier::new("properties"),
+            data_type: ClickHouseType::Json,
+            default: None,
         },
     ];
 
+    if schema.sessions.is_some() {
+        columns.insert(
+            4,
+            ColumnExpr {
+                name: Identifier::new("session_id"),
+                data_type: ClickHouseType::String,
+                default: None,
+            },
+        );
+    }
+
+    if table.timestamp.ingested_at {
+        columns.push(ColumnExpr {
+            name: Identifier::new("ingested_at"),
+            data_type: timestamp_type,
+            default: Some(timestamp::now_expr(&table.timestamp)),
+        });
+    }
+    columns
+}
+
+pub fn build_create_table_sql(
+    schema: &EventSchema,
+    partition_sql: &str,
+    order_by_cols: &[String],
+    sample_by: Option<String>,
+    ttl: Option<String>,
+) -> CreateTable {
     CreateTable {
-        table_name: table.name.clone(),
-        columns,
+        table_name: TableName::new(&schema.event_table.name),
+        columns: columns(schema),
         engine: "MergeTree".to_string(),
         partition_by: Some(partition_sql.to_string()),
         order_by: order_by_cols.to_vec(),
+        sample_by,
         ttl,
+        settings: vec![],
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::schema::{EventSchema, EventTable};
+    use crate::config::schema::{EventSchema, EventTable, TimestampConfig};
 
     fn test_schema() -> EventSchema {
         EventSchema {
@@ -59,7 +87,9 @@ mod tests {
                 multi_tenant: true,
                 expected_events_per_day: 50_000_000,
                 retention_days: 90,
+                ..Default::default()
             },
+            ..Default::default()
         }
     }
 
@@ -76,6 +106,7 @@ mod tests {
             &schema,
             "toYYYYMMDD(timestamp)",
             &order_cols,
+            None,
             Some("timestamp + INTERVAL 90 DAY".to_string()),
         );
         let sql = ast.to_sql();
@@ -90,6 +121,22 @@ mod tests {
         assert!(sql.contains("PARTITION BY toYYYYMMDD(timestamp)"));
         assert!(sql.contains("ORDER BY (project_id, event_name, timestamp, distinct_id)"));
         assert!(sql.contains("TTL timestamp + INTERVAL 90 DAY"));
+        assert!(!sql.contains("SAMPLE BY"));
+    }
+
+    #[test]
+    fn renders_sample_by_when_given() {
+        let schema = test_schema();
+        let ast = build_create_table_sql(
+            &schema,
+            "toYYYYMMDD(timestamp)",
+            &["event_name".to_string(), "cityHash64(distinct_id)".to_string()],
+            Some("cityHash64(distinct_id)".to_string()),
+            None,
+        );
+        let sql = ast.to_sql();
+        assert!(sql.contains("ORDER BY (event_name, cityHash64(distinct_id))"));
+        assert!(sql.contains("SAMPLE BY cityHash64(distinct_id)"));
     }
 
     #[test]
@@ -102,6 +149,7 @@ mod tests {
             &schema,
             "toYYYYMM(timestamp)",
             &["timestamp".to_string()],
+            None,
             Some("timestamp + INTERVAL 30 DAY".to_string()),
         );
         let sql = ast.to_sql();
@@ -118,6 +166,7 @@ mod tests {
             "toYYYYMMDD(timestamp)",
             &["timestamp".to_string()],
             None,
+            None,
         );
         let sql = ast.to_sql();
         assert!(!sql.contains("TTL"));
@@ -131,10 +180,11 @@ mod tests {
             "toYYYYMMDD(timestamp)",
             &["timestamp".to_string()],
             None,
+            None,
         );
         assert_eq!(ast.columns.len(), 5);
-        assert_eq!(ast.columns[0].name, "project_id");
-        assert_eq!(ast.columns[4].name, "properties");
+        assert_eq!(ast.columns[0].name.as_str(), "project_id");
+        assert_eq!(ast.columns[4].name.as_str(), "properties");
     }
 
     #[test]
@@ -145,7 +195,47 @@ mod tests {
             "toYYYYMMDD(timestamp)",
             &["timestamp".to_string()],
             None,
+            None,
         );
         assert_eq!(ast.engine, "MergeTree");
     }
+
+    #[test]
+    fn timestamp_type_follows_precision_and_timezone() {
+        let mut schema = test_schema();
+        schema.event_table.timestamp = TimestampConfig {
+            precision: 3,
+            timezone: Some("UTC".to_string()),
+            ingested_at: false,
+        };
+        let ast = build_create_table_sql(
+            &schema,
+            "toYYYYMMDD(timestamp)",
+            &["timestamp".to_string()],
+            None,
+            None,
+        );
+        let sql = ast.to_sql();
+        assert!(sql.contains("timestamp DateTime64(3, 'UTC')"));
+        assert!(!sql.contains("ingested_at"));
+    }
+
+    #[test]
+    fn ingested_at_column_appended_with_default() {
+        let mut schema = test_schema();
+        schema.event_table.timestamp = TimestampConfig {
+            precision: 6,
+            timezone: None,
+            ingested_at: true,
+        };
+        let ast = build_create_table_sql(
+            &schema,
+            "toYYYYMMDD(timestamp)",
+            &["timestamp".to_string()],
+            None,
+            None,
+        );
+        assert_eq!(ast.columns.len(), 6);
+        assert!(ast.to_sql().contains("ingested_at DateTime64(6) DEFAULT now64(6)"));
+    }
 }
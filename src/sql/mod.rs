@@ -1,2 +1,4 @@
 pub mod ast;
 pub mod builder;
+pub mod query;
+pub mod types;
@@ -0,0 +1,509 @@
+use crate::errors::SharpError;
+use crate::sql::ast::{Identifier, TableName};
+
+// A deliberately small SELECT reader: enough structure to see which table a
+// query reads, what it filters on and how it groups, without a full grammar.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Word,
+    Number,
+    Str,
+    // ClickHouse query parameter, e.g. {from:DateTime}
+    Param,
+    Symbol,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+impl Token {
+    pub fn is_word(&self, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Source {
+    Table(TableName),
+    Subquery(Box<SelectQuery>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SelectQuery {
+    pub source: Source,
+    pub alias: Option<String>,
+    pub select: Vec<Token>,
+    pub sample: bool,
+    // Top-level AND-ed conditions from PREWHERE and WHERE.
+    pub predicates: Vec<Vec<Token>>,
+    pub group_by: Vec<Vec<Token>>,
+}
+
+// Keywords that end a clause at nesting depth 0.
+const CLAUSES: &[&str] = &[
+    "SAMPLE", "PREWHERE", "WHERE", "GROUP", "HAVING", "ORDER", "LIMIT", "SETTINGS", "FORMAT",
+    "UNION", "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "ARRAY", "GLOBAL", "ANY", "ALL",
+    "FINAL", "WITH",
+];
+
+fn invalid(message: impl Into<String>) -> SharpError {
+    SharpError::InvalidQuery(message.into())
+}
+
+pub fn tokenize(sql: &str) -> Result<Vec<Token>, SharpError> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let push = |tokens: &mut Vec<Token>, kind, text: String| tokens.push(Token { kind, text });
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '-' && chars.get(i + 1) == Some(&'-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '\'' || c == '`' || c == '"' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' {
+                    i += 1;
+                }
+                i += 1;
+                // A doubled quote is an escaped quote.
+                if i + 1 < chars.len() && chars[i] == c && chars[i + 1] == c {
+                    i += 2;
+                }
+            }
+            if i >= chars.len() {
+                return Err(invalid(format!("unterminated {c} quote")));
+            }
+            i += 1;
+            if c == '\'' {
+                push(
+                    &mut tokens,
+                    TokenKind::Str,
+                    chars[start..i].iter().collect(),
+                );
+            } else {
+                // Quoted identifiers compare by their unquoted name.
+                push(
+                    &mut tokens,
+                    TokenKind::Word,
+                    chars[start + 1..i - 1].iter().collect(),
+                );
+            }
+        } else if c == '{' {
+            let start = i;
+            while i < chars.len() && chars[i] != '}' {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(invalid("unterminated query parameter"));
+            }
+            i += 1;
+            push(
+                &mut tokens,
+                TokenKind::Param,
+                chars[start..i].iter().collect(),
+            );
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            push(
+                &mut tokens,
+                TokenKind::Number,
+                chars[start..i].iter().collect(),
+            );
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            push(
+                &mut tokens,
+                TokenKind::Word,
+                chars[start..i].iter().collect(),
+            );
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let symbol = if ["<=", ">=", "!=", "<>", "==", "||", "->"].contains(&two.as_str()) {
+                two
+            } else {
+                c.to_string()
+            };
+            i += symbol.len();
+            push(&mut tokens, TokenKind::Symbol, symbol);
+        }
+    }
+    Ok(tokens)
+}
+
+pub fn is_function_name(token: &Token) -> bool {
+    token.kind == TokenKind::Word
+        && !["IN", "AND", "OR", "NOT", "AS", "FROM", "WHERE", "BETWEEN"]
+            .iter()
+            .any(|kw| token.is_word(kw))
+}
+
+// Re-assembles tokens into readable SQL for messages.
+pub fn render(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let glue = i == 0
+            || token.is_symbol(")")
+            || token.is_symbol(",")
+            || token.is_symbol(".")
+            || token.is_symbol("[")
+            || token.is_symbol("]")
+            || (token.is_symbol("(") && is_function_name(&tokens[i - 1]))
+            || tokens[i - 1].is_symbol("(")
+            || tokens[i - 1].is_symbol(".")
+            || tokens[i - 1].is_symbol("[");
+        if !glue {
+            out.push(' ');
+        }
+        if token.kind == TokenKind::Word
+            && token
+                .text
+                .contains(|c: char| !c.is_alphanumeric() && c != '_')
+        {
+            out.push_str(&format!("`{}`", token.text));
+        } else {
+            out.push_str(&token.text);
+        }
+    }
+    out
+}
+
+fn is_clause(token: &Token) -> bool {
+    token.kind == TokenKind::Word && CLAUSES.iter().any(|kw| token.is_word(kw))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_word(&self, word: &str) -> bool {
+        self.peek().is_some_and(|t| t.is_word(word))
+    }
+
+    fn at_end(&self) -> bool {
+        self.peek()
+            .is_none_or(|t| t.is_symbol(")") || t.is_symbol(";"))
+    }
+
+    // Consumes tokens up to (not including) the next depth-0 token matching
+    // `stop`, or the end of the current query.
+    fn take_until(&mut self, stop: impl Fn(&Token) -> bool) -> Vec<Token> {
+        let mut depth = 0usize;
+        let mut taken = Vec::new();
+        while let Some(token) = self.peek() {
+            if depth == 0 && (stop(token) || token.is_symbol(")") || token.is_symbol(";")) {
+                break;
+            }
+            if token.is_symbol("(") || token.is_symbol("[") {
+                depth += 1;
+            } else if token.is_symbol(")") || token.is_symbol("]") {
+                depth = depth.saturating_sub(1);
+            }
+            taken.push(token.clone());
+            self.pos += 1;
+        }
+        taken
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), SharpError> {
+        if self.peek_word(word) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            let found = self
+                .peek()
+                .map_or("end of query".to_string(), |t| format!("'{}'", t.text));
+            Err(invalid(format!("expected {word}, found {found}")))
+        }
+    }
+
+    fn select(&mut self) -> Result<SelectQuery, SharpError> {
+        if self.peek_word("WITH") {
+            self.pos += 1;
+            self.take_until(|t| t.is_word("SELECT"));
+        }
+        self.expect_word("SELECT")?;
+        let select = self.take_until(|t| t.is_word("FROM"));
+        self.expect_word("FROM")?;
+
+        let source = if self.peek().is_some_and(|t| t.is_symbol("(")) {
+            self.pos += 1;
+            let inner = self.select()?;
+            if !self.peek().is_some_and(|t| t.is_symbol(")")) {
+                return Err(invalid("unclosed subquery"));
+            }
+            self.pos += 1;
+            Source::Subquery(Box::new(inner))
+        } else {
+            let mut name = match self.peek() {
+                Some(t) if t.kind == TokenKind::Word => TableName {
+                    database: None,
+                    name: Identifier::new(&t.text),
+                },
+                _ => return Err(invalid("expected a table name after FROM")),
+            };
+            self.pos += 1;
+            if self.peek().is_some_and(|t| t.is_symbol(".")) {
+                self.pos += 1;
+                let table = self.peek().map(|t| t.text.clone()).unwrap_or_default();
+                name = TableName {
+                    database: Some(name.name),
+                    name: Identifier::new(table),
+                };
+                self.pos += 1;
+            }
+            Source::Table(name)
+        };
+
+        if self.peek_word("AS") {
+            self.pos += 1;
+        }
+        let alias = match self.peek() {
+            Some(t) if t.kind == TokenKind::Word && !is_clause(t) => {
+                self.pos += 1;
+                Some(self.tokens[self.pos - 1].text.clone())
+            }
+            _ => None,
+        };
+
+        let mut query = SelectQuery {
+            source,
+            alias,
+            select,
+            sample: false,
+            predicates: vec![],
+            group_by: vec![],
+        };
+
+        while !self.at_end() {
+            let token = self.peek().cloned().unwrap();
+            self.pos += 1;
+            if token.is_word("SAMPLE") {
+                query.sample = true;
+            } else if token.is_word("WHERE") || token.is_word("PREWHERE") {
+                let condition = self.take_until(is_clause);
+                if condition.is_empty() {
+                    return Err(invalid(format!("{} without a condition", token.text)));
+                }
+                query.predicates.extend(split_and(&condition));
+            } else if token.is_word("UNION") {
+                return Err(invalid(
+                    "UNION is not supported; check each SELECT on its own",
+                ));
+            } else if token.is_word("GROUP") {
+                self.expect_word("BY")?;
+                let group = self.take_until(is_clause);
+                query.group_by = split_top_level(&group, |t| t.is_symbol(","));
+            } else {
+                // Clauses we don't analyse (ORDER BY, LIMIT, JOINs, ...).
+                self.take_until(is_clause);
+            }
+        }
+        Ok(query)
+    }
+}
+
+pub fn split_top_level(tokens: &[Token], at: impl Fn(&Token) -> bool) -> Vec<Vec<Token>> {
+    let mut parts = vec![vec![]];
+    let mut depth = 0usize;
+    for token in tokens {
+        if token.is_symbol("(") || token.is_symbol("[") {
+            depth += 1;
+        } else if token.is_symbol(")") || token.is_symbol("]") {
+            depth = depth.saturating_sub(1);
+        }
+        if depth == 0 && at(token) {
+            parts.push(vec![]);
+        } else {
+            parts.last_mut().unwrap().push(token.clone());
+        }
+    }
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+// Splits on depth-0 AND, except the AND that belongs to BETWEEN.
+fn split_and(tokens: &[Token]) -> Vec<Vec<Token>> {
+    let mut in_between = false;
+    let mut parts = vec![vec![]];
+    let mut depth = 0usize;
+    for token in tokens {
+        if token.is_symbol("(") || token.is_symbol("[") {
+            depth += 1;
+        } else if token.is_symbol(")") || token.is_symbol("]") {
+            depth = depth.saturating_sub(1);
+        }
+        if depth == 0 && token.is_word("BETWEEN") {
+            in_between = true;
+        }
+        if depth == 0 && token.is_word("AND") {
+            if in_between {
+                in_between = false;
+            } else {
+                parts.push(vec![]);
+                continue;
+            }
+        }
+        parts.last_mut().unwrap().push(token.clone());
+    }
+    // Unwrap a condition written as `(a = 1)`.
+    parts
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .flat_map(|p| {
+            let wrapped = p.first().is_some_and(|t| t.is_symbol("(")) && closes_at_end(&p);
+            if wrapped {
+                split_and(&p[1..p.len() - 1])
+            } else {
+                vec![p]
+            }
+        })
+        .collect()
+}
+
+fn closes_at_end(tokens: &[Token]) -> bool {
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate() {
+        if token.is_symbol("(") {
+            depth += 1;
+        } else if token.is_symbol(")") {
+            depth -= 1;
+            if depth == 0 {
+                return i == tokens.len() - 1;
+            }
+        }
+    }
+    false
+}
+
+pub fn parse_select(sql: &str) -> Result<SelectQuery, SharpError> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        pos: 0,
+    };
+    let query = parser.select()?;
+    if parser.peek().is_some_and(|t| t.is_symbol(";")) {
+        parser.pos += 1;
+    }
+    if let Some(extra) = parser.peek() {
+        return Err(invalid(format!(
+            "unexpected '{}' after the query",
+            extra.text
+        )));
+    }
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(parts: &[Vec<Token>]) -> Vec<String> {
+        parts.iter().map(|p| render(p)).collect()
+    }
+
+    #[test]
+    fn tokenizes_params_strings_and_operators() {
+        let tokens = tokenize("timestamp >= {from:DateTime} AND name != 'it''s'").unwrap();
+        assert_eq!(tokens[1].text, ">=");
+        assert_eq!(tokens[2].kind, TokenKind::Param);
+        assert_eq!(tokens[5].text, "!=");
+        assert_eq!(tokens[6].kind, TokenKind::Str);
+        assert_eq!(tokens[6].text, "'it''s'");
+    }
+
+    #[test]
+    fn parses_where_and_group_by() {
+        let q = parse_select(
+            "SELECT toDate(timestamp) AS day, count() FROM events e \
+             WHERE project_id = 1 AND timestamp BETWEEN {a:DateTime} AND {b:DateTime} \
+             GROUP BY day ORDER BY day LIMIT 10;",
+        )
+        .unwrap();
+        assert_eq!(q.source, Source::Table(TableName::new("events")));
+        assert_eq!(q.alias.as_deref(), Some("e"));
+        assert_eq!(
+            texts(&q.predicates),
+            [
+                "project_id = 1",
+                "timestamp BETWEEN {a:DateTime} AND {b:DateTime}"
+            ]
+        );
+        assert_eq!(texts(&q.group_by), ["day"]);
+    }
+
+    #[test]
+    fn parses_subquery_source() {
+        let q = parse_select(
+            "SELECT level, count() FROM (SELECT distinct_id, windowFunnel(3600)(timestamp, \
+             event_name = 'a') AS level FROM analytics.events WHERE (event_name IN ('a') AND \
+             project_id = 2) GROUP BY distinct_id) GROUP BY level",
+        )
+        .unwrap();
+        let Source::Subquery(inner) = q.source else {
+            panic!("expected subquery");
+        };
+        assert_eq!(
+            inner.source,
+            Source::Table(TableName::new("analytics.events"))
+        );
+        assert_eq!(
+            texts(&inner.predicates),
+            ["event_name IN ('a')", "project_id = 2"]
+        );
+    }
+
+    #[test]
+    fn notes_sample_clause() {
+        let q = parse_select("SELECT count() FROM events SAMPLE 0.1 WHERE x = 1").unwrap();
+        assert!(q.sample);
+    }
+
+    #[test]
+    fn rejects_union_and_empty_where() {
+        let err = parse_select("SELECT 1 FROM events UNION ALL SELECT 2 FROM events").unwrap_err();
+        assert!(err.to_string().contains("UNION is not supported"));
+        let err = parse_select("SELECT 1 FROM events WHERE GROUP BY x").unwrap_err();
+        assert!(err.to_string().contains("WHERE without a condition"));
+        assert!(parse_select("SELECT 1 FROM events WHERE").is_err());
+    }
+
+    #[test]
+    fn rejects_non_select() {
+        let err = parse_select("DELETE FROM events WHERE 1").unwrap_err();
+        assert!(err.to_string().contains("expected SELECT"));
+    }
+}
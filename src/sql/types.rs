@@ -0,0 +1,657 @@
+use crate::errors::SharpError;
+use crate::sql::ast::string_literal;
+use serde::Deserialize;
+use std::fmt;
+
+const INTEGER_BITS: &[u16] = &[8, 16, 32, 64, 128, 256];
+const MAX_DECIMAL_PRECISION: u8 = 76;
+const MAX_SUBSECOND_PRECISION: u8 = 9;
+
+// A column type as ClickHouse spells it. `parse` accepts the server's own
+// output (system.columns) and `Display` renders it back the same way, so
+// parsed types compare structurally instead of by string.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ClickHouseType {
+    UInt(u16),
+    Int(u16),
+    Float32,
+    Float64,
+    Bool,
+    Decimal {
+        precision: u8,
+        scale: u8,
+    },
+    String,
+    FixedString(u32),
+    Uuid,
+    Ipv4,
+    Ipv6,
+    Json,
+    Date,
+    Date32,
+    DateTime(Option<String>),
+    DateTime64 {
+        precision: u8,
+        timezone: Option<String>,
+    },
+    Enum8(Vec<(String, i16)>),
+    Enum16(Vec<(String, i16)>),
+    LowCardinality(Box<ClickHouseType>),
+    Nullable(Box<ClickHouseType>),
+    Array(Box<ClickHouseType>),
+    Map(Box<ClickHouseType>, Box<ClickHouseType>),
+    // Elements are optionally named: Tuple(a UInt8, b String).
+    Tuple(Vec<(Option<String>, ClickHouseType)>),
+    // The function is kept as written, parameters included: quantiles(0.5, 0.9).
+    AggregateFunction(String, Vec<ClickHouseType>),
+    SimpleAggregateFunction(String, Box<ClickHouseType>),
+}
+
+fn invalid(message: String) -> SharpError {
+    SharpError::InvalidType(message)
+}
+
+impl ClickHouseType {
+    pub fn parse(input: &str) -> Result<Self, SharpError> {
+        let mut parser = Parser { input, pos: 0 };
+        let parsed = parser.parse_type()?;
+        parser.skip_whitespace();
+        if parser.pos != input.len() {
+            return Err(parser.error("unexpected trailing input"));
+        }
+        Ok(parsed)
+    }
+
+    pub fn low_cardinality(inner: ClickHouseType) -> Self {
+        Self::LowCardinality(Box::new(inner))
+    }
+
+    // Nullable goes inside LowCardinality, never around it.
+    pub fn nullable(self) -> Self {
+        match self {
+            Self::Nullable(_) => self,
+            Self::LowCardinality(inner) => Self::LowCardinality(Box::new(inner.nullable())),
+            other => Self::Nullable(Box::new(other)),
+        }
+    }
+
+    pub fn is_nullable(&self) -> bool {
+        match self {
+            Self::Nullable(_) => true,
+            Self::LowCardinality(inner) => inner.is_nullable(),
+            _ => false,
+        }
+    }
+
+    fn is_scalar(&self) -> bool {
+        !matches!(
+            self,
+            Self::Json
+                | Self::LowCardinality(_)
+                | Self::Nullable(_)
+                | Self::Array(_)
+                | Self::Map(..)
+                | Self::Tuple(_)
+                | Self::AggregateFunction(..)
+                | Self::SimpleAggregateFunction(..)
+        )
+    }
+
+    // Combinations the server would reject at CREATE time.
+    pub fn validate(&self) -> Result<(), SharpError> {
+        match self {
+            Self::UInt(bits) | Self::Int(bits) if !INTEGER_BITS.contains(bits) => {
+                Err(invalid(format!("{self}: no integer type has {bits} bits")))
+            }
+            Self::Decimal { precision, scale }
+                if *precision == 0 || *precision > MAX_DECIMAL_PRECISION || scale > precision =>
+            {
+                Err(invalid(format!(
+                    "{self}: precision must be 1 to {MAX_DECIMAL_PRECISION} and scale at most \
+                     the precision"
+                )))
+            }
+            Self::FixedString(0) => Err(invalid(format!("{self}: length must be at least 1"))),
+            Self::DateTime64 { precision, .. } if *precision > MAX_SUBSECOND_PRECISION => {
+                Err(invalid(format!(
+                    "{self}: precision must be 0 to {MAX_SUBSECOND_PRECISION}"
+                )))
+            }
+            Self::Enum8(values) => {
+                validate_enum(self, values, i16::from(i8::MIN), i16::from(i8::MAX))
+            }
+            Self::Enum16(values) => validate_enum(self, values, i16::MIN, i16::MAX),
+            Self::Nullable(inner) => match inner.as_ref() {
+                Self::LowCardinality(lc) => Err(invalid(format!(
+                    "{self}: write {} instead",
+                    Self::low_cardinality(lc.as_ref().clone().nullable())
+                ))),
+                inner if !inner.is_scalar() => {
+                    Err(invalid(format!("{self}: {inner} can't be Nullable")))
+                }
+                inner => inner.validate(),
+            },
+            Self::LowCardinality(inner) => {
+                let base = match inner.as_ref() {
+                    Self::Nullable(base) => base.as_ref(),
+                    base => base,
+                };
+                if !base.is_scalar() || matches!(base, Self::Decimal { .. } | Self::Bool) {
+                    return Err(invalid(format!(
+                        "{self}: LowCardinality only wraps strings, numbers and dates"
+                    )));
+                }
+                inner.validate()
+            }
+            Self::Array(inner) => inner.validate(),
+            Self::Map(key, value) => {
+                let key_ok = matches!(
+                    key.as_ref(),
+                    Self::UInt(_)
+                        | Self::Int(_)
+                        | Self::String
+                        | Self::FixedString(_)
+                        | Self::Uuid
+                        | Self::Date
+                        | Self::Date32
+                        | Self::DateTime(_)
+                        | Self::Enum8(_)
+                        | Self::Enum16(_)
+                        | Self::LowCardinality(_)
+                ) && !key.is_nullable();
+                if !key_ok {
+                    return Err(invalid(format!("{self}: {key} can't be a Map key")));
+                }
+                key.validate()?;
+                value.validate()
+            }
+            Self::Tuple(elements) if elements.is_empty() => {
+                Err(invalid("Tuple(): needs at least one element".to_string()))
+            }
+            Self::Tuple(elements) => elements.iter().try_for_each(|(_, t)| t.validate()),
+            Self::AggregateFunction(_, arguments) => arguments.iter().try_for_each(Self::validate),
+            Self::SimpleAggregateFunction(_, inner) => inner.validate(),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn validate_enum(
+    t: &ClickHouseType,
+    values: &[(String, i16)],
+    min: i16,
+    max: i16,
+) -> Result<(), SharpError> {
+    if values.is_empty() {
+        return Err(invalid(format!("{t}: needs at least one value")));
+    }
+    for (i, (name, value)) in values.iter().enumerate() {
+        if !(min..=max).contains(value) {
+            return Err(invalid(format!("{t}: {value} is outside {min}..={max}")));
+        }
+        if values[..i].iter().any(|(n, v)| n == name || v == value) {
+            return Err(invalid(format!("{t}: '{name}' = {value} is not unique")));
+        }
+    }
+    Ok(())
+}
+
+impl TryFrom<String> for ClickHouseType {
+    type Error = SharpError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+fn join<T: fmt::Display>(items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn enum_values(values: &[(String, i16)]) -> String {
+    join(
+        values
+            .iter()
+            .map(|(name, value)| format!("{} = {value}", string_literal(name))),
+    )
+}
+
+impl fmt::Display for ClickHouseType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UInt(bits) => write!(f, "UInt{bits}"),
+            Self::Int(bits) => write!(f, "Int{bits}"),
+            Self::Float32 => f.write_str("Float32"),
+            Self::Float64 => f.write_str("Float64"),
+            Self::Bool => f.write_str("Bool"),
+            Self::Decimal { precision, scale } => write!(f, "Decimal({precision}, {scale})"),
+            Self::String => f.write_str("String"),
+            Self::FixedString(n) => write!(f, "FixedString({n})"),
+            Self::Uuid => f.write_str("UUID"),
+            Self::Ipv4 => f.write_str("IPv4"),
+            Self::Ipv6 => f.write_str("IPv6"),
+            Self::Json => f.write_str("JSON"),
+            Self::Date => f.write_str("Date"),
+            Self::Date32 => f.write_str("Date32"),
+            Self::DateTime(None) => f.write_str("DateTime"),
+            Self::DateTime(Some(tz)) => write!(f, "DateTime({})", string_literal(tz)),
+            Self::DateTime64 {
+                precision,
+                timezone: None,
+            } => write!(f, "DateTime64({precision})"),
+            Self::DateTime64 {
+                precision,
+                timezone: Some(tz),
+            } => write!(f, "DateTime64({precision}, {})", string_literal(tz)),
+            Self::Enum8(values) => write!(f, "Enum8({})", enum_values(values)),
+            Self::Enum16(values) => write!(f, "Enum16({})", enum_values(values)),
+            Self::LowCardinality(inner) => write!(f, "LowCardinality({inner})"),
+            Self::Nullable(inner) => write!(f, "Nullable({inner})"),
+            Self::Array(inner) => write!(f, "Array({inner})"),
+            Self::Map(key, value) => write!(f, "Map({key}, {value})"),
+            Self::Tuple(elements) => write!(
+                f,
+                "Tuple({})",
+                join(elements.iter().map(|(name, t)| match name {
+                    Some(name) => format!("{name} {t}"),
+                    None => t.to_string(),
+                }))
+            ),
+            Self::AggregateFunction(function, arguments) => {
+                write!(f, "AggregateFunction({function}, {})", join(arguments))
+            }
+            Self::SimpleAggregateFunction(function, inner) => {
+                write!(f, "SimpleAggregateFunction({function}, {inner})")
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> SharpError {
+        invalid(format!("{:?} at byte {}: {message}", self.input, self.pos))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.input.len() - self.rest().trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SharpError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    fn word(&mut self) -> Result<&'a str, SharpError> {
+        self.skip_whitespace();
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let start = self.pos;
+        self.pos += len;
+        Ok(&self.input[start..self.pos])
+    }
+
+    fn integer<T: std::str::FromStr>(&mut self) -> Result<T, SharpError> {
+        self.skip_whitespace();
+        let sign = usize::from(self.rest().starts_with('-'));
+        let len = sign
+            + self.rest()[sign..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(self.rest().len() - sign);
+        let text = &self.rest()[..len];
+        let value = text
+            .parse()
+            .map_err(|_| self.error("expected a number in range"))?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, SharpError> {
+        self.expect('\'')?;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\'' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => out.push(escaped),
+                    None => break,
+                },
+                c => out.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    // Raw text up to the next top-level comma, for aggregate function names.
+    fn function(&mut self) -> Result<String, SharpError> {
+        self.skip_whitespace();
+        let mut depth = 0;
+        let end = self
+            .rest()
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' if depth > 0 => depth -= 1,
+                    ',' | ')' if depth == 0 => return true,
+                    _ => {}
+                }
+                false
+            })
+            .map(|(i, _)| i)
+            .ok_or_else(|| self.error("expected an aggregate function"))?;
+        let function = self.rest()[..end].trim().to_string();
+        if function.is_empty() {
+            return Err(self.error("expected an aggregate function"));
+        }
+        self.pos += end;
+        Ok(function)
+    }
+
+    fn inner(&mut self) -> Result<Box<ClickHouseType>, SharpError> {
+        self.expect('(')?;
+        let inner = self.parse_type()?;
+        self.expect(')')?;
+        Ok(Box::new(inner))
+    }
+
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, SharpError>,
+    ) -> Result<Vec<T>, SharpError> {
+        let mut items = vec![item(self)?];
+        while self.eat(',') {
+            items.push(item(self)?);
+        }
+        self.expect(')')?;
+        Ok(items)
+    }
+
+    fn timezone(&mut self) -> Result<Option<String>, SharpError> {
+        if self.eat(',') {
+            Ok(Some(self.string()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn enum_values(&mut self) -> Result<Vec<(String, i16)>, SharpError> {
+        self.expect('(')?;
+        self.list(|p| {
+            let name = p.string()?;
+            p.expect('=')?;
+            Ok((name, p.integer()?))
+        })
+    }
+
+    fn tuple_element(&mut self) -> Result<(Option<String>, ClickHouseType), SharpError> {
+        // `name Type` when a second word follows the first before any '('.
+        let start = self.pos;
+        let first = self.word()?.to_string();
+        self.skip_whitespace();
+        if self
+            .rest()
+            .starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        {
+            return Ok((Some(first), self.parse_type()?));
+        }
+        self.pos = start;
+        Ok((None, self.parse_type()?))
+    }
+
+    fn parse_type(&mut self) -> Result<ClickHouseType, SharpError> {
+        let start = self.pos;
+        let name = self.word()?;
+        let integer = |prefix: &str| {
+            name.strip_prefix(prefix)
+                .and_then(|bits| bits.parse::<u16>().ok())
+                .filter(|bits| INTEGER_BITS.contains(bits))
+        };
+        if let Some(bits) = integer("UInt") {
+            return Ok(ClickHouseType::UInt(bits));
+        }
+        if let Some(bits) = integer("Int") {
+            return Ok(ClickHouseType::Int(bits));
+        }
+        Ok(match name {
+            "Float32" => ClickHouseType::Float32,
+            "Float64" => ClickHouseType::Float64,
+            "Bool" | "Boolean" => ClickHouseType::Bool,
+            "String" => ClickHouseType::String,
+            "UUID" => ClickHouseType::Uuid,
+            "IPv4" => ClickHouseType::Ipv4,
+            "IPv6" => ClickHouseType::Ipv6,
+            "JSON" => ClickHouseType::Json,
+            "Date" => ClickHouseType::Date,
+            "Date32" => ClickHouseType::Date32,
+            "FixedString" => {
+                self.expect('(')?;
+                let n = self.integer()?;
+                self.expect(')')?;
+                ClickHouseType::FixedString(n)
+            }
+            "Decimal" => {
+                self.expect('(')?;
+                let precision = self.integer()?;
+                let scale = if self.eat(',') { self.integer()? } else { 0 };
+                self.expect(')')?;
+                ClickHouseType::Decimal { precision, scale }
+            }
+            "Decimal32" | "Decimal64" | "Decimal128" | "Decimal256" => {
+                let precision = match name {
+                    "Decimal32" => 9,
+                    "Decimal64" => 18,
+                    "Decimal128" => 38,
+                    _ => MAX_DECIMAL_PRECISION,
+                };
+                self.expect('(')?;
+                let scale = self.integer()?;
+                self.expect(')')?;
+                ClickHouseType::Decimal { precision, scale }
+            }
+            "DateTime" => {
+                if self.eat('(') {
+                    let tz = self.string()?;
+                    self.expect(')')?;
+                    ClickHouseType::DateTime(Some(tz))
+                } else {
+                    ClickHouseType::DateTime(None)
+                }
+            }
+            "DateTime64" => {
+                self.expect('(')?;
+                let precision = self.integer()?;
+                let timezone = self.timezone()?;
+                self.expect(')')?;
+                ClickHouseType::DateTime64 {
+                    precision,
+                    timezone,
+                }
+            }
+            "Enum8" => ClickHouseType::Enum8(self.enum_values()?),
+            "Enum16" => ClickHouseType::Enum16(self.enum_values()?),
+            "LowCardinality" => ClickHouseType::LowCardinality(self.inner()?),
+            "Nullable" => ClickHouseType::Nullable(self.inner()?),
+            "Array" => ClickHouseType::Array(self.inner()?),
+            "Map" => {
+                self.expect('(')?;
+                let key = self.parse_type()?;
+                self.expect(',')?;
+                let value = self.parse_type()?;
+                self.expect(')')?;
+                ClickHouseType::Map(Box::new(key), Box::new(value))
+            }
+            "Tuple" => {
+                self.expect('(')?;
+                ClickHouseType::Tuple(self.list(Self::tuple_element)?)
+            }
+            "AggregateFunction" => {
+                self.expect('(')?;
+                let function = self.function()?;
+                let mut arguments = Vec::new();
+                while self.eat(',') {
+                    arguments.push(self.parse_type()?);
+                }
+                self.expect(')')?;
+                ClickHouseType::AggregateFunction(function, arguments)
+            }
+            "SimpleAggregateFunction" => {
+                self.expect('(')?;
+                let function = self.function()?;
+                self.expect(',')?;
+                let inner = self.parse_type()?;
+                self.expect(')')?;
+                ClickHouseType::SimpleAggregateFunction(function, Box::new(inner))
+            }
+            _ => {
+                self.pos = start;
+                return Err(self.error("unknown type"));
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(input: &str) {
+        let parsed = ClickHouseType::parse(input).unwrap();
+        assert_eq!(parsed.to_string(), input);
+        assert!(parsed.validate().is_ok(), "{input} should be valid");
+    }
+
+    #[test]
+    fn round_trips_canonical_types() {
+        for input in [
+            "UInt8",
+            "Int256",
+            "Float64",
+            "Bool",
+            "Decimal(18, 4)",
+            "FixedString(16)",
+            "UUID",
+            "IPv6",
+            "JSON",
+            "Date32",
+            "DateTime",
+            "DateTime('UTC')",
+            "DateTime64(3)",
+            "DateTime64(6, 'Europe/Berlin')",
+            "Enum8('a' = 1, 'it\\'s' = -2)",
+            "LowCardinality(Nullable(String))",
+            "Array(Nullable(UInt32))",
+            "Map(LowCardinality(String), Array(Float32))",
+            "Tuple(a UInt8, b Tuple(String, Date))",
+            "AggregateFunction(argMin, String, DateTime)",
+            "AggregateFunction(quantiles(0.5, 0.9), Float64)",
+            "SimpleAggregateFunction(max, DateTime64(3, 'UTC'))",
+        ] {
+            round_trip(input);
+        }
+    }
+
+    #[test]
+    fn normalizes_spelling() {
+        let canonical = |input: &str| ClickHouseType::parse(input).unwrap().to_string();
+        assert_eq!(canonical("Decimal64(4)"), "Decimal(18, 4)");
+        assert_eq!(canonical("Decimal(10)"), "Decimal(10, 0)");
+        assert_eq!(canonical("Boolean"), "Bool");
+        assert_eq!(canonical(" Map( String ,UInt64 ) "), "Map(String, UInt64)");
+        assert_eq!(canonical("DateTime64(3,'UTC')"), "DateTime64(3, 'UTC')");
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for input in [
+            "",
+            "UInt7",
+            "Strin",
+            "Nullable(String",
+            "Array(String))",
+            "Map(String)",
+            "DateTime('UTC)",
+            "Enum8('a')",
+        ] {
+            assert!(
+                ClickHouseType::parse(input).is_err(),
+                "{input} should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn nullable_goes_inside_low_cardinality() {
+        let lc = ClickHouseType::low_cardinality(ClickHouseType::String);
+        assert_eq!(
+            lc.clone().nullable().to_string(),
+            "LowCardinality(Nullable(String))"
+        );
+        assert!(lc.clone().nullable().is_nullable());
+        assert!(!lc.is_nullable());
+        assert_eq!(
+            ClickHouseType::String.nullable().nullable(),
+            ClickHouseType::String.nullable()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_combinations() {
+        let error = |input: &str| {
+            ClickHouseType::parse(input)
+                .unwrap()
+                .validate()
+                .unwrap_err()
+                .to_string()
+        };
+        assert!(
+            error("Nullable(LowCardinality(String))")
+                .contains("write LowCardinality(Nullable(String)) instead")
+        );
+        assert!(error("Nullable(Array(String))").contains("can't be Nullable"));
+        assert!(error("Nullable(Nullable(String))").contains("can't be Nullable"));
+        assert!(error("LowCardinality(Array(String))").contains("only wraps"));
+        assert!(error("Map(Nullable(String), UInt8)").contains("can't be a Map key"));
+        assert!(error("Decimal(80, 2)").contains("precision must be 1 to 76"));
+        assert!(error("Decimal(4, 5)").contains("scale at most"));
+        assert!(error("DateTime64(12)").contains("precision must be 0 to 9"));
+        assert!(error("Enum8('a' = 1, 'a' = 2)").contains("not unique"));
+        assert!(error("Enum8('a' = 300)").contains("outside -128..=127"));
+        assert!(error("Array(Nullable(LowCardinality(String)))").contains("write"));
+    }
+}